@@ -4,4 +4,5 @@ DROP TABLE IF EXISTS keyresults;
 DROP TABLE IF EXISTS initiatives;
 DROP TABLE IF EXISTS projects;
 DROP TABLE IF EXISTS objectives;
-DROP TABLE IF EXISTS themes;
\ No newline at end of file
+DROP TABLE IF EXISTS themes;
+DROP TYPE IF EXISTS status_type;
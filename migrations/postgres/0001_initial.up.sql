@@ -1,4 +1,6 @@
--- Add migration script here
+-- Schema the app had before migrations were tracked. Applied on boot, like
+-- every migration in this directory. Databases set up by hand back then are
+-- recorded as having it applied, and the later migrations upgrade them.
 CREATE TYPE status_type AS ENUM ('NotStarted', 'InProgress', 'Completed');
 CREATE TABLE IF NOT EXISTS themes (
     theme_id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
@@ -57,4 +59,4 @@ CREATE TABLE IF NOT EXISTS measurements (
     created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
     updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
     CONSTRAINT fk_keyresult_id FOREIGN KEY (keyresult_id) REFERENCES keyresults(keyresult_id) ON DELETE NO ACTION
-);
\ No newline at end of file
+);
@@ -0,0 +1,3 @@
+ALTER TABLE measurements DROP COLUMN IF EXISTS unit;
+ALTER TABLE measurements DROP COLUMN IF EXISTS observed_at;
+ALTER TABLE measurements DROP COLUMN IF EXISTS value;
@@ -0,0 +1,11 @@
+-- Measurements get a value, unit and observation time. Those whose title is a
+-- number take it as their value, the rest start at 0, and each was observed
+-- when it was created.
+ALTER TABLE measurements ADD COLUMN value DOUBLE PRECISION DEFAULT 0 NOT NULL;
+UPDATE measurements SET value = title::DOUBLE PRECISION
+WHERE title ~ '^\s*[-+]?[0-9]+(\.[0-9]+)?\s*$';
+-- New measurements must give their value
+ALTER TABLE measurements ALTER COLUMN value DROP DEFAULT;
+ALTER TABLE measurements ADD COLUMN observed_at TIMESTAMPTZ DEFAULT NOW() NOT NULL;
+UPDATE measurements SET observed_at = created_at;
+ALTER TABLE measurements ADD COLUMN unit VARCHAR(32) DEFAULT '' NOT NULL;
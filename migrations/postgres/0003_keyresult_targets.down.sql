@@ -0,0 +1,5 @@
+ALTER TABLE keyresults DROP COLUMN IF EXISTS direction;
+ALTER TABLE keyresults DROP COLUMN IF EXISTS unit;
+ALTER TABLE keyresults DROP COLUMN IF EXISTS target;
+ALTER TABLE keyresults DROP COLUMN IF EXISTS baseline;
+DROP TYPE IF EXISTS direction_type;
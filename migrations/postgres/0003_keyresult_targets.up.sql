@@ -0,0 +1,7 @@
+-- Key results get a baseline, target and direction. Existing ones go from 0
+-- up to 100.
+CREATE TYPE direction_type AS ENUM ('Increase', 'Decrease', 'Range');
+ALTER TABLE keyresults ADD COLUMN baseline DOUBLE PRECISION DEFAULT 0 NOT NULL;
+ALTER TABLE keyresults ADD COLUMN target DOUBLE PRECISION DEFAULT 100 NOT NULL;
+ALTER TABLE keyresults ADD COLUMN unit VARCHAR(32) DEFAULT '' NOT NULL;
+ALTER TABLE keyresults ADD COLUMN direction direction_type DEFAULT 'Increase' NOT NULL;
@@ -0,0 +1 @@
+ALTER TABLE keyresults DROP COLUMN IF EXISTS weight;
@@ -0,0 +1,3 @@
+-- Key results get a weight in their objective's score. Existing ones count
+-- equally.
+ALTER TABLE keyresults ADD COLUMN weight DOUBLE PRECISION DEFAULT 1 NOT NULL;
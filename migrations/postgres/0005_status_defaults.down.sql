@@ -0,0 +1,4 @@
+ALTER TABLE tasks ALTER COLUMN task_status DROP DEFAULT;
+ALTER TABLE projects ALTER COLUMN project_status DROP DEFAULT;
+ALTER TABLE initiatives ALTER COLUMN initiative_status DROP DEFAULT;
+ALTER TABLE themes ALTER COLUMN theme_status DROP DEFAULT;
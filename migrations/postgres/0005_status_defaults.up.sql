@@ -0,0 +1,5 @@
+-- New items start out Not Started when no status is given
+ALTER TABLE themes ALTER COLUMN theme_status SET DEFAULT 'NotStarted';
+ALTER TABLE initiatives ALTER COLUMN initiative_status SET DEFAULT 'NotStarted';
+ALTER TABLE projects ALTER COLUMN project_status SET DEFAULT 'NotStarted';
+ALTER TABLE tasks ALTER COLUMN task_status SET DEFAULT 'NotStarted';
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS measurement_revisions;
+ALTER TABLE measurements DROP COLUMN IF EXISTS source;
+ALTER TABLE measurements DROP COLUMN IF EXISTS notes;
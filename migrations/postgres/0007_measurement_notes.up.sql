@@ -0,0 +1,13 @@
+-- Measurements get notes, a source and a history of revised values, which
+-- starts with the first revision made after this is applied.
+ALTER TABLE measurements ADD COLUMN notes TEXT DEFAULT '' NOT NULL;
+ALTER TABLE measurements ADD COLUMN source VARCHAR(255) DEFAULT '' NOT NULL;
+CREATE TABLE measurement_revisions (
+    revision_id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    measurement_id INT NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    unit VARCHAR(32) NOT NULL,
+    observed_at TIMESTAMPTZ NOT NULL,
+    revised_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CONSTRAINT fk_measurement_id FOREIGN KEY (measurement_id) REFERENCES measurements(measurement_id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+ALTER TABLE projects DROP COLUMN IF EXISTS description;
+ALTER TABLE initiatives DROP COLUMN IF EXISTS description;
+ALTER TABLE keyresults DROP COLUMN IF EXISTS description;
+ALTER TABLE objectives DROP COLUMN IF EXISTS description;
+ALTER TABLE themes DROP COLUMN IF EXISTS description;
@@ -0,0 +1,6 @@
+-- Everything else gets a description too, after tasks
+ALTER TABLE themes ADD COLUMN description TEXT DEFAULT '' NOT NULL;
+ALTER TABLE objectives ADD COLUMN description TEXT DEFAULT '' NOT NULL;
+ALTER TABLE keyresults ADD COLUMN description TEXT DEFAULT '' NOT NULL;
+ALTER TABLE initiatives ADD COLUMN description TEXT DEFAULT '' NOT NULL;
+ALTER TABLE projects ADD COLUMN description TEXT DEFAULT '' NOT NULL;
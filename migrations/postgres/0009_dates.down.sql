@@ -0,0 +1,6 @@
+ALTER TABLE tasks DROP COLUMN IF EXISTS due_date;
+ALTER TABLE tasks DROP COLUMN IF EXISTS start_date;
+ALTER TABLE projects DROP COLUMN IF EXISTS due_date;
+ALTER TABLE projects DROP COLUMN IF EXISTS start_date;
+ALTER TABLE initiatives DROP COLUMN IF EXISTS due_date;
+ALTER TABLE initiatives DROP COLUMN IF EXISTS start_date;
@@ -0,0 +1,6 @@
+ALTER TABLE initiatives ADD COLUMN start_date DATE;
+ALTER TABLE initiatives ADD COLUMN due_date DATE;
+ALTER TABLE projects ADD COLUMN start_date DATE;
+ALTER TABLE projects ADD COLUMN due_date DATE;
+ALTER TABLE tasks ADD COLUMN start_date DATE;
+ALTER TABLE tasks ADD COLUMN due_date DATE;
@@ -0,0 +1,3 @@
+ALTER TABLE objectives DROP COLUMN IF EXISTS carried_from;
+ALTER TABLE objectives DROP COLUMN IF EXISTS cycle_id;
+DROP TABLE IF EXISTS cycles;
@@ -0,0 +1,15 @@
+-- Objectives belong to cycles, and remember the objective they were carried
+-- over from. Existing objectives belong to no cycle.
+CREATE TABLE cycles (
+    cycle_id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    title VARCHAR(255) NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CONSTRAINT chk_cycle_dates CHECK (end_date >= start_date)
+);
+ALTER TABLE objectives ADD COLUMN cycle_id INT;
+ALTER TABLE objectives ADD COLUMN carried_from INT;
+ALTER TABLE objectives ADD CONSTRAINT fk_cycle_id FOREIGN KEY (cycle_id) REFERENCES cycles(cycle_id) ON DELETE SET NULL;
+ALTER TABLE objectives ADD CONSTRAINT fk_carried_from FOREIGN KEY (carried_from) REFERENCES objectives(objective_id) ON DELETE SET NULL;
@@ -0,0 +1,5 @@
+-- Postgres can't drop enum values, so status_type keeps Blocked and Cancelled
+ALTER TABLE tasks DROP COLUMN IF EXISTS blocked_reason;
+ALTER TABLE projects DROP COLUMN IF EXISTS blocked_reason;
+ALTER TABLE initiatives DROP COLUMN IF EXISTS blocked_reason;
+ALTER TABLE themes DROP COLUMN IF EXISTS blocked_reason;
@@ -0,0 +1,8 @@
+-- The Blocked and Cancelled statuses, and why an item is blocked. The new
+-- values can't be used until this has committed.
+ALTER TYPE status_type ADD VALUE 'Blocked';
+ALTER TYPE status_type ADD VALUE 'Cancelled';
+ALTER TABLE themes ADD COLUMN blocked_reason TEXT DEFAULT '' NOT NULL;
+ALTER TABLE initiatives ADD COLUMN blocked_reason TEXT DEFAULT '' NOT NULL;
+ALTER TABLE projects ADD COLUMN blocked_reason TEXT DEFAULT '' NOT NULL;
+ALTER TABLE tasks ADD COLUMN blocked_reason TEXT DEFAULT '' NOT NULL;
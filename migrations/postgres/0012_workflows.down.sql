@@ -0,0 +1,66 @@
+-- States that aren't one of the fixed statuses fall back on their category
+CREATE TYPE status_type AS ENUM ('NotStarted', 'InProgress', 'Completed', 'Blocked', 'Cancelled');
+ALTER TABLE tasks ADD COLUMN task_status status_type DEFAULT 'NotStarted' NOT NULL;
+UPDATE tasks SET task_status = CASE workflow_states.title
+    WHEN 'In Progress' THEN 'InProgress'
+    WHEN 'Blocked' THEN 'Blocked'
+    WHEN 'Completed' THEN 'Completed'
+    WHEN 'Cancelled' THEN 'Cancelled'
+    ELSE CASE workflow_states.category
+        WHEN 'Active' THEN 'InProgress'
+        WHEN 'Done' THEN 'Completed'
+        ELSE 'NotStarted'
+    END
+END::status_type
+FROM workflow_states
+WHERE workflow_states.state_id = tasks.task_state_id;
+ALTER TABLE tasks DROP COLUMN task_state_id;
+ALTER TABLE projects ADD COLUMN project_status status_type DEFAULT 'NotStarted' NOT NULL;
+UPDATE projects SET project_status = CASE workflow_states.title
+    WHEN 'In Progress' THEN 'InProgress'
+    WHEN 'Blocked' THEN 'Blocked'
+    WHEN 'Completed' THEN 'Completed'
+    WHEN 'Cancelled' THEN 'Cancelled'
+    ELSE CASE workflow_states.category
+        WHEN 'Active' THEN 'InProgress'
+        WHEN 'Done' THEN 'Completed'
+        ELSE 'NotStarted'
+    END
+END::status_type
+FROM workflow_states
+WHERE workflow_states.state_id = projects.project_state_id;
+ALTER TABLE projects DROP COLUMN project_state_id;
+ALTER TABLE initiatives ADD COLUMN initiative_status status_type DEFAULT 'NotStarted' NOT NULL;
+UPDATE initiatives SET initiative_status = CASE workflow_states.title
+    WHEN 'In Progress' THEN 'InProgress'
+    WHEN 'Blocked' THEN 'Blocked'
+    WHEN 'Completed' THEN 'Completed'
+    WHEN 'Cancelled' THEN 'Cancelled'
+    ELSE CASE workflow_states.category
+        WHEN 'Active' THEN 'InProgress'
+        WHEN 'Done' THEN 'Completed'
+        ELSE 'NotStarted'
+    END
+END::status_type
+FROM workflow_states
+WHERE workflow_states.state_id = initiatives.initiative_state_id;
+ALTER TABLE initiatives DROP COLUMN initiative_state_id;
+ALTER TABLE themes ADD COLUMN theme_status status_type DEFAULT 'NotStarted' NOT NULL;
+UPDATE themes SET theme_status = CASE workflow_states.title
+    WHEN 'In Progress' THEN 'InProgress'
+    WHEN 'Blocked' THEN 'Blocked'
+    WHEN 'Completed' THEN 'Completed'
+    WHEN 'Cancelled' THEN 'Cancelled'
+    ELSE CASE workflow_states.category
+        WHEN 'Active' THEN 'InProgress'
+        WHEN 'Done' THEN 'Completed'
+        ELSE 'NotStarted'
+    END
+END::status_type
+FROM workflow_states
+WHERE workflow_states.state_id = themes.theme_state_id;
+ALTER TABLE themes DROP COLUMN theme_state_id;
+DROP TABLE IF EXISTS workflow_transitions;
+DROP TABLE IF EXISTS workflow_states;
+DROP TYPE IF EXISTS state_category;
+DROP TYPE IF EXISTS workflow_entity;
@@ -0,0 +1,98 @@
+-- Fixed statuses become workflows of states, one for each kind of item. Each
+-- item keeps its status as the workflow state of the same name.
+CREATE TYPE workflow_entity AS ENUM ('Theme', 'Initiative', 'Project', 'Task');
+CREATE TYPE state_category AS ENUM ('Open', 'Active', 'Done');
+CREATE TABLE workflow_states (
+    state_id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    entity workflow_entity NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    category state_category NOT NULL,
+    position INT DEFAULT 0 NOT NULL,
+    requires_reason BOOLEAN DEFAULT FALSE NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CONSTRAINT uq_state_title UNIQUE (entity, title)
+);
+CREATE TABLE workflow_transitions (
+    from_state_id INT NOT NULL,
+    to_state_id INT NOT NULL,
+    PRIMARY KEY (from_state_id, to_state_id),
+    CONSTRAINT fk_from_state_id FOREIGN KEY (from_state_id) REFERENCES workflow_states(state_id) ON DELETE CASCADE,
+    CONSTRAINT fk_to_state_id FOREIGN KEY (to_state_id) REFERENCES workflow_states(state_id) ON DELETE CASCADE
+);
+-- Every kind of item starts out with the same workflow, which admins can change
+INSERT INTO workflow_states (entity, title, category, position, requires_reason)
+SELECT entity, state.title, state.category, state.position, state.requires_reason
+FROM unnest(enum_range(NULL::workflow_entity)) AS entity
+CROSS JOIN (VALUES
+    ('Not Started', 'Open'::state_category, 0, FALSE),
+    ('In Progress', 'Active'::state_category, 1, FALSE),
+    ('Blocked', 'Active'::state_category, 2, TRUE),
+    ('Completed', 'Done'::state_category, 3, FALSE),
+    ('Cancelled', 'Done'::state_category, 4, FALSE)
+) AS state (title, category, position, requires_reason);
+INSERT INTO workflow_transitions (from_state_id, to_state_id)
+SELECT from_state.state_id, to_state.state_id
+FROM (VALUES
+    ('Not Started', 'In Progress'),
+    ('Not Started', 'Blocked'),
+    ('Not Started', 'Completed'),
+    ('Not Started', 'Cancelled'),
+    ('In Progress', 'Not Started'),
+    ('In Progress', 'Blocked'),
+    ('In Progress', 'Completed'),
+    ('In Progress', 'Cancelled'),
+    ('Blocked', 'Not Started'),
+    ('Blocked', 'In Progress'),
+    ('Blocked', 'Cancelled'),
+    ('Completed', 'In Progress'),
+    ('Cancelled', 'Not Started')
+) AS transition (from_title, to_title)
+JOIN workflow_states AS from_state ON from_state.title = transition.from_title
+JOIN workflow_states AS to_state
+    ON to_state.title = transition.to_title AND to_state.entity = from_state.entity;
+ALTER TABLE themes ADD COLUMN theme_state_id INT;
+UPDATE themes SET theme_state_id = workflow_states.state_id
+FROM workflow_states
+WHERE workflow_states.entity = 'Theme' AND workflow_states.title = CASE themes.theme_status
+    WHEN 'NotStarted' THEN 'Not Started'
+    WHEN 'InProgress' THEN 'In Progress'
+    ELSE themes.theme_status::TEXT
+END;
+ALTER TABLE themes ALTER COLUMN theme_state_id SET NOT NULL;
+ALTER TABLE themes ADD CONSTRAINT fk_theme_state_id FOREIGN KEY (theme_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION;
+ALTER TABLE themes DROP COLUMN theme_status;
+ALTER TABLE initiatives ADD COLUMN initiative_state_id INT;
+UPDATE initiatives SET initiative_state_id = workflow_states.state_id
+FROM workflow_states
+WHERE workflow_states.entity = 'Initiative' AND workflow_states.title = CASE initiatives.initiative_status
+    WHEN 'NotStarted' THEN 'Not Started'
+    WHEN 'InProgress' THEN 'In Progress'
+    ELSE initiatives.initiative_status::TEXT
+END;
+ALTER TABLE initiatives ALTER COLUMN initiative_state_id SET NOT NULL;
+ALTER TABLE initiatives ADD CONSTRAINT fk_initiative_state_id FOREIGN KEY (initiative_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION;
+ALTER TABLE initiatives DROP COLUMN initiative_status;
+ALTER TABLE projects ADD COLUMN project_state_id INT;
+UPDATE projects SET project_state_id = workflow_states.state_id
+FROM workflow_states
+WHERE workflow_states.entity = 'Project' AND workflow_states.title = CASE projects.project_status
+    WHEN 'NotStarted' THEN 'Not Started'
+    WHEN 'InProgress' THEN 'In Progress'
+    ELSE projects.project_status::TEXT
+END;
+ALTER TABLE projects ALTER COLUMN project_state_id SET NOT NULL;
+ALTER TABLE projects ADD CONSTRAINT fk_project_state_id FOREIGN KEY (project_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION;
+ALTER TABLE projects DROP COLUMN project_status;
+ALTER TABLE tasks ADD COLUMN task_state_id INT;
+UPDATE tasks SET task_state_id = workflow_states.state_id
+FROM workflow_states
+WHERE workflow_states.entity = 'Task' AND workflow_states.title = CASE tasks.task_status
+    WHEN 'NotStarted' THEN 'Not Started'
+    WHEN 'InProgress' THEN 'In Progress'
+    ELSE tasks.task_status::TEXT
+END;
+ALTER TABLE tasks ALTER COLUMN task_state_id SET NOT NULL;
+ALTER TABLE tasks ADD CONSTRAINT fk_task_state_id FOREIGN KEY (task_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION;
+ALTER TABLE tasks DROP COLUMN task_status;
+DROP TYPE status_type;
@@ -0,0 +1,22 @@
+-- A history of state changes, which starts with the first change made after
+-- this is applied
+CREATE TABLE state_changes (
+    change_id INT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    theme_id INT,
+    initiative_id INT,
+    project_id INT,
+    task_id INT,
+    from_state_id INT,
+    from_state VARCHAR(255) NOT NULL,
+    to_state_id INT,
+    to_state VARCHAR(255) NOT NULL,
+    changed_by VARCHAR(255) DEFAULT '' NOT NULL,
+    changed_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CONSTRAINT ck_state_change_item CHECK (num_nonnulls(theme_id, initiative_id, project_id, task_id) = 1),
+    CONSTRAINT fk_change_theme_id FOREIGN KEY (theme_id) REFERENCES themes(theme_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_initiative_id FOREIGN KEY (initiative_id) REFERENCES initiatives(initiative_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_project_id FOREIGN KEY (project_id) REFERENCES projects(project_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_task_id FOREIGN KEY (task_id) REFERENCES tasks(task_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_from_state_id FOREIGN KEY (from_state_id) REFERENCES workflow_states(state_id) ON DELETE SET NULL,
+    CONSTRAINT fk_change_to_state_id FOREIGN KEY (to_state_id) REFERENCES workflow_states(state_id) ON DELETE SET NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE tasks ADD COLUMN assignee VARCHAR(255) DEFAULT '' NOT NULL;
@@ -0,0 +1,12 @@
+DROP TABLE IF EXISTS state_changes;
+DROP TABLE IF EXISTS tasks;
+DROP TABLE IF EXISTS measurement_revisions;
+DROP TABLE IF EXISTS measurements;
+DROP TABLE IF EXISTS keyresults;
+DROP TABLE IF EXISTS initiatives;
+DROP TABLE IF EXISTS projects;
+DROP TABLE IF EXISTS objectives;
+DROP TABLE IF EXISTS cycles;
+DROP TABLE IF EXISTS themes;
+DROP TABLE IF EXISTS workflow_transitions;
+DROP TABLE IF EXISTS workflow_states;
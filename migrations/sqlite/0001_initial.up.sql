@@ -0,0 +1,193 @@
+-- SQLite version of migrations/postgres, as of 0014. SQLite came later, so its
+-- databases start out with the whole schema. Enums are checked text, timestamps
+-- are UTC text in RFC 3339 with milliseconds, so that they sort as they compare.
+CREATE TABLE IF NOT EXISTS workflow_states (
+    state_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    entity TEXT NOT NULL CHECK (entity IN ('Theme', 'Initiative', 'Project', 'Task')),
+    title VARCHAR(255) NOT NULL,
+    category TEXT NOT NULL CHECK (category IN ('Open', 'Active', 'Done')),
+    -- New items start in the first state of their workflow
+    position INT DEFAULT 0 NOT NULL,
+    requires_reason BOOLEAN DEFAULT FALSE NOT NULL,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT uq_state_title UNIQUE (entity, title)
+);
+CREATE TABLE IF NOT EXISTS workflow_transitions (
+    from_state_id INT NOT NULL,
+    to_state_id INT NOT NULL,
+    PRIMARY KEY (from_state_id, to_state_id),
+    CONSTRAINT fk_from_state_id FOREIGN KEY (from_state_id) REFERENCES workflow_states(state_id) ON DELETE CASCADE,
+    CONSTRAINT fk_to_state_id FOREIGN KEY (to_state_id) REFERENCES workflow_states(state_id) ON DELETE CASCADE
+);
+CREATE TABLE IF NOT EXISTS themes (
+    theme_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    description TEXT DEFAULT '' NOT NULL,
+    theme_state_id INT NOT NULL,
+    blocked_reason TEXT DEFAULT '' NOT NULL,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_theme_state_id FOREIGN KEY (theme_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION
+);
+CREATE TABLE IF NOT EXISTS cycles (
+    cycle_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT chk_cycle_dates CHECK (end_date >= start_date)
+);
+CREATE TABLE IF NOT EXISTS objectives (
+    objective_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    description TEXT DEFAULT '' NOT NULL,
+    theme_id INT,
+    cycle_id INT,
+    -- The objective this one was carried over from, in the previous cycle
+    carried_from INT,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_theme_id FOREIGN KEY (theme_id) REFERENCES themes(theme_id) ON DELETE NO ACTION,
+    CONSTRAINT fk_cycle_id FOREIGN KEY (cycle_id) REFERENCES cycles(cycle_id) ON DELETE SET NULL,
+    CONSTRAINT fk_carried_from FOREIGN KEY (carried_from) REFERENCES objectives(objective_id) ON DELETE SET NULL
+);
+CREATE TABLE IF NOT EXISTS keyresults (
+    keyresult_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    description TEXT DEFAULT '' NOT NULL,
+    objective_id INT,
+    baseline DOUBLE PRECISION DEFAULT 0 NOT NULL,
+    target DOUBLE PRECISION DEFAULT 100 NOT NULL,
+    unit VARCHAR(32) DEFAULT '' NOT NULL,
+    direction TEXT DEFAULT 'Increase' NOT NULL CHECK (direction IN ('Increase', 'Decrease', 'Range')),
+    weight DOUBLE PRECISION DEFAULT 1 NOT NULL,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_objective_id FOREIGN KEY (objective_id) REFERENCES objectives(objective_id) ON DELETE NO ACTION
+);
+CREATE TABLE IF NOT EXISTS initiatives (
+    initiative_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    description TEXT DEFAULT '' NOT NULL,
+    objective_id INT,
+    initiative_state_id INT NOT NULL,
+    blocked_reason TEXT DEFAULT '' NOT NULL,
+    start_date DATE,
+    due_date DATE,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_objective_id FOREIGN KEY (objective_id) REFERENCES objectives(objective_id) ON DELETE NO ACTION,
+    CONSTRAINT fk_initiative_state_id FOREIGN KEY (initiative_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION
+);
+CREATE TABLE IF NOT EXISTS projects (
+    project_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    description TEXT DEFAULT '' NOT NULL,
+    objective_id INT,
+    project_state_id INT NOT NULL,
+    blocked_reason TEXT DEFAULT '' NOT NULL,
+    start_date DATE,
+    due_date DATE,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_objective_id FOREIGN KEY (objective_id) REFERENCES objectives(objective_id) ON DELETE NO ACTION,
+    CONSTRAINT fk_project_state_id FOREIGN KEY (project_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION
+);
+CREATE TABLE IF NOT EXISTS tasks (
+    task_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    description TEXT DEFAULT '' NOT NULL,
+    project_id INT,
+    task_state_id INT NOT NULL,
+    blocked_reason TEXT DEFAULT '' NOT NULL,
+    assignee VARCHAR(255) DEFAULT '' NOT NULL,
+    start_date DATE,
+    due_date DATE,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_project_id FOREIGN KEY (project_id) REFERENCES projects(project_id) ON DELETE NO ACTION,
+    CONSTRAINT fk_task_state_id FOREIGN KEY (task_state_id) REFERENCES workflow_states(state_id) ON DELETE NO ACTION
+);
+CREATE TABLE IF NOT EXISTS measurements (
+    measurement_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    title VARCHAR(255) NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    unit VARCHAR(32) DEFAULT '' NOT NULL,
+    observed_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    keyresult_id INT,
+    notes TEXT DEFAULT '' NOT NULL,
+    source VARCHAR(255) DEFAULT '' NOT NULL,
+    created_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    updated_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_keyresult_id FOREIGN KEY (keyresult_id) REFERENCES keyresults(keyresult_id) ON DELETE NO ACTION
+);
+CREATE TABLE IF NOT EXISTS measurement_revisions (
+    revision_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    measurement_id INT NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    unit VARCHAR(32) NOT NULL,
+    observed_at DATETIME NOT NULL,
+    revised_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT fk_measurement_id FOREIGN KEY (measurement_id) REFERENCES measurements(measurement_id) ON DELETE CASCADE
+);
+CREATE TABLE IF NOT EXISTS state_changes (
+    change_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    theme_id INT,
+    initiative_id INT,
+    project_id INT,
+    task_id INT,
+    from_state_id INT,
+    from_state VARCHAR(255) NOT NULL,
+    to_state_id INT,
+    to_state VARCHAR(255) NOT NULL,
+    changed_by VARCHAR(255) DEFAULT '' NOT NULL,
+    changed_at DATETIME DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')) NOT NULL,
+    CONSTRAINT ck_state_change_item CHECK (
+        (theme_id IS NOT NULL) + (initiative_id IS NOT NULL) + (project_id IS NOT NULL) + (task_id IS NOT NULL) = 1
+    ),
+    CONSTRAINT fk_change_theme_id FOREIGN KEY (theme_id) REFERENCES themes(theme_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_initiative_id FOREIGN KEY (initiative_id) REFERENCES initiatives(initiative_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_project_id FOREIGN KEY (project_id) REFERENCES projects(project_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_task_id FOREIGN KEY (task_id) REFERENCES tasks(task_id) ON DELETE CASCADE,
+    CONSTRAINT fk_change_from_state_id FOREIGN KEY (from_state_id) REFERENCES workflow_states(state_id) ON DELETE SET NULL,
+    CONSTRAINT fk_change_to_state_id FOREIGN KEY (to_state_id) REFERENCES workflow_states(state_id) ON DELETE SET NULL
+);
+-- Every kind of item starts out with the same workflow, which admins can change
+WITH entity (name, entity_position) AS (
+    VALUES ('Theme', 0), ('Initiative', 1), ('Project', 2), ('Task', 3)
+), state (title, category, position, requires_reason) AS (
+    VALUES
+        ('Not Started', 'Open', 0, FALSE),
+        ('In Progress', 'Active', 1, FALSE),
+        ('Blocked', 'Active', 2, TRUE),
+        ('Completed', 'Done', 3, FALSE),
+        ('Cancelled', 'Done', 4, FALSE)
+)
+INSERT INTO workflow_states (entity, title, category, position, requires_reason)
+SELECT entity.name, state.title, state.category, state.position, state.requires_reason
+FROM entity CROSS JOIN state
+ORDER BY entity.entity_position, state.position;
+WITH transition (from_title, to_title) AS (
+    VALUES
+        ('Not Started', 'In Progress'),
+        ('Not Started', 'Blocked'),
+        ('Not Started', 'Completed'),
+        ('Not Started', 'Cancelled'),
+        ('In Progress', 'Not Started'),
+        ('In Progress', 'Blocked'),
+        ('In Progress', 'Completed'),
+        ('In Progress', 'Cancelled'),
+        ('Blocked', 'Not Started'),
+        ('Blocked', 'In Progress'),
+        ('Blocked', 'Cancelled'),
+        ('Completed', 'In Progress'),
+        ('Cancelled', 'Not Started')
+)
+INSERT INTO workflow_transitions (from_state_id, to_state_id)
+SELECT from_state.state_id, to_state.state_id
+FROM transition
+JOIN workflow_states AS from_state ON from_state.title = transition.from_title
+JOIN workflow_states AS to_state
+    ON to_state.title = transition.to_title AND to_state.entity = from_state.entity;
@@ -0,0 +1,169 @@
+use crate::model;
+use std::fmt::Write;
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 240.0;
+const PADDING_LEFT: f64 = 56.0;
+const PADDING_RIGHT: f64 = 72.0;
+const PADDING_Y: f64 = 24.0;
+
+// Maps measurement times and values onto the drawing area of the chart.
+struct Scale {
+    start: i64,
+    end: i64,
+    min: f64,
+    max: f64,
+}
+
+impl Scale {
+    fn x(&self, timestamp: i64) -> f64 {
+        let plot_width = WIDTH - PADDING_LEFT - PADDING_RIGHT;
+        if self.end == self.start {
+            return PADDING_LEFT + plot_width / 2.0;
+        }
+        PADDING_LEFT + (timestamp - self.start) as f64 / (self.end - self.start) as f64 * plot_width
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        let plot_height = HEIGHT - 2.0 * PADDING_Y;
+        HEIGHT - PADDING_Y - (value - self.min) / (self.max - self.min) * plot_height
+    }
+}
+
+/// Renders the measurements of a key result as an SVG line chart, with the
+/// baseline and target drawn as reference lines. Measurements are expected to
+/// be ordered by `observed_at`. Returns `None` when there is nothing to plot.
+pub fn measurements_svg(
+    keyresult: &model::KeyResult,
+    measurements: &[model::Measurement],
+) -> Option<String> {
+    let first = measurements.first()?;
+    let last = measurements.last()?;
+
+    let (min, max) = measurements
+        .iter()
+        .map(|measurement| measurement.value)
+        .chain([keyresult.baseline, keyresult.target])
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+    // Leave some headroom, and avoid dividing by zero when everything is equal
+    let margin = if max > min { (max - min) * 0.1 } else { 1.0 };
+    let scale = Scale {
+        start: first.observed_at.timestamp(),
+        end: last.observed_at.timestamp(),
+        min: min - margin,
+        max: max + margin,
+    };
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r#"<svg viewBox="0 0 {WIDTH} {HEIGHT}" width="100%" role="img" aria-label="Measurements of {title}" xmlns="http://www.w3.org/2000/svg">"#,
+        title = escape(&keyresult.title),
+    );
+    let _ = write!(
+        svg,
+        r##"<rect x="{PADDING_LEFT}" y="{PADDING_Y}" width="{w}" height="{h}" fill="#f8fafc" stroke="#cbd5e1"/>"##,
+        w = WIDTH - PADDING_LEFT - PADDING_RIGHT,
+        h = HEIGHT - 2.0 * PADDING_Y,
+    );
+
+    // Y axis extent labels
+    for value in [scale.min, scale.max] {
+        let _ = write!(
+            svg,
+            r##"<text x="{x}" y="{y:.1}" text-anchor="end" dominant-baseline="middle" font-size="11" fill="#475569">{value}</text>"##,
+            x = PADDING_LEFT - 6.0,
+            y = scale.y(value),
+            value = format_value(value),
+        );
+    }
+
+    // X axis date labels
+    let _ = write!(
+        svg,
+        r##"<text x="{PADDING_LEFT}" y="{y}" font-size="11" fill="#475569">{date}</text>"##,
+        y = HEIGHT - 6.0,
+        date = first.observed_at.format("%Y-%m-%d"),
+    );
+    if scale.end != scale.start {
+        let _ = write!(
+            svg,
+            r##"<text x="{x}" y="{y}" text-anchor="end" font-size="11" fill="#475569">{date}</text>"##,
+            x = WIDTH - PADDING_RIGHT,
+            y = HEIGHT - 6.0,
+            date = last.observed_at.format("%Y-%m-%d"),
+        );
+    }
+
+    // Baseline and target reference lines, or the bounds of a range to stay in
+    let (baseline, target) = (keyresult.baseline, keyresult.target);
+    let references = match keyresult.direction {
+        model::Direction::Range => [
+            ("Lower bound", baseline.min(target), "#65a30d"),
+            ("Upper bound", baseline.max(target), "#65a30d"),
+        ],
+        _ => [
+            ("Baseline", baseline, "#64748b"),
+            ("Target", target, "#65a30d"),
+        ],
+    };
+    for (label, value, colour) in references {
+        let y = scale.y(value);
+        let _ = write!(
+            svg,
+            r#"<line x1="{PADDING_LEFT}" y1="{y:.1}" x2="{x2}" y2="{y:.1}" stroke="{colour}" stroke-width="1.5" stroke-dasharray="6 4"/>"#,
+            x2 = WIDTH - PADDING_RIGHT,
+        );
+        let _ = write!(
+            svg,
+            r#"<text x="{x}" y="{y:.1}" dominant-baseline="middle" font-size="11" fill="{colour}">{label}</text>"#,
+            x = WIDTH - PADDING_RIGHT + 6.0,
+        );
+    }
+
+    // Measurement line and points
+    let points: Vec<String> = measurements
+        .iter()
+        .map(|measurement| {
+            format!(
+                "{:.1},{:.1}",
+                scale.x(measurement.observed_at.timestamp()),
+                scale.y(measurement.value)
+            )
+        })
+        .collect();
+    let _ = write!(
+        svg,
+        r##"<polyline points="{points}" fill="none" stroke="#0284c7" stroke-width="2"/>"##,
+        points = points.join(" "),
+    );
+    for measurement in measurements {
+        let _ = write!(
+            svg,
+            r##"<circle cx="{x:.1}" cy="{y:.1}" r="3.5" fill="#0284c7"><title>{title}: {value} {unit} ({date})</title></circle>"##,
+            x = scale.x(measurement.observed_at.timestamp()),
+            y = scale.y(measurement.value),
+            title = escape(&measurement.title),
+            value = format_value(measurement.value),
+            unit = escape(&measurement.unit),
+            date = measurement.observed_at.format("%Y-%m-%d %H:%M"),
+        );
+    }
+
+    svg.push_str("</svg>");
+    Some(svg)
+}
+
+fn format_value(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    format!("{rounded}")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,117 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{Parser, Subcommand};
+use tracing_subscriber::filter::Targets;
+
+// Request logs from tower_http, without sqlx repeating that its migrations
+// table already exists
+const DEFAULT_LOG: &str = "info,tower_http::trace::on_response=trace,\
+    tower_http::trace::on_request=trace,tower_http::trace::make_span=debug,\
+    sqlx::postgres::notice=warn";
+
+/// Settings, each taken from the first of: its command line flag, its
+/// environment variable, or the config file, a list of KEY=value lines.
+#[derive(Debug, Parser)]
+#[clap(version, about = "Objectives, key results and the work towards them")]
+pub struct Config {
+    /// Config file setting environment variables that are not already set
+    /// [default: .env, if there is one]
+    #[clap(long, env = "TOKIP_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Postgres URL, or sqlite: URL of a database file
+    #[clap(long, env = "DATABASE_URL", hide_env_values = true)]
+    pub database_url: Option<String>,
+
+    /// Most database connections to keep open at once
+    #[clap(long, env = "TOKIP_MAX_CONNECTIONS", default_value_t = 50)]
+    pub max_connections: u32,
+
+    /// Seconds to wait for a free database connection
+    #[clap(long, env = "TOKIP_ACQUIRE_TIMEOUT", default_value_t = 30)]
+    pub acquire_timeout: u64,
+
+    /// Address to serve on
+    #[clap(long, env = "TOKIP_LISTEN", default_value = "0.0.0.0:3000")]
+    pub listen: SocketAddr,
+
+    /// Log level, optionally followed by levels for specific targets, e.g.
+    /// "warn,tokip=debug"
+    #[clap(long, env = "TOKIP_LOG", default_value = DEFAULT_LOG)]
+    pub log: Targets,
+
+    /// Directory of the files served under /static
+    #[clap(long, env = "TOKIP_STATIC_DIR", default_value = "static")]
+    pub static_dir: PathBuf,
+
+    /// Keep everything in memory instead of a database
+    #[clap(long, env = "TOKIP_MEMORY")]
+    pub memory: bool,
+
+    /// Keep everything in memory, starting from this JSON fixture
+    #[clap(long, env = "TOKIP_FIXTURE")]
+    pub fixture: Option<PathBuf>,
+
+    /// Refuse to start with pending migrations instead of applying them
+    #[clap(long, env = "TOKIP_NO_MIGRATE")]
+    pub no_migrate: bool,
+
+    /// Record who changed an item's state from the X-Forwarded-User header.
+    /// Only set this behind an authenticating proxy that always sets the
+    /// header itself, since otherwise any client can name anyone
+    #[clap(long, env = "TOKIP_TRUST_FORWARDED_USER")]
+    pub trust_forwarded_user: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List the database's migrations and whether they have been applied
+    MigrationStatus,
+}
+
+impl Config {
+    /// Reads the command line, after the config file it names has set the
+    /// environment. Exits with usage on a bad command line.
+    pub fn load() -> Result<Config, String> {
+        let config = Config::parse();
+        let loaded = match &config.config {
+            Some(path) => dotenvy::from_path(path).map_err(|err| (path.as_path(), err)),
+            // Without a config file, the environment and flags are enough
+            None => match dotenvy::from_path(".env") {
+                Err(dotenvy::Error::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => {
+                    return Ok(config)
+                }
+                loaded => loaded.map_err(|err| (Path::new(".env"), err)),
+            },
+        };
+        loaded.map_err(|(path, err)| {
+            format!("Could not read config file {}: {err}", path.display())
+        })?;
+        Ok(Config::parse())
+    }
+
+    pub fn acquire_timeout(&self) -> Duration {
+        Duration::from_secs(self.acquire_timeout)
+    }
+
+    /// The database to use, unless everything is kept in memory.
+    pub fn database_url(&self) -> Result<&str, String> {
+        self.database_url.as_deref().ok_or_else(|| {
+            format!(
+                "No database to use. Set DATABASE_URL in {} or the environment, \
+                 pass --database-url, or pass --memory to keep everything in memory",
+                self.config
+                    .as_deref()
+                    .unwrap_or(Path::new(".env"))
+                    .display()
+            )
+        })
+    }
+}
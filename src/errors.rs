@@ -1,7 +1,17 @@
-use axum::http::StatusCode;
+use axum::{
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use sqlx::error::ErrorKind;
 
+use crate::{templater, validation::FieldErrors};
+
+#[derive(Debug)]
 pub enum CustomError {
     BadRequest,
+    NotFound,
     ThemeNotFound,
     ObjectiveNotFound,
     KeyResultNotFound,
@@ -9,16 +19,29 @@ pub enum CustomError {
     ProjectNotFound,
     TaskNotFound,
     MeasurementNotFound,
+    CycleNotFound,
+    WorkflowStateNotFound,
+    // Rejected payload, with a message for each offending field
+    Validation(FieldErrors),
+    // Delete refused because other items still refer to the one deleted,
+    // with a message naming them
+    InUse(&'static str),
     InternalServerError,
 }
 
 impl CustomError {
     pub fn get_error_message(self) -> (StatusCode, String) {
         let (status, error_message) = match self {
+            Self::Validation(fields) => {
+                let messages: Vec<&str> = fields.messages().map(String::as_str).collect();
+                return (StatusCode::UNPROCESSABLE_ENTITY, messages.join(". "));
+            }
             Self::InternalServerError => {
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
             }
+            Self::InUse(message) => (StatusCode::CONFLICT, message),
             Self::BadRequest => (StatusCode::BAD_REQUEST, "Bad Request"),
+            Self::NotFound => (StatusCode::NOT_FOUND, "Not Found"),
             Self::ThemeNotFound => (StatusCode::NOT_FOUND, "Theme Not Found"),
             Self::ObjectiveNotFound => (StatusCode::NOT_FOUND, "Objective Not Found"),
             Self::KeyResultNotFound => (StatusCode::NOT_FOUND, "Key Result Not Found"),
@@ -26,46 +49,167 @@ impl CustomError {
             Self::ProjectNotFound => (StatusCode::NOT_FOUND, "Project Not Found"),
             Self::TaskNotFound => (StatusCode::NOT_FOUND, "Task Not Found"),
             Self::MeasurementNotFound => (StatusCode::NOT_FOUND, "Measurement Not Found"),
+            Self::CycleNotFound => (StatusCode::NOT_FOUND, "Cycle Not Found"),
+            Self::WorkflowStateNotFound => (StatusCode::NOT_FOUND, "Workflow State Not Found"),
         };
         (status, error_message.to_owned())
     }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            Self::NotFound
+                | Self::ThemeNotFound
+                | Self::ObjectiveNotFound
+                | Self::KeyResultNotFound
+                | Self::InitiativeNotFound
+                | Self::ProjectNotFound
+                | Self::TaskNotFound
+                | Self::MeasurementNotFound
+                | Self::CycleNotFound
+                | Self::WorkflowStateNotFound
+        )
+    }
+
+    // Maps a failed query onto an error, using `not_found` when the row is
+    // missing. Integrity violations and bad data (SQLSTATE classes 23 and 22
+    // on Postgres) are the client's fault; anything else is ours.
+    pub fn from_sqlx(error: sqlx::Error, not_found: CustomError) -> CustomError {
+        match error {
+            sqlx::Error::RowNotFound => not_found,
+            sqlx::Error::Database(err)
+                if !matches!(err.kind(), ErrorKind::Other)
+                    || err
+                        .code()
+                        .is_some_and(|code| code.starts_with("23") || code.starts_with("22")) =>
+            {
+                CustomError::BadRequest
+            }
+            err => {
+                tracing::error!("database error: {err}");
+                CustomError::InternalServerError
+            }
+        }
+    }
+
+    // Like `from_sqlx`, for deletes. A foreign key violation means other rows
+    // still refer to the deleted one, which `in_use` explains when given.
+    pub fn from_sqlx_delete(
+        error: sqlx::Error,
+        not_found: CustomError,
+        in_use: Option<&'static str>,
+    ) -> CustomError {
+        match (error, in_use) {
+            (sqlx::Error::Database(err), Some(in_use))
+                if matches!(err.kind(), ErrorKind::ForeignKeyViolation) =>
+            {
+                CustomError::InUse(in_use)
+            }
+            (error, _) => CustomError::from_sqlx(error, not_found),
+        }
+    }
+}
+
+impl From<sqlx::Error> for CustomError {
+    fn from(error: sqlx::Error) -> Self {
+        CustomError::from_sqlx(error, CustomError::NotFound)
+    }
+}
+
+// Marks a response as an error, so that `negotiate` can re-render it as HTML
+#[derive(Clone)]
+struct ErrorMessage(String, Option<FieldErrors>);
+
+impl IntoResponse for CustomError {
+    fn into_response(self) -> Response {
+        let fields = match &self {
+            Self::Validation(fields) => Some(fields.clone()),
+            _ => None,
+        };
+        let (status, error_message) = self.get_error_message();
+        let body = match &fields {
+            Some(fields) => json!({ "error": error_message, "fields": fields }),
+            None => json!({ "error": error_message }),
+        };
+        let mut response = (status, Json(body)).into_response();
+        response
+            .extensions_mut()
+            .insert(ErrorMessage(error_message, fields));
+        response
+    }
 }
 
-// impl IntoResponse for CustomError {
-//     fn into_response(self) -> axum::response::Response {
-//         let (status, error_message) = match self {
-//             Self::InternalServerError => {
-//                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
-//             }
-//             Self::BadRequest => (StatusCode::BAD_REQUEST, "Bad Request"),
-//             Self::ThemeNotFound => (StatusCode::NOT_FOUND, "Theme Not Found"),
-//             Self::ObjectiveNotFound => (StatusCode::NOT_FOUND, "Objective Not Found"),
-//             Self::KeyResultNotFound => (StatusCode::NOT_FOUND, "Key Result Not Found"),
-//             Self::InitiativeNotFound => (StatusCode::NOT_FOUND, "Initiative Not Found"),
-//             Self::ProjectNotFound => (StatusCode::NOT_FOUND, "Project Not Found"),
-//             Self::TaskNotFound => (StatusCode::NOT_FOUND, "Task Not Found"),
-//             Self::MeasurementNotFound => (StatusCode::NOT_FOUND, "Measurement Not Found"),
-//         };
-//         let template = templater::ErrorTemplate::new(status, error_message.to_owned());
-//         templater::HtmlTemplate(template).into_response()
-//     }
-// }
+// Errors are JSON by default. Browsers and htmx get the error page instead,
+// unless they explicitly ask for JSON.
+fn wants_html(headers: &HeaderMap, path: &str) -> bool {
+    let accept = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .unwrap_or_default();
+    if accept.contains("application/json") {
+        false
+    } else if accept.contains("text/html") || headers.contains_key("HX-Request") {
+        true
+    } else {
+        !path.starts_with("/api")
+    }
+}
 
-// impl IntoResponse for CustomError {
-//     fn into_response(self) -> axum::response::Response {
-//         let (status, error_message) = match self {
-//             Self::InternalServerError => {
-//                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
-//             }
-//             Self::BadRequest => (StatusCode::BAD_REQUEST, "Bad Request"),
-//             Self::ThemeNotFound => (StatusCode::NOT_FOUND, "Theme Not Found"),
-//             Self::ObjectiveNotFound => (StatusCode::NOT_FOUND, "Objective Not Found"),
-//             Self::KeyResultNotFound => (StatusCode::NOT_FOUND, "Key Result Not Found"),
-//             Self::InitiativeNotFound => (StatusCode::NOT_FOUND, "Initiative Not Found"),
-//             Self::ProjectNotFound => (StatusCode::NOT_FOUND, "Project Not Found"),
-//             Self::TaskNotFound => (StatusCode::NOT_FOUND, "Task Not Found"),
-//             Self::MeasurementNotFound => (StatusCode::NOT_FOUND, "Measurement Not Found"),
-//         };
-//         (status, Json(json!({ "error": error_message }))).into_response()
-//     }
-// }
+// htmx requests swapping part of a page, as opposed to boosted navigation
+fn is_htmx_fragment(headers: &HeaderMap) -> bool {
+    headers.contains_key("HX-Request") && !headers.contains_key("HX-Boosted")
+}
+
+// Middleware choosing how `CustomError`s are rendered, based on the request.
+// Errors from htmx fragment requests become an alert retargeted at the
+// `#messages` area of the page, so a failed write is never silently dropped.
+pub async fn negotiate<B>(request: Request<B>, next: Next<B>) -> Response {
+    let wants_html = wants_html(request.headers(), request.uri().path());
+    let htmx_fragment = wants_html && is_htmx_fragment(request.headers());
+    let mut response = next.run(request).await;
+
+    let status = response.status();
+    let (error_message, fields) = match response.extensions_mut().remove::<ErrorMessage>() {
+        Some(ErrorMessage(error_message, fields)) => (error_message, fields),
+        // Extractor rejections, e.g. malformed form data, bypass `CustomError`
+        None if htmx_fragment && (status.is_client_error() || status.is_server_error()) => {
+            let reason = status.canonical_reason().unwrap_or("Request Failed");
+            (reason.to_owned(), None)
+        }
+        None => return response,
+    };
+
+    if htmx_fragment {
+        let template = templater::ErrorAlertTemplate::new(error_message);
+        let mut response = (
+            status,
+            [("HX-Retarget", "#messages"), ("HX-Reswap", "innerHTML")],
+            templater::HtmlTemplate(template),
+        )
+            .into_response();
+        // Lets the page show each message next to its input, see base.html
+        if let Some(trigger) = fields.and_then(|fields| field_errors_trigger(&fields)) {
+            response.headers_mut().insert("HX-Trigger", trigger);
+        }
+        response
+    } else if wants_html {
+        let template = templater::ErrorTemplate::new(status, error_message);
+        (status, templater::HtmlTemplate(template)).into_response()
+    } else {
+        response
+    }
+}
+
+// `HX-Trigger` header raising a `fieldErrors` event on the element that made
+// the request. Header values must be ASCII, so anything else is escaped.
+fn field_errors_trigger(fields: &FieldErrors) -> Option<HeaderValue> {
+    let json = json!({ "fieldErrors": fields }).to_string();
+    let ascii: String = json
+        .encode_utf16()
+        .map(|unit| match char::from_u32(unit.into()) {
+            Some(c) if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+            _ => format!("\\u{unit:04x}"),
+        })
+        .collect();
+    HeaderValue::from_str(&ascii).ok()
+}
@@ -1,3 +1,221 @@
+use crate::{
+    errors::CustomError,
+    model,
+    store::{CarriedObjective, Store},
+    validation::{self, FieldErrors},
+};
+
 pub mod assets;
 pub mod data;
-pub mod hypermedia;
\ No newline at end of file
+pub mod hypermedia;
+
+// Every item of a listing, in the given order
+pub fn all(sort: &str) -> model::ListQuery {
+    model::ListQuery {
+        sort: Some(sort.to_owned()),
+        ..Default::default()
+    }
+}
+
+// Every child of any of the given parents, in the given order
+pub fn children_of(parent_ids: Vec<i32>, sort: &str) -> model::ListQuery {
+    model::ListQuery {
+        parent_ids: Some(parent_ids),
+        ..all(sort)
+    }
+}
+
+/// Every cycle, earliest first.
+pub async fn all_cycles(store: &dyn Store) -> Result<Vec<model::Cycle>, CustomError> {
+    Ok(store.cycles(&all("start_date")).await?.items)
+}
+
+/// Looks up the cycle a filter refers to, `None` meaning all cycles.
+pub async fn resolve_cycle(
+    store: &dyn Store,
+    filter: model::CycleFilter,
+) -> Result<Option<model::Cycle>, CustomError> {
+    let today = model::today();
+    let cycle = match filter {
+        model::CycleFilter::All => return Ok(None),
+        model::CycleFilter::Current => current_cycle(store).await?,
+        // The most recent cycle that has already ended
+        model::CycleFilter::Previous => all_cycles(store)
+            .await?
+            .into_iter()
+            .filter(|cycle| cycle.end_date < today)
+            .max_by_key(|cycle| cycle.end_date),
+        model::CycleFilter::Id(cycle_id) => Some(store.cycle(cycle_id).await?),
+    };
+    cycle.map(Some).ok_or(CustomError::CycleNotFound)
+}
+
+/// The cycle today falls in, the latest starting one if cycles overlap.
+pub async fn current_cycle(store: &dyn Store) -> Result<Option<model::Cycle>, CustomError> {
+    Ok(all_cycles(store)
+        .await?
+        .into_iter()
+        .filter(model::Cycle::is_current)
+        .max_by_key(|cycle| cycle.start_date))
+}
+
+/// Fills in each objective's score from the progress of its key results.
+pub async fn score_objectives(
+    store: &dyn Store,
+    objectives: &mut [model::Objective],
+) -> Result<(), CustomError> {
+    let objective_ids = objectives.iter().map(|obj| obj.objective_id).collect();
+    let keyresults = store
+        .keyresults(&children_of(objective_ids, "keyresult_id"))
+        .await?
+        .items;
+
+    for objective in objectives.iter_mut() {
+        objective.score = model::objective_score(
+            keyresults
+                .iter()
+                .filter(|kr| kr.objective_id == objective.objective_id),
+        );
+    }
+    Ok(())
+}
+
+/// Fills in each theme's score from the scores of its objectives, only counting
+/// objectives in the given cycle when there is one.
+pub async fn score_themes(
+    store: &dyn Store,
+    themes: &mut [model::Theme],
+    cycle_id: Option<i32>,
+) -> Result<(), CustomError> {
+    let theme_ids = themes.iter().map(|theme| theme.theme_id).collect();
+    let mut objectives = store
+        .objectives(&model::ListQuery {
+            cycle_id,
+            ..children_of(theme_ids, "objective_id")
+        })
+        .await?
+        .items;
+    score_objectives(store, &mut objectives).await?;
+
+    for theme in themes.iter_mut() {
+        theme.score = model::theme_score(
+            objectives
+                .iter()
+                .filter(|obj| obj.theme_id == theme.theme_id),
+        );
+    }
+    Ok(())
+}
+
+/// Adds an objective, in the current cycle unless one is given.
+pub async fn add_objective(
+    store: &dyn Store,
+    mut create_objective: model::CreateObjective,
+) -> Result<model::Objective, CustomError> {
+    validation::create_objective(store, &mut create_objective).await?;
+    if create_objective.cycle_id.is_none() {
+        create_objective.cycle_id = current_cycle(store).await?.map(|cycle| cycle.cycle_id);
+    }
+    store.add_objective(create_objective).await
+}
+
+/// Copies the unfinished objectives of a cycle into the cycle that follows it,
+/// along with their unfinished key results. Objectives that were already
+/// carried over are skipped, so running it twice copies nothing new.
+pub async fn carry_over(store: &dyn Store, cycle_id: i32) -> Result<model::CarryOver, CustomError> {
+    let from_cycle = store.cycle(cycle_id).await?;
+    let to_cycle = all_cycles(store)
+        .await?
+        .into_iter()
+        .find(|cycle| cycle.start_date > from_cycle.start_date)
+        .ok_or_else(|| {
+            let mut errors = FieldErrors::default();
+            errors.add(
+                "cycle_id",
+                format!(
+                    "There is no cycle after {} to carry over into",
+                    from_cycle.title
+                ),
+            );
+            CustomError::Validation(errors)
+        })?;
+
+    let in_cycle = |cycle_id| model::ListQuery {
+        cycle_id: Some(cycle_id),
+        ..all("objective_id")
+    };
+    let copied: Vec<i32> = store
+        .objectives(&in_cycle(to_cycle.cycle_id))
+        .await?
+        .items
+        .into_iter()
+        .filter_map(|copy| copy.carried_from)
+        .collect();
+    let objectives: Vec<model::Objective> = store
+        .objectives(&in_cycle(from_cycle.cycle_id))
+        .await?
+        .items
+        .into_iter()
+        .filter(|objective| !copied.contains(&objective.objective_id))
+        .collect();
+    let objective_ids = objectives.iter().map(|obj| obj.objective_id).collect();
+    let keyresults = store
+        .keyresults(&children_of(objective_ids, "keyresult_id"))
+        .await?
+        .items;
+
+    let mut carried = Vec::new();
+    for objective in objectives {
+        let keyresults: Vec<&model::KeyResult> = keyresults
+            .iter()
+            .filter(|kr| kr.objective_id == objective.objective_id)
+            .collect();
+        if model::objective_score(keyresults.iter().copied()).unwrap_or(0.0) >= 100.0 {
+            continue;
+        }
+        let keyresults = keyresults
+            .into_iter()
+            .filter(|kr| kr.progress().unwrap_or(0.0) < 100.0)
+            .cloned()
+            .collect();
+        carried.push(CarriedObjective {
+            objective,
+            keyresults,
+        });
+    }
+    let objective_count = carried.len();
+    let keyresult_count = carried.iter().map(|carried| carried.keyresults.len()).sum();
+    store.carry_over(to_cycle.cycle_id, carried).await?;
+
+    Ok(model::CarryOver {
+        from_cycle,
+        to_cycle,
+        objectives: objective_count,
+        keyresults: keyresult_count,
+    })
+}
+
+/// Adds a state at the end of its workflow. Items may move between it and every
+/// other state of the workflow, until an admin narrows that down.
+pub async fn add_workflow_state(
+    store: &dyn Store,
+    mut create_state: model::CreateWorkflowState,
+) -> Result<model::WorkflowState, CustomError> {
+    validation::create_workflow_state(store, &mut create_state).await?;
+    store.add_workflow_state(create_state).await
+}
+
+/// Updates a workflow state, replacing the states it leads to when given.
+pub async fn save_workflow_state(
+    store: &dyn Store,
+    state_id: i32,
+    mut update_state: model::UpdateWorkflowState,
+) -> Result<model::WorkflowState, CustomError> {
+    validation::update_workflow_state(store, state_id, &mut update_state).await?;
+    store.save_workflow_state(state_id, update_state).await
+}
+
+pub async fn remove_workflow_state(store: &dyn Store, state_id: i32) -> Result<(), CustomError> {
+    validation::remove_workflow_state(store, state_id).await?;
+    store.remove_workflow_state(state_id).await
+}
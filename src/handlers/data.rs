@@ -1,89 +1,907 @@
 use askama_axum::IntoResponse;
-use axum::{http::StatusCode, Extension, Json};
-use sqlx::PgPool;
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    Extension, Json,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 
-use crate::model;
+use super::{children_of, score_objectives, score_themes};
+use crate::{
+    errors::CustomError,
+    model,
+    store::{DynStore, Page, Store},
+    validation::{self, ChangedBy, Params, Path, Payload},
+};
 
-// GET /api/theme
-pub async fn get_all_themes(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * from themes".to_string();
+// 201 Created, pointing at the new resource
+fn created<T: Serialize>(location: String, body: T) -> impl IntoResponse {
+    (
+        StatusCode::CREATED,
+        [(header::LOCATION, location)],
+        Json(body),
+    )
+}
+
+// PUT bodies replace the resource, except that optional fields left out keep
+// their current values: a description, assignee, blocked reason or date is only
+// changed when given. A null or empty date clears it.
+//
+// PATCH bodies are merged over the current values of the resource, then
+// validated as a full update.
+fn apply_patch<T: Serialize + DeserializeOwned>(
+    current: T,
+    patch: Value,
+) -> Result<T, CustomError> {
+    let mut merged = serde_json::to_value(current).map_err(|_| CustomError::InternalServerError)?;
+    match (merged.as_object_mut(), patch) {
+        (Some(fields), Value::Object(patch)) => fields.extend(patch),
+        _ => return Err(CustomError::BadRequest),
+    }
+    validation::from_value(merged)
+}
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 1000;
+
+// The list endpoints always page, unlike the store
+fn paged(mut query: model::ListQuery) -> model::ListQuery {
+    query.limit = Some(
+        query
+            .limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE),
+    );
+    query
+}
+
+// Responds with a page of items, reporting the unpaged total in the
+// X-Total-Count header
+fn page<T>(page: Page<T>) -> (HeaderMap, Json<Vec<T>>) {
+    let mut headers = HeaderMap::new();
+    headers.insert("X-Total-Count", HeaderValue::from(page.total));
+    (headers, Json(page.items))
+}
+
+// GET /api/cycle
+pub async fn get_all_cycles(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::Cycle>>), CustomError> {
+    store.cycles(&paged(query)).await.map(page)
+}
+
+// GET /api/workflow
+pub async fn get_workflows(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::WorkflowQuery>,
+) -> Result<Json<Vec<model::WorkflowState>>, CustomError> {
+    store.workflow_states(query.entity).await.map(Json)
+}
+
+// GET /api/workflow/state/:state_id
+pub async fn get_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Path(state_id): Path<i32>,
+) -> Result<Json<model::WorkflowState>, CustomError> {
+    store.workflow_state(state_id).await.map(Json)
+}
 
-    let themes = sqlx::query_as::<_, model::Theme>(&sql)
-        .fetch_all(&pool)
+// POST /api/workflow/state
+pub async fn add_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Payload(create_state): Payload<model::CreateWorkflowState>,
+) -> Result<impl IntoResponse, CustomError> {
+    let state = super::add_workflow_state(&*store, create_state).await?;
+    Ok(created(
+        format!("/api/workflow/state/{}", state.state_id),
+        state,
+    ))
+}
+
+// PUT /api/workflow/state/:state_id
+pub async fn update_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Path(state_id): Path<i32>,
+    Payload(update_state): Payload<model::UpdateWorkflowState>,
+) -> Result<Json<model::WorkflowState>, CustomError> {
+    super::save_workflow_state(&*store, state_id, update_state)
         .await
-        .unwrap();
+        .map(Json)
+}
 
-    (StatusCode::OK, Json(themes))
+// PATCH /api/workflow/state/:state_id
+pub async fn patch_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Path(state_id): Path<i32>,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::WorkflowState>, CustomError> {
+    let state = store.workflow_state(state_id).await?;
+    let update_state = apply_patch(model::UpdateWorkflowState::from(state), patch)?;
+    super::save_workflow_state(&*store, state_id, update_state)
+        .await
+        .map(Json)
+}
+
+// DELETE /api/workflow/state/:state_id
+pub async fn remove_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Path(state_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    super::remove_workflow_state(&*store, state_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// GET /api/theme
+pub async fn get_all_themes(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::Theme>>), CustomError> {
+    let mut themes = store.themes(&paged(query)).await?;
+    score_themes(&*store, &mut themes.items, None).await?;
+    Ok(page(themes))
 }
 
 // GET /api/objective
-pub async fn get_all_objectives(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * from objectives".to_string();
+pub async fn get_all_objectives(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::Objective>>), CustomError> {
+    let mut objectives = store.objectives(&paged(query)).await?;
+    score_objectives(&*store, &mut objectives.items).await?;
+    Ok(page(objectives))
+}
+
+// GET /api/keyresult
+pub async fn get_all_keyresults(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::KeyResult>>), CustomError> {
+    store.keyresults(&paged(query)).await.map(page)
+}
+
+// GET /api/initiative
+pub async fn get_all_initiatives(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::Initiative>>), CustomError> {
+    store.initiatives(&paged(query)).await.map(page)
+}
+
+// GET /api/project
+pub async fn get_all_projects(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::Project>>), CustomError> {
+    store.projects(&paged(query)).await.map(page)
+}
+
+// GET /api/task
+pub async fn get_all_tasks(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::Task>>), CustomError> {
+    store.tasks(&paged(query)).await.map(page)
+}
+
+// GET /api/measure
+pub async fn get_all_measures(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::ListQuery>,
+) -> Result<(HeaderMap, Json<Vec<model::Measurement>>), CustomError> {
+    store.measurements(&paged(query)).await.map(page)
+}
+
+// GET /api/tree
+pub async fn get_tree(
+    Extension(store): Extension<DynStore>,
+) -> Result<Json<Vec<model::ThemeTree>>, CustomError> {
+    let themes = store.themes(&super::all("theme_id")).await?.items;
+    build_theme_trees(&*store, themes).await.map(Json)
+}
+
+// GET /api/theme/:theme_id/tree
+pub async fn get_theme_tree(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+) -> Result<Json<model::ThemeTree>, CustomError> {
+    let theme = store.theme(theme_id).await?;
+    let mut trees = build_theme_trees(&*store, vec![theme]).await?;
+    trees.pop().map(Json).ok_or(CustomError::ThemeNotFound)
+}
+
+// GET /api/objective/:objective_id/tree
+pub async fn get_objective_tree(
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<Json<model::ObjectiveTree>, CustomError> {
+    let objective = store.objective(objective_id).await?;
+    let mut trees = build_objective_trees(&*store, vec![objective]).await?;
+    trees.pop().map(Json).ok_or(CustomError::ObjectiveNotFound)
+}
+
+// Each level of the tree is loaded with one listing for all of its parents,
+// then the items are grouped under their parent in memory.
+async fn build_theme_trees(
+    store: &dyn Store,
+    themes: Vec<model::Theme>,
+) -> Result<Vec<model::ThemeTree>, CustomError> {
+    let theme_ids: Vec<i32> = themes.iter().map(|theme| theme.theme_id).collect();
+    let objectives = store
+        .objectives(&children_of(theme_ids, "objective_id"))
+        .await?
+        .items;
+    let objectives = build_objective_trees(store, objectives).await?;
+
+    Ok(themes
+        .into_iter()
+        .map(|mut theme| {
+            let objectives: Vec<model::ObjectiveTree> = objectives
+                .iter()
+                .filter(|tree| tree.objective.theme_id == theme.theme_id)
+                .cloned()
+                .collect();
+            theme.score = model::theme_score(objectives.iter().map(|tree| &tree.objective));
+            model::ThemeTree { theme, objectives }
+        })
+        .collect())
+}
 
-    let objectives = sqlx::query_as::<_, model::Objective>(&sql)
-        .fetch_all(&pool)
+async fn build_objective_trees(
+    store: &dyn Store,
+    objectives: Vec<model::Objective>,
+) -> Result<Vec<model::ObjectiveTree>, CustomError> {
+    let objective_ids: Vec<i32> = objectives.iter().map(|obj| obj.objective_id).collect();
+    let keyresults = store
+        .keyresults(&children_of(objective_ids.clone(), "keyresult_id"))
+        .await?
+        .items;
+    let initiatives = store
+        .initiatives(&children_of(objective_ids.clone(), "initiative_id"))
+        .await?
+        .items;
+    let projects = store
+        .projects(&children_of(objective_ids, "project_id"))
+        .await?
+        .items;
+
+    let keyresult_ids: Vec<i32> = keyresults.iter().map(|kr| kr.keyresult_id).collect();
+    let measurements = store
+        .measurements(&children_of(keyresult_ids, "observed_at"))
+        .await?
+        .items;
+
+    let project_ids: Vec<i32> = projects.iter().map(|proj| proj.project_id).collect();
+    let tasks = store
+        .tasks(&children_of(project_ids, "task_id"))
+        .await?
+        .items;
+
+    Ok(objectives
+        .into_iter()
+        .map(|mut objective| {
+            let objective_id = objective.objective_id;
+            let keyresults: Vec<model::KeyResult> = keyresults
+                .iter()
+                .filter(|kr| kr.objective_id == objective_id)
+                .cloned()
+                .collect();
+            objective.score = model::objective_score(&keyresults);
+            model::ObjectiveTree {
+                objective,
+                keyresults: keyresults
+                    .into_iter()
+                    .map(|keyresult| model::KeyResultTree {
+                        measurements: measurements
+                            .iter()
+                            .filter(|measure| measure.keyresult_id == keyresult.keyresult_id)
+                            .cloned()
+                            .collect(),
+                        keyresult,
+                    })
+                    .collect(),
+                initiatives: initiatives
+                    .iter()
+                    .filter(|ini| ini.objective_id == objective_id)
+                    .cloned()
+                    .collect(),
+                projects: projects
+                    .iter()
+                    .filter(|proj| proj.objective_id == objective_id)
+                    .cloned()
+                    .map(|project| model::ProjectTree {
+                        tasks: tasks
+                            .iter()
+                            .filter(|task| task.project_id == project.project_id)
+                            .cloned()
+                            .collect(),
+                        project,
+                    })
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+// GET /api/cycle/:cycle_id
+pub async fn get_cycle(
+    Extension(store): Extension<DynStore>,
+    Path(cycle_id): Path<i32>,
+) -> Result<Json<model::Cycle>, CustomError> {
+    store.cycle(cycle_id).await.map(Json)
+}
+
+// POST /api/cycle
+pub async fn add_cycle(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_cycle): Payload<model::CreateCycle>,
+) -> Result<impl IntoResponse, CustomError> {
+    validation::create_cycle(&mut create_cycle)?;
+    let cycle = store.add_cycle(create_cycle).await?;
+    Ok(created(format!("/api/cycle/{}", cycle.cycle_id), cycle))
+}
+
+// PUT /api/cycle/:cycle_id
+pub async fn update_cycle(
+    Extension(store): Extension<DynStore>,
+    Path(cycle_id): Path<i32>,
+    Payload(update_cycle): Payload<model::UpdateCycle>,
+) -> Result<Json<model::Cycle>, CustomError> {
+    save_cycle(&*store, cycle_id, update_cycle).await.map(Json)
+}
+
+// PATCH /api/cycle/:cycle_id
+pub async fn patch_cycle(
+    Extension(store): Extension<DynStore>,
+    Path(cycle_id): Path<i32>,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::Cycle>, CustomError> {
+    let cycle = store.cycle(cycle_id).await?;
+    let update_cycle = apply_patch(model::UpdateCycle::from(cycle), patch)?;
+    save_cycle(&*store, cycle_id, update_cycle).await.map(Json)
+}
+
+// DELETE /api/cycle/:cycle_id
+pub async fn remove_cycle(
+    Extension(store): Extension<DynStore>,
+    Path(cycle_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_cycle(cycle_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// POST /api/cycle/:cycle_id/carry_over
+pub async fn carry_over_cycle(
+    Extension(store): Extension<DynStore>,
+    Path(cycle_id): Path<i32>,
+) -> Result<Json<model::CarryOver>, CustomError> {
+    super::carry_over(&*store, cycle_id).await.map(Json)
+}
+
+async fn save_cycle(
+    store: &dyn Store,
+    cycle_id: i32,
+    mut update_cycle: model::UpdateCycle,
+) -> Result<model::Cycle, CustomError> {
+    validation::update_cycle(&mut update_cycle)?;
+    store.save_cycle(cycle_id, update_cycle).await
+}
+
+// GET /api/theme/:theme_id
+pub async fn get_theme(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+) -> Result<Json<model::Theme>, CustomError> {
+    let theme = store.theme(theme_id).await?;
+    scored_theme(&*store, theme).await.map(Json)
+}
+
+// POST /api/theme
+pub async fn add_theme(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_theme): Payload<model::CreateTheme>,
+) -> Result<impl IntoResponse, CustomError> {
+    validation::create_theme(&mut create_theme)?;
+    let theme = store.add_theme(create_theme).await?;
+    Ok(created(format!("/api/theme/{}", theme.theme_id), theme))
+}
+
+// PUT /api/theme/:theme_id, leaving out optional fields keeps them as they are
+pub async fn update_theme(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(update_theme): Payload<model::UpdateTheme>,
+) -> Result<Json<model::Theme>, CustomError> {
+    save_theme(&*store, theme_id, update_theme, &changed_by)
         .await
-        .unwrap();
+        .map(Json)
+}
 
-    (StatusCode::OK, Json(objectives))
+// PATCH /api/theme/:theme_id
+pub async fn patch_theme(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::Theme>, CustomError> {
+    let theme = store.theme(theme_id).await?;
+    let update_theme = apply_patch(model::UpdateTheme::from(theme), patch)?;
+    save_theme(&*store, theme_id, update_theme, &changed_by)
+        .await
+        .map(Json)
 }
 
-// GET /api/keyresult
-pub async fn get_all_keyresults(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * from keyresults".to_string();
+// DELETE /api/theme/:theme_id
+pub async fn remove_theme(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_theme(theme_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn save_theme(
+    store: &dyn Store,
+    theme_id: i32,
+    mut update_theme: model::UpdateTheme,
+    changed_by: &str,
+) -> Result<model::Theme, CustomError> {
+    validation::update_theme(store, theme_id, &mut update_theme).await?;
+    let theme = store.save_theme(theme_id, update_theme, changed_by).await?;
+    scored_theme(store, theme).await
+}
 
-    let keyresults = sqlx::query_as::<_, model::KeyResult>(&sql)
-        .fetch_all(&pool)
+async fn scored_theme(store: &dyn Store, theme: model::Theme) -> Result<model::Theme, CustomError> {
+    let mut themes = [theme];
+    score_themes(store, &mut themes, None).await?;
+    let [theme] = themes;
+    Ok(theme)
+}
+
+// GET /api/theme/:theme_id/history
+pub async fn get_theme_history(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+) -> Result<Json<Vec<model::StateChange>>, CustomError> {
+    store.theme(theme_id).await?;
+    let changes = store
+        .state_changes(model::WorkflowEntity::Theme, theme_id)
+        .await?;
+    Ok(Json(changes))
+}
+
+// GET /api/objective/:objective_id
+pub async fn get_objective(
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<Json<model::Objective>, CustomError> {
+    let objective = store.objective(objective_id).await?;
+    scored_objective(&*store, objective).await.map(Json)
+}
+
+// POST /api/objective
+pub async fn add_objective(
+    Extension(store): Extension<DynStore>,
+    Payload(create_objective): Payload<model::CreateObjective>,
+) -> Result<impl IntoResponse, CustomError> {
+    let objective = super::add_objective(&*store, create_objective).await?;
+    Ok(created(
+        format!("/api/objective/{}", objective.objective_id),
+        objective,
+    ))
+}
+
+// PUT /api/objective/:objective_id
+pub async fn update_objective(
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+    Payload(update_objective): Payload<model::UpdateObjective>,
+) -> Result<Json<model::Objective>, CustomError> {
+    save_objective(&*store, objective_id, update_objective)
+        .await
+        .map(Json)
+}
+
+// PATCH /api/objective/:objective_id
+pub async fn patch_objective(
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::Objective>, CustomError> {
+    let objective = store.objective(objective_id).await?;
+    let update_objective = apply_patch(model::UpdateObjective::from(objective), patch)?;
+    save_objective(&*store, objective_id, update_objective)
         .await
-        .unwrap();
+        .map(Json)
+}
 
-    (StatusCode::OK, Json(keyresults))
+// DELETE /api/objective/:objective_id
+pub async fn remove_objective(
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_objective(objective_id).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-// GET /api/initiative
-pub async fn get_all_initiatives(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * from initiatives".to_string();
+async fn save_objective(
+    store: &dyn Store,
+    objective_id: i32,
+    mut update_objective: model::UpdateObjective,
+) -> Result<model::Objective, CustomError> {
+    validation::update_objective(store, &mut update_objective).await?;
+    let objective = store.save_objective(objective_id, update_objective).await?;
+    scored_objective(store, objective).await
+}
+
+async fn scored_objective(
+    store: &dyn Store,
+    objective: model::Objective,
+) -> Result<model::Objective, CustomError> {
+    let mut objectives = [objective];
+    score_objectives(store, &mut objectives).await?;
+    let [objective] = objectives;
+    Ok(objective)
+}
+
+// GET /api/keyresult/:keyresult_id
+pub async fn get_keyresult(
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+) -> Result<Json<model::KeyResult>, CustomError> {
+    store.keyresult(keyresult_id).await.map(Json)
+}
+
+// POST /api/keyresult
+pub async fn add_keyresult(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_keyresult): Payload<model::CreateKeyResult>,
+) -> Result<impl IntoResponse, CustomError> {
+    validation::create_keyresult(&*store, &mut create_keyresult).await?;
+    let keyresult = store.add_keyresult(create_keyresult).await?;
+    Ok(created(
+        format!("/api/keyresult/{}", keyresult.keyresult_id),
+        keyresult,
+    ))
+}
 
-    let initiatives = sqlx::query_as::<_, model::Initiative>(&sql)
-        .fetch_all(&pool)
+// PUT /api/keyresult/:keyresult_id
+pub async fn update_keyresult(
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+    Payload(update_keyresult): Payload<model::UpdateKeyResult>,
+) -> Result<Json<model::KeyResult>, CustomError> {
+    save_keyresult(&*store, keyresult_id, update_keyresult)
         .await
-        .unwrap();
+        .map(Json)
+}
 
-    (StatusCode::OK, Json(initiatives))
+// PATCH /api/keyresult/:keyresult_id
+pub async fn patch_keyresult(
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::KeyResult>, CustomError> {
+    let keyresult = store.keyresult(keyresult_id).await?;
+    let update_keyresult = apply_patch(model::UpdateKeyResult::from(keyresult), patch)?;
+    save_keyresult(&*store, keyresult_id, update_keyresult)
+        .await
+        .map(Json)
 }
 
-// GET /api/project
-pub async fn get_all_projects(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * from projects".to_string();
+// DELETE /api/keyresult/:keyresult_id
+pub async fn remove_keyresult(
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_keyresult(keyresult_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn save_keyresult(
+    store: &dyn Store,
+    keyresult_id: i32,
+    mut update_keyresult: model::UpdateKeyResult,
+) -> Result<model::KeyResult, CustomError> {
+    validation::update_keyresult(&mut update_keyresult)?;
+    store.save_keyresult(keyresult_id, update_keyresult).await
+}
+
+// GET /api/initiative/:initiative_id
+pub async fn get_initiative(
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+) -> Result<Json<model::Initiative>, CustomError> {
+    store.initiative(initiative_id).await.map(Json)
+}
+
+// POST /api/initiative
+pub async fn add_initiative(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_initiative): Payload<model::CreateInitiative>,
+) -> Result<impl IntoResponse, CustomError> {
+    validation::create_initiative(&*store, &mut create_initiative).await?;
+    let initiative = store.add_initiative(create_initiative).await?;
+    Ok(created(
+        format!("/api/initiative/{}", initiative.initiative_id),
+        initiative,
+    ))
+}
 
-    let projects = sqlx::query_as::<_, model::Project>(&sql)
-        .fetch_all(&pool)
+// PUT /api/initiative/:initiative_id, leaving out optional fields keeps them as they are
+pub async fn update_initiative(
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(update_initiative): Payload<model::UpdateInitiative>,
+) -> Result<Json<model::Initiative>, CustomError> {
+    save_initiative(&*store, initiative_id, update_initiative, &changed_by)
         .await
-        .unwrap();
+        .map(Json)
+}
 
-    (StatusCode::OK, Json(projects))
+// PATCH /api/initiative/:initiative_id
+pub async fn patch_initiative(
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::Initiative>, CustomError> {
+    let initiative = store.initiative(initiative_id).await?;
+    let update_initiative = apply_patch(model::UpdateInitiative::from(initiative), patch)?;
+    save_initiative(&*store, initiative_id, update_initiative, &changed_by)
+        .await
+        .map(Json)
 }
 
-// GET /api/task
-pub async fn get_all_tasks(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * from tasks".to_string();
+// DELETE /api/initiative/:initiative_id
+pub async fn remove_initiative(
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_initiative(initiative_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    let tasks = sqlx::query_as::<_, model::Task>(&sql)
-        .fetch_all(&pool)
+async fn save_initiative(
+    store: &dyn Store,
+    initiative_id: i32,
+    mut update_initiative: model::UpdateInitiative,
+    changed_by: &str,
+) -> Result<model::Initiative, CustomError> {
+    validation::update_initiative(store, initiative_id, &mut update_initiative).await?;
+    store
+        .save_initiative(initiative_id, update_initiative, changed_by)
         .await
-        .unwrap();
+}
 
-    (StatusCode::OK, Json(tasks))
+// GET /api/initiative/:initiative_id/history
+pub async fn get_initiative_history(
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+) -> Result<Json<Vec<model::StateChange>>, CustomError> {
+    store.initiative(initiative_id).await?;
+    let changes = store
+        .state_changes(model::WorkflowEntity::Initiative, initiative_id)
+        .await?;
+    Ok(Json(changes))
 }
 
-// GET /api/measure
-pub async fn get_all_measures(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * from measures".to_string();
+// GET /api/project/:project_id
+pub async fn get_project(
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<model::Project>, CustomError> {
+    store.project(project_id).await.map(Json)
+}
+
+// POST /api/project
+pub async fn add_project(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_project): Payload<model::CreateProject>,
+) -> Result<impl IntoResponse, CustomError> {
+    validation::create_project(&*store, &mut create_project).await?;
+    let project = store.add_project(create_project).await?;
+    Ok(created(
+        format!("/api/project/{}", project.project_id),
+        project,
+    ))
+}
 
-    let measurements = sqlx::query_as::<_, model::Measurement>(&sql)
-        .fetch_all(&pool)
+// PUT /api/project/:project_id, leaving out optional fields keeps them as they are
+pub async fn update_project(
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(update_project): Payload<model::UpdateProject>,
+) -> Result<Json<model::Project>, CustomError> {
+    save_project(&*store, project_id, update_project, &changed_by)
         .await
-        .unwrap();
+        .map(Json)
+}
+
+// PATCH /api/project/:project_id
+pub async fn patch_project(
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::Project>, CustomError> {
+    let project = store.project(project_id).await?;
+    let update_project = apply_patch(model::UpdateProject::from(project), patch)?;
+    save_project(&*store, project_id, update_project, &changed_by)
+        .await
+        .map(Json)
+}
+
+// DELETE /api/project/:project_id
+pub async fn remove_project(
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_project(project_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn save_project(
+    store: &dyn Store,
+    project_id: i32,
+    mut update_project: model::UpdateProject,
+    changed_by: &str,
+) -> Result<model::Project, CustomError> {
+    validation::update_project(store, project_id, &mut update_project).await?;
+    store
+        .save_project(project_id, update_project, changed_by)
+        .await
+}
+
+// GET /api/project/:project_id/history
+pub async fn get_project_history(
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<Json<Vec<model::StateChange>>, CustomError> {
+    store.project(project_id).await?;
+    let changes = store
+        .state_changes(model::WorkflowEntity::Project, project_id)
+        .await?;
+    Ok(Json(changes))
+}
+
+// GET /api/task/:task_id
+pub async fn get_task(
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+) -> Result<Json<model::Task>, CustomError> {
+    store.task(task_id).await.map(Json)
+}
+
+// POST /api/task
+pub async fn add_task(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_task): Payload<model::CreateTask>,
+) -> Result<impl IntoResponse, CustomError> {
+    validation::create_task(&*store, &mut create_task).await?;
+    let task = store.add_task(create_task).await?;
+    Ok(created(format!("/api/task/{}", task.task_id), task))
+}
+
+// PUT /api/task/:task_id, leaving out optional fields keeps them as they are
+pub async fn update_task(
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(update_task): Payload<model::UpdateTask>,
+) -> Result<Json<model::Task>, CustomError> {
+    save_task(&*store, task_id, update_task, &changed_by)
+        .await
+        .map(Json)
+}
+
+// PATCH /api/task/:task_id
+pub async fn patch_task(
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::Task>, CustomError> {
+    let task = store.task(task_id).await?;
+    let update_task = apply_patch(model::UpdateTask::from(task), patch)?;
+    save_task(&*store, task_id, update_task, &changed_by)
+        .await
+        .map(Json)
+}
+
+// DELETE /api/task/:task_id
+pub async fn remove_task(
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_task(task_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn save_task(
+    store: &dyn Store,
+    task_id: i32,
+    mut update_task: model::UpdateTask,
+    changed_by: &str,
+) -> Result<model::Task, CustomError> {
+    validation::update_task(store, task_id, &mut update_task).await?;
+    store.save_task(task_id, update_task, changed_by).await
+}
+
+// GET /api/task/:task_id/history
+pub async fn get_task_history(
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+) -> Result<Json<Vec<model::StateChange>>, CustomError> {
+    store.task(task_id).await?;
+    let changes = store
+        .state_changes(model::WorkflowEntity::Task, task_id)
+        .await?;
+    Ok(Json(changes))
+}
+
+// GET /api/measure/:measure_id
+pub async fn get_measure(
+    Extension(store): Extension<DynStore>,
+    Path(measure_id): Path<i32>,
+) -> Result<Json<model::Measurement>, CustomError> {
+    store.measurement(measure_id).await.map(Json)
+}
+
+// POST /api/measure
+pub async fn add_measure(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_measurement): Payload<model::CreateMeasurement>,
+) -> Result<impl IntoResponse, CustomError> {
+    validation::create_measurement(&*store, &mut create_measurement).await?;
+    let measurement = store.add_measurement(create_measurement).await?;
+    Ok(created(
+        format!("/api/measure/{}", measurement.measurement_id),
+        measurement,
+    ))
+}
+
+// PUT /api/measure/:measure_id
+pub async fn update_measure(
+    Extension(store): Extension<DynStore>,
+    Path(measure_id): Path<i32>,
+    Payload(update_measurement): Payload<model::UpdateMeasurement>,
+) -> Result<Json<model::Measurement>, CustomError> {
+    save_measurement(&*store, measure_id, update_measurement)
+        .await
+        .map(Json)
+}
+
+// PATCH /api/measure/:measure_id
+pub async fn patch_measure(
+    Extension(store): Extension<DynStore>,
+    Path(measure_id): Path<i32>,
+    Payload(patch): Payload<Value>,
+) -> Result<Json<model::Measurement>, CustomError> {
+    let measurement = store.measurement(measure_id).await?;
+    let update_measurement = apply_patch(model::UpdateMeasurement::from(measurement), patch)?;
+    save_measurement(&*store, measure_id, update_measurement)
+        .await
+        .map(Json)
+}
+
+// DELETE /api/measure/:measure_id
+pub async fn remove_measure(
+    Extension(store): Extension<DynStore>,
+    Path(measure_id): Path<i32>,
+) -> Result<StatusCode, CustomError> {
+    store.remove_measurement(measure_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    (StatusCode::OK, Json(measurements))
+async fn save_measurement(
+    store: &dyn Store,
+    measure_id: i32,
+    mut update_measurement: model::UpdateMeasurement,
+) -> Result<model::Measurement, CustomError> {
+    validation::update_measurement(&mut update_measurement)?;
+    store.save_measurement(measure_id, update_measurement).await
 }
@@ -1,1028 +1,1053 @@
-use crate::{errors::CustomError, model, templater};
+use super::{all, all_cycles, children_of, resolve_cycle, score_objectives, score_themes};
+use crate::{
+    errors::CustomError,
+    model,
+    store::{DynStore, Store},
+    templater,
+    validation::{self, ChangedBy, Params, Path, Payload},
+};
 use axum::{
-    extract,
     http::StatusCode,
     response::{IntoResponse, Redirect},
     Extension,
 };
-use sqlx::PgPool;
+
+// The objectives of a theme, only those in the given cycle when there is one
+fn theme_objectives(theme_id: i32, cycle_id: Option<i32>, sort: &str) -> model::ListQuery {
+    model::ListQuery {
+        cycle_id,
+        ..children_of(vec![theme_id], sort)
+    }
+}
+
+// States of a workflow, in the order items move through them
+async fn workflow_states(
+    store: &dyn Store,
+    entity: model::WorkflowEntity,
+) -> Result<Vec<model::WorkflowState>, CustomError> {
+    store.workflow_states(Some(entity)).await
+}
 
 // GET /
-pub async fn get_root(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * FROM themes ORDER BY created_at".to_string();
+pub async fn get_root(
+    Extension(store): Extension<DynStore>,
+    Params(query): Params<model::CycleQuery>,
+) -> Result<axum::response::Response, CustomError> {
+    let cycle_id = resolve_cycle(&*store, query.cycle)
+        .await?
+        .map(|cycle| cycle.cycle_id);
+
+    let mut themes = store.themes(&all("created_at")).await?.items;
+    score_themes(&*store, &mut themes, cycle_id).await?;
+
+    let template = templater::PageRootTemplate::new(themes, all_cycles(&*store).await?, cycle_id);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
 
-    let themes = sqlx::query_as::<_, model::Theme>(&sql)
-        .fetch_all(&pool)
-        .await;
+// GET /overdue
+pub async fn get_overdue(
+    Extension(store): Extension<DynStore>,
+) -> Result<axum::response::Response, CustomError> {
+    let items = store.overdue(model::today()).await?;
+    let template = templater::PageOverdueTemplate::new(items);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
 
-    let template = templater::PageRootTemplate::new(themes.ok());
-    templater::HtmlTemplate(template).into_response()
+// GET /cycles
+pub async fn get_cycles_page(
+    Extension(store): Extension<DynStore>,
+) -> Result<axum::response::Response, CustomError> {
+    let template = templater::PageCyclesTemplate::new(all_cycles(&*store).await?);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
-// GET /theme
-pub async fn get_root_themes(Extension(pool): Extension<PgPool>) -> impl IntoResponse {
-    let sql = "SELECT * FROM themes ORDER BY created_at".to_string();
+// GET /cycle
+pub async fn get_cycles(
+    Extension(store): Extension<DynStore>,
+) -> Result<axum::response::Response, CustomError> {
+    let template = templater::TableCyclesTemplate::new(all_cycles(&*store).await?, None);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// POST /cycle
+pub async fn add_cycle(
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_cycle): Payload<model::CreateCycle>,
+) -> Result<Redirect, CustomError> {
+    validation::create_cycle(&mut create_cycle)?;
+    store.add_cycle(create_cycle).await?;
+    Ok(Redirect::to("/cycle"))
+}
+
+// POST /cycle/:cycle_id/carry_over
+pub async fn carry_over_cycle(
+    Extension(store): Extension<DynStore>,
+    Path(cycle_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let carry_over = super::carry_over(&*store, cycle_id).await?;
+    let plural = |count: usize, noun: &str| match count {
+        1 => format!("1 {noun}"),
+        count => format!("{count} {noun}s"),
+    };
+    let notice = format!(
+        "Carried {} and {} over from {} into {}.",
+        plural(carry_over.objectives, "objective"),
+        plural(carry_over.keyresults, "key result"),
+        carry_over.from_cycle.title,
+        carry_over.to_cycle.title
+    );
+    let template = templater::TableCyclesTemplate::new(all_cycles(&*store).await?, Some(notice));
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// DELETE /cycle/:cycle_id
+pub async fn remove_cycle(
+    Extension(store): Extension<DynStore>,
+    Path(cycle_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_cycle(cycle_id).await?;
+    Ok((StatusCode::OK, ""))
+}
+
+// GET /workflows
+pub async fn get_workflows_page(
+    Extension(store): Extension<DynStore>,
+) -> Result<impl IntoResponse, CustomError> {
+    let mut workflows = Vec::new();
+    for entity in model::WorkflowEntity::ALL {
+        let states = workflow_states(&*store, entity).await?;
+        workflows.push(templater::Workflow { entity, states });
+    }
+    let template = templater::PageWorkflowsTemplate::new(workflows);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
 
-    let themes = sqlx::query_as::<_, model::Theme>(&sql)
-        .fetch_all(&pool)
-        .await;
+// GET /workflow/:entity
+pub async fn get_workflow(
+    Extension(store): Extension<DynStore>,
+    Path(entity): Path<model::WorkflowEntity>,
+) -> Result<impl IntoResponse, CustomError> {
+    let states = workflow_states(&*store, entity).await?;
+    let template = templater::TableWorkflowTemplate::new(entity, states);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// POST /workflow/state
+pub async fn add_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Payload(create_state): Payload<model::CreateWorkflowState>,
+) -> Result<Redirect, CustomError> {
+    let state = super::add_workflow_state(&*store, create_state).await?;
+    Ok(Redirect::to(&format!("/workflow/{}", state.entity)))
+}
+
+// PUT /workflow/state/:state_id
+pub async fn update_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Path(state_id): Path<i32>,
+    Payload(update_state): Payload<model::UpdateWorkflowState>,
+) -> Result<Redirect, CustomError> {
+    let state = super::save_workflow_state(&*store, state_id, update_state).await?;
+    Ok(Redirect::to(&format!("/workflow/{}", state.entity)))
+}
+
+// DELETE /workflow/state/:state_id
+pub async fn remove_workflow_state(
+    Extension(store): Extension<DynStore>,
+    Path(state_id): Path<i32>,
+) -> Result<Redirect, CustomError> {
+    let state = store.workflow_state(state_id).await?;
+    super::remove_workflow_state(&*store, state_id).await?;
+    Ok(Redirect::to(&format!("/workflow/{}", state.entity)))
+}
 
-    let template = templater::TableThemesTemplate::new(themes.ok());
-    templater::HtmlTemplate(template).into_response()
+// GET /theme
+pub async fn get_root_themes(
+    Extension(store): Extension<DynStore>,
+) -> Result<axum::response::Response, CustomError> {
+    let mut themes = store.themes(&all("created_at")).await?.items;
+    score_themes(&*store, &mut themes, None).await?;
+
+    let template = templater::TableThemesTemplate::new(themes);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /theme/:theme_id
 pub async fn get_theme(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(theme_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let theme_row: Result<model::Theme, sqlx::Error> =
-        sqlx::query_as(r#"SELECT * FROM themes WHERE theme_id = $1 ORDER BY created_at"#)
-            .bind(&theme_id)
-            .fetch_one(&pool)
-            .await;
-
-    match theme_row {
-        Ok(theme) => {
-            let objectives: Option<Vec<model::Objective>> = sqlx::query_as(
-                r#"SELECT * FROM objectives WHERE theme_id = $1 ORDER BY created_at;"#,
-            )
-            .bind(&theme_id)
-            .fetch_all(&pool)
-            .await
-            .ok();
-            let template = templater::PageThemeTemplate::new(
-                theme.title,
-                theme_id,
-                theme.theme_status,
-                objectives,
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template =
-                templater::ErrorTemplate::new(StatusCode::NOT_FOUND, "Theme Not Found".to_string());
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+    Params(query): Params<model::CycleQuery>,
+) -> Result<axum::response::Response, CustomError> {
+    let cycle_id = resolve_cycle(&*store, query.cycle)
+        .await?
+        .map(|cycle| cycle.cycle_id);
+    let theme = store.theme(theme_id).await?;
+
+    let mut objectives = store
+        .objectives(&theme_objectives(theme_id, cycle_id, "created_at"))
+        .await?
+        .items;
+    score_objectives(&*store, &mut objectives).await?;
+    let state_changes = store
+        .state_changes(model::WorkflowEntity::Theme, theme_id)
+        .await?;
+    let template = templater::PageThemeTemplate::new(
+        theme,
+        objectives,
+        all_cycles(&*store).await?,
+        cycle_id,
+        state_changes,
+    );
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /theme/:theme_id/row
 pub async fn get_theme_row(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(theme_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let theme_row = sqlx::query_as(r#"SELECT * FROM themes WHERE theme_id = $1"#)
-        .bind(&theme_id)
-        .fetch_one(&pool)
-        .await;
-
-    match theme_row {
-        Ok(theme) => {
-            let template = templater::RowThemeTemplate::new(theme);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template =
-                templater::ErrorTemplate::new(StatusCode::NOT_FOUND, "Theme Not Found".to_string());
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let theme = store.theme(theme_id).await?;
+
+    let mut themes = [theme];
+    score_themes(&*store, &mut themes, None).await?;
+    let [theme] = themes;
+    let template = templater::RowThemeTemplate::new(theme);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /theme/:theme_id/form
 pub async fn get_theme_form(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(theme_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let theme_row = sqlx::query_as(r#"SELECT * FROM themes WHERE theme_id = $1"#)
-        .bind(&theme_id)
-        .fetch_one(&pool)
-        .await;
-
-    match theme_row {
-        Ok(theme) => {
-            let template = templater::EditRowThemeTemplate::new(theme);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template =
-                templater::ErrorTemplate::new(StatusCode::NOT_FOUND, "Theme Not Found".to_string());
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let theme = store.theme(theme_id).await?;
+
+    let states = workflow_states(&*store, model::WorkflowEntity::Theme).await?;
+    let template = templater::EditRowThemeTemplate::new(theme, states);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /theme/:theme_id/objectives
 pub async fn get_theme_objectives(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(theme_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let objectives: Option<Vec<model::Objective>> =
-        sqlx::query_as(r#"SELECT * FROM objectives WHERE theme_id = $1 ORDER BY objective_id;"#)
-            .bind(&theme_id)
-            .fetch_all(&pool)
-            .await
-            .ok();
-    let template = templater::TableObjectivesTemplate::new(objectives, theme_id);
-    templater::HtmlTemplate(template).into_response()
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+    Params(query): Params<model::CycleQuery>,
+) -> Result<axum::response::Response, CustomError> {
+    let cycle_id = resolve_cycle(&*store, query.cycle)
+        .await?
+        .map(|cycle| cycle.cycle_id);
+    let mut objectives = store
+        .objectives(&theme_objectives(theme_id, cycle_id, "objective_id"))
+        .await?
+        .items;
+    score_objectives(&*store, &mut objectives).await?;
+    let template = templater::TableObjectivesTemplate::new(objectives, theme_id, cycle_id);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /objective/:objective_id
 pub async fn get_objective(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let objective_row = sqlx::query!(
-        r#"
-        SELECT objectives.title, themes.theme_id, themes.title as theme_title 
-        FROM objectives 
-        LEFT JOIN themes 
-        ON objectives.theme_id = themes.theme_id 
-        WHERE objectives.objective_id = $1;
-        "#,
-        objective_id
-    )
-    .fetch_one(&pool)
-    .await;
-
-    match objective_row {
-        Ok(obj) => {
-            let kr_rows: Option<Vec<model::KeyResult>> = sqlx::query_as(
-                r#"SELECT * FROM keyresults WHERE objective_id = $1 ORDER BY keyresult_id;"#,
-            )
-            .bind(&objective_id)
-            .fetch_all(&pool)
-            .await
-            .ok();
-
-            let ini_rows: Option<Vec<model::Initiative>> = sqlx::query_as(
-                r#"SELECT * FROM initiatives WHERE objective_id = $1 ORDER BY initiative_id;"#,
-            )
-            .bind(&objective_id)
-            .fetch_all(&pool)
-            .await
-            .ok();
-
-            let proj_rows: Option<Vec<model::Project>> = sqlx::query_as(
-                r#"SELECT * FROM projects WHERE objective_id = $1 ORDER BY project_id;"#,
-            )
-            .bind(&objective_id)
-            .fetch_all(&pool)
-            .await
-            .ok();
-            let template = templater::PageObjectiveTemplate::new(
-                obj.title,
-                objective_id,
-                obj.theme_id,
-                obj.theme_title,
-                kr_rows,
-                ini_rows,
-                proj_rows,
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Objective Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let objective = store.objective(objective_id).await?;
+    let theme_title = store.theme(objective.theme_id).await?.title;
+    let cycle = match objective.cycle_id {
+        Some(cycle_id) => Some(store.cycle(cycle_id).await?),
+        None => None,
+    };
+
+    let kr_rows = store
+        .keyresults(&children_of(vec![objective_id], "keyresult_id"))
+        .await?
+        .items;
+    let ini_rows = store
+        .initiatives(&children_of(vec![objective_id], "initiative_id"))
+        .await?
+        .items;
+    let proj_rows = store
+        .projects(&children_of(vec![objective_id], "project_id"))
+        .await?
+        .items;
+    let template = templater::PageObjectiveTemplate::new(
+        objective,
+        theme_title,
+        cycle,
+        kr_rows,
+        ini_rows,
+        proj_rows,
+    );
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /objective/:objective_id/row
 pub async fn get_objective_row(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let objective_row = sqlx::query_as(r#"SELECT * FROM objectives WHERE objective_id = $1"#)
-        .bind(&objective_id)
-        .fetch_one(&pool)
-        .await;
-
-    match objective_row {
-        Ok(objective) => {
-            let template = templater::RowObjectiveTemplate::new(objective);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Objective Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let objective = store.objective(objective_id).await?;
+
+    let mut objectives = [objective];
+    score_objectives(&*store, &mut objectives).await?;
+    let [objective] = objectives;
+    let template = templater::RowObjectiveTemplate::new(objective);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /objective/:objective_id/form
 pub async fn get_objective_form(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let objective_row = sqlx::query_as(r#"SELECT * FROM objectives WHERE objective_id = $1"#)
-        .bind(&objective_id)
-        .fetch_one(&pool)
-        .await;
-
-    match objective_row {
-        Ok(objective) => {
-            let template = templater::EditRowObjectiveTemplate::new(objective);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Objective Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let objective = store.objective(objective_id).await?;
+
+    let template = templater::EditRowObjectiveTemplate::new(objective, all_cycles(&*store).await?);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /objective/:objective_id/keyresults
 pub async fn get_objective_keyresults(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let keyresults: Option<Vec<model::KeyResult>> = sqlx::query_as(
-        r#"SELECT * FROM keyresults WHERE objective_id = $1 ORDER BY keyresult_id;"#,
-    )
-    .bind(&objective_id)
-    .fetch_all(&pool)
-    .await
-    .ok();
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let keyresults = store
+        .keyresults(&children_of(vec![objective_id], "keyresult_id"))
+        .await?
+        .items;
     let template = templater::TableKeyResultsTemplate::new(keyresults, objective_id);
-    templater::HtmlTemplate(template).into_response()
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /objective/:objective_id/initiatives
 pub async fn get_objective_initiatives(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let initiatives: Option<Vec<model::Initiative>> = sqlx::query_as(
-        r#"SELECT * FROM initiatives WHERE objective_id = $1 ORDER BY created_at;"#,
-    )
-    .bind(&objective_id)
-    .fetch_all(&pool)
-    .await
-    .ok();
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let initiatives = store
+        .initiatives(&children_of(vec![objective_id], "created_at"))
+        .await?
+        .items;
     let template = templater::TableInitiativesTemplate::new(initiatives, objective_id);
-    templater::HtmlTemplate(template).into_response()
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /objective/:objective_id/projects
 pub async fn get_objective_projects(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let projects: Option<Vec<model::Project>> = sqlx::query_as(
-        r#"SELECT * FROM projects
-        WHERE objective_id = $1
-        ORDER BY created_at;"#,
-    )
-    .bind(&objective_id)
-    .fetch_all(&pool)
-    .await
-    .ok();
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let projects = store
+        .projects(&children_of(vec![objective_id], "created_at"))
+        .await?
+        .items;
     let template = templater::TableProjectsTemplate::new(projects, objective_id);
-    templater::HtmlTemplate(template).into_response()
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /keyresult/:keyresult_id
 pub async fn get_keyresult(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(keyresult_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let keyresult_row = sqlx::query!(
-        r#"
-        SELECT keyresults.title, objectives.objective_id, objectives.title as objective_title 
-        FROM keyresults 
-        LEFT JOIN objectives 
-        ON keyresults.objective_id = objectives.objective_id 
-        WHERE keyresults.keyresult_id = $1;
-        "#,
-        keyresult_id
-    )
-    .fetch_one(&pool)
-    .await;
-
-    match keyresult_row {
-        Ok(keyresult) => {
-            let measurements: Result<Vec<model::Measurement>, _> = sqlx::query_as(
-                r#"SELECT *
-                FROM measurements
-                WHERE measurements.keyresult_id = $1
-                ORDER BY measurements.measurement_id;"#,
-            )
-            .bind(&keyresult_id)
-            .fetch_all(&pool)
-            .await;
-
-            let template = templater::PageKeyResultTemplate::new(
-                keyresult.title,
-                keyresult.objective_id,
-                keyresult_id,
-                keyresult.objective_title,
-                measurements.ok(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Key Result Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let keyresult = store.keyresult(keyresult_id).await?;
+
+    let objective_title = store.objective(keyresult.objective_id).await?.title;
+
+    let measurements = store
+        .measurements(&children_of(vec![keyresult_id], "observed_at"))
+        .await?
+        .items;
+
+    let template = templater::PageKeyResultTemplate::new(keyresult, objective_title, measurements);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /keyresult/:keyresult_id/row
 pub async fn get_keyresult_row(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(keyresult_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let keyresult_row = sqlx::query_as(r#"SELECT * FROM keyresults WHERE keyresult_id = $1"#)
-        .bind(&keyresult_id)
-        .fetch_one(&pool)
-        .await;
-
-    match keyresult_row {
-        Ok(keyresult) => {
-            let template = templater::RowKeyResultTemplate::new(keyresult);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Key Result Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let keyresult = store.keyresult(keyresult_id).await?;
+
+    let template = templater::RowKeyResultTemplate::new(keyresult);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /keyresult/:keyresult_id/form
 pub async fn get_keyresult_form(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(keyresult_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let keyresult_row = sqlx::query_as(r#"SELECT * FROM keyresults WHERE keyresult_id = $1"#)
-        .bind(&keyresult_id)
-        .fetch_one(&pool)
-        .await;
-
-    match keyresult_row {
-        Ok(keyresult) => {
-            let template = templater::EditRowKeyResultTemplate::new(keyresult);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Key Result Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let keyresult = store.keyresult(keyresult_id).await?;
+
+    let template = templater::EditRowKeyResultTemplate::new(keyresult);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /keyresult/:keyresult_id/measures
 pub async fn get_keyresult_measurements(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(keyresult_id): extract::Path<i32>,
-) -> axum::response::Response {
-    // let measurements: Result<Vec<model::Measurement>, _> = sqlx::query_as(
-    let measurements: Option<Vec<model::Measurement>> = sqlx::query_as(
-        r#"SELECT *
-        FROM measurements
-        WHERE keyresult_id = $1
-        ORDER BY created_at;"#,
-    )
-    .bind(&keyresult_id)
-    .fetch_all(&pool)
-    .await
-    .ok();
-    // println!("{:#?}", measurements);
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let measurements = store
+        .measurements(&children_of(vec![keyresult_id], "observed_at"))
+        .await?
+        .items;
     let template = templater::TableMeasurementsTemplate::new(measurements, keyresult_id);
-    templater::HtmlTemplate(template).into_response()
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /initiative/:initiative_id
 pub async fn get_initiative(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(initiative_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let initiative_row = sqlx::query!(
-        r#"
-        SELECT initiatives.title, objectives.objective_id, objectives.title as objective_title 
-        FROM initiatives 
-        LEFT JOIN objectives 
-        ON initiatives.objective_id = objectives.objective_id 
-        WHERE initiatives.initiative_id = $1;
-        "#,
-        initiative_id
-    )
-    .fetch_one(&pool)
-    .await;
-
-    match initiative_row {
-        Ok(initiative) => {
-            let template = templater::PageInitiativeTemplate::new(
-                initiative.title,
-                initiative.objective_id,
-                initiative.objective_title,
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Key Result Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let initiative = store.initiative(initiative_id).await?;
+    let objective = store.objective(initiative.objective_id).await?;
+
+    let state_changes = store
+        .state_changes(model::WorkflowEntity::Initiative, initiative_id)
+        .await?;
+    let template = templater::PageInitiativeTemplate::new(
+        initiative.title,
+        initiative.description,
+        objective.objective_id,
+        objective.title,
+        state_changes,
+    );
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /initiative/:initiative_id/row
 pub async fn get_initiative_row(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(initiative_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let initiative_row = sqlx::query_as(r#"SELECT * FROM initiatives WHERE initiative_id = $1"#)
-        .bind(&initiative_id)
-        .fetch_one(&pool)
-        .await;
-
-    match initiative_row {
-        Ok(initiative) => {
-            let template = templater::RowInitiativeTemplate::new(initiative);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Initiative Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let initiative = store.initiative(initiative_id).await?;
+
+    let template = templater::RowInitiativeTemplate::new(initiative);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /initiative/:initiative_id/form
 pub async fn get_initiative_form(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(initiative_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let initiative_row = sqlx::query_as(r#"SELECT * FROM initiatives WHERE initiative_id = $1"#)
-        .bind(&initiative_id)
-        .fetch_one(&pool)
-        .await;
-
-    match initiative_row {
-        Ok(initiative) => {
-            let template = templater::EditRowInitiativeTemplate::new(initiative);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Initiative Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let initiative = store.initiative(initiative_id).await?;
+
+    let states = workflow_states(&*store, model::WorkflowEntity::Initiative).await?;
+    let template = templater::EditRowInitiativeTemplate::new(initiative, states);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /project/:project_id
 pub async fn get_project(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(project_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let project_row = sqlx::query!(
-        r#"
-        SELECT projects.title, objectives.objective_id, objectives.title as objective_title 
-        FROM projects 
-        LEFT JOIN objectives 
-        ON projects.objective_id = objectives.objective_id 
-        WHERE projects.project_id = $1;
-        "#,
-        project_id
-    )
-    .fetch_one(&pool)
-    .await;
-
-    match project_row {
-        Ok(project) => {
-            let tasks: Option<Vec<model::Task>> = sqlx::query_as(
-                r#"SELECT *
-                FROM tasks
-                WHERE tasks.project_id = $1
-                ORDER BY tasks.task_id;"#,
-            )
-            .bind(&project_id)
-            .fetch_all(&pool)
-            .await
-            .ok();
-
-            let template = templater::PageProjectTemplate::new(
-                project.title,
-                project_id,
-                project.objective_id,
-                project.objective_title,
-                tasks,
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Key Result Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let project = store.project(project_id).await?;
+    let objective = store.objective(project.objective_id).await?;
+
+    let tasks = store
+        .tasks(&children_of(vec![project_id], "task_id"))
+        .await?
+        .items;
+
+    let state_changes = store
+        .state_changes(model::WorkflowEntity::Project, project_id)
+        .await?;
+    let template = templater::PageProjectTemplate::new(
+        project.title,
+        project.description,
+        project_id,
+        objective.objective_id,
+        objective.title,
+        tasks,
+        state_changes,
+    );
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /project/:project_id/row
 pub async fn get_project_row(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(project_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let project_row = sqlx::query_as(r#"SELECT * FROM projects WHERE project_id = $1"#)
-        .bind(&project_id)
-        .fetch_one(&pool)
-        .await;
-
-    match project_row {
-        Ok(project) => {
-            let template = templater::RowProjectTemplate::new(project);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Project Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let project = store.project(project_id).await?;
+
+    let template = templater::RowProjectTemplate::new(project);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /project/:project_id/form
 pub async fn get_project_form(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(project_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let project_row = sqlx::query_as(r#"SELECT * FROM projects WHERE project_id = $1"#)
-        .bind(&project_id)
-        .fetch_one(&pool)
-        .await;
-
-    match project_row {
-        Ok(project) => {
-            let template = templater::EditRowProjectTemplate::new(project);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Project Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let project = store.project(project_id).await?;
+
+    let states = workflow_states(&*store, model::WorkflowEntity::Project).await?;
+    let template = templater::EditRowProjectTemplate::new(project, states);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /project/:project_id/tasks
 pub async fn get_project_tasks(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(project_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let tasks: Option<Vec<model::Task>> =
-        sqlx::query_as(r#"SELECT * FROM tasks WHERE project_id = $1 ORDER BY task_id;"#)
-            .bind(&project_id)
-            .fetch_all(&pool)
-            .await
-            .ok();
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let tasks = store
+        .tasks(&children_of(vec![project_id], "task_id"))
+        .await?
+        .items;
     let template = templater::TableTasksTemplate::new(tasks, project_id);
-    templater::HtmlTemplate(template).into_response()
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
-// GET /task/:task_id/row
-pub async fn get_task_row(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(task_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let task_row = sqlx::query_as(r#"SELECT * FROM tasks WHERE task_id = $1"#)
-        .bind(&task_id)
-        .fetch_one(&pool)
-        .await;
-
-    match task_row {
-        Ok(task) => {
-            let template = templater::RowTaskTemplate::new(task);
-            templater::HtmlTemplate(template).into_response()
+// The board of a project's tasks, one column per state of the task workflow
+async fn project_board(
+    store: &dyn Store,
+    project_id: i32,
+) -> Result<Vec<templater::BoardColumn>, CustomError> {
+    let states = workflow_states(store, model::WorkflowEntity::Task).await?;
+    let tasks = store
+        .tasks(&children_of(vec![project_id], "task_id"))
+        .await?
+        .items;
+    Ok(templater::board_columns(states, tasks))
+}
+
+// GET /project/:project_id/board
+pub async fn get_project_board(
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let project = store.project(project_id).await?;
+    let objective = store.objective(project.objective_id).await?;
+
+    let template = templater::PageBoardTemplate::new(
+        project.title,
+        project_id,
+        objective.objective_id,
+        objective.title,
+        project_board(&*store, project_id).await?,
+    );
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// GET /project/:project_id/board/columns
+pub async fn get_project_board_columns(
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let columns = project_board(&*store, project_id).await?;
+    let template = templater::TableBoardTemplate::new(project_id, columns);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// The projects a cross-project board covers
+enum BoardScope {
+    Objective(i32),
+    Theme(i32),
+}
+
+impl BoardScope {
+    // The ids of the objectives whose projects are in scope
+    async fn objective_ids(&self, store: &dyn Store) -> Result<Vec<i32>, CustomError> {
+        match self {
+            BoardScope::Objective(id) => Ok(vec![*id]),
+            BoardScope::Theme(id) => Ok(store
+                .objectives(&children_of(vec![*id], "objective_id"))
+                .await?
+                .items
+                .into_iter()
+                .map(|objective| objective.objective_id)
+                .collect()),
         }
-        Err(_) => {
-            let template =
-                templater::ErrorTemplate::new(StatusCode::NOT_FOUND, "Task Not Found".to_string());
-            templater::HtmlTemplate(template).into_response()
+    }
+
+    // The projects in scope, in the order their swimlanes are shown
+    async fn projects(&self, store: &dyn Store) -> Result<Vec<model::Project>, CustomError> {
+        let objective_ids = self.objective_ids(store).await?;
+        Ok(store
+            .projects(&children_of(objective_ids, "project_id"))
+            .await?
+            .items)
+    }
+
+    fn page_url(&self) -> String {
+        match self {
+            BoardScope::Objective(id) => format!("/objective/{id}"),
+            BoardScope::Theme(id) => format!("/theme/{id}"),
         }
     }
+
+    fn board_url(&self) -> String {
+        format!("{}/board", self.page_url())
+    }
+}
+
+// The tasks of the given projects, filtered on state by the query
+async fn board_tasks(
+    store: &dyn Store,
+    projects: &[model::Project],
+    query: &model::BoardQuery,
+) -> Result<Vec<model::Task>, CustomError> {
+    let project_ids = projects.iter().map(|project| project.project_id).collect();
+    let tasks = store
+        .tasks(&model::ListQuery {
+            state_id: query.state_id,
+            category: query.category,
+            ..children_of(project_ids, "task_id")
+        })
+        .await?
+        .items;
+    Ok(tasks)
+}
+
+// The swimlanes of a cross-project board, with the query's filters applied
+async fn board_swimlanes(
+    store: &dyn Store,
+    scope: &BoardScope,
+    states: &[model::WorkflowState],
+    query: &model::BoardQuery,
+) -> Result<Vec<templater::Swimlane>, CustomError> {
+    let projects = scope.projects(store).await?;
+    let tasks = board_tasks(store, &projects, query)
+        .await?
+        .into_iter()
+        .filter(|task| query.assignee.is_empty() || task.assignee == query.assignee)
+        .collect();
+    Ok(templater::swimlanes(states, projects, tasks, query))
+}
+
+// Everyone with a task in scope, for the assignee filter
+async fn board_assignees(
+    store: &dyn Store,
+    scope: &BoardScope,
+) -> Result<Vec<String>, CustomError> {
+    let projects = scope.projects(store).await?;
+    let tasks = board_tasks(store, &projects, &model::BoardQuery::default()).await?;
+    let mut assignees: Vec<String> = tasks
+        .into_iter()
+        .map(|task| task.assignee)
+        .filter(|assignee| !assignee.is_empty())
+        .collect();
+    assignees.sort();
+    assignees.dedup();
+    Ok(assignees)
+}
+
+// Cross-project board page for the projects in scope
+async fn swimlanes_page(
+    store: &dyn Store,
+    scope: BoardScope,
+    title: String,
+    query: model::BoardQuery,
+) -> Result<axum::response::Response, CustomError> {
+    let states = workflow_states(store, model::WorkflowEntity::Task).await?;
+    let lanes = board_swimlanes(store, &scope, &states, &query).await?;
+    let assignees = board_assignees(store, &scope).await?;
+    let template = templater::PageSwimlanesTemplate::new(
+        title,
+        scope.page_url(),
+        scope.board_url(),
+        states,
+        assignees,
+        query,
+        lanes,
+    );
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// Swimlanes of a cross-project board, reloaded after a card moves
+async fn swimlanes_table(
+    store: &dyn Store,
+    scope: BoardScope,
+    query: model::BoardQuery,
+) -> Result<axum::response::Response, CustomError> {
+    let states = workflow_states(store, model::WorkflowEntity::Task).await?;
+    let lanes = board_swimlanes(store, &scope, &states, &query).await?;
+    let template = templater::TableSwimlanesTemplate::new(scope.board_url(), lanes);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// GET /objective/:objective_id/board
+pub async fn get_objective_board(
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+    Params(query): Params<model::BoardQuery>,
+) -> Result<axum::response::Response, CustomError> {
+    let title = store.objective(objective_id).await?.title;
+    let scope = BoardScope::Objective(objective_id);
+    swimlanes_page(&*store, scope, format!("Objective: {title}"), query).await
+}
+
+// GET /objective/:objective_id/board/lanes
+pub async fn get_objective_board_lanes(
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+    Params(query): Params<model::BoardQuery>,
+) -> Result<axum::response::Response, CustomError> {
+    swimlanes_table(&*store, BoardScope::Objective(objective_id), query).await
+}
+
+// GET /theme/:theme_id/board
+pub async fn get_theme_board(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+    Params(query): Params<model::BoardQuery>,
+) -> Result<axum::response::Response, CustomError> {
+    let title = store.theme(theme_id).await?.title;
+    let scope = BoardScope::Theme(theme_id);
+    swimlanes_page(&*store, scope, format!("Theme: {title}"), query).await
+}
+
+// GET /theme/:theme_id/board/lanes
+pub async fn get_theme_board_lanes(
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+    Params(query): Params<model::BoardQuery>,
+) -> Result<axum::response::Response, CustomError> {
+    swimlanes_table(&*store, BoardScope::Theme(theme_id), query).await
+}
+
+// GET /task/:task_id
+pub async fn get_task(
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let task = store.task(task_id).await?;
+    let project = store.project(task.project_id).await?;
+    let objective = store.objective(project.objective_id).await?;
+    let theme = store.theme(objective.theme_id).await?;
+
+    let state_changes = store
+        .state_changes(model::WorkflowEntity::Task, task_id)
+        .await?;
+    let template = templater::PageTaskTemplate::new(
+        task,
+        project.title,
+        objective.objective_id,
+        objective.title,
+        theme.theme_id,
+        theme.title,
+        state_changes,
+    );
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// GET /task/:task_id/row
+pub async fn get_task_row(
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let task = store.task(task_id).await?;
+
+    let template = templater::RowTaskTemplate::new(task);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /task/:task_id/form
 pub async fn get_task_form(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(task_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let task_row = sqlx::query_as(r#"SELECT * FROM tasks WHERE task_id = $1"#)
-        .bind(&task_id)
-        .fetch_one(&pool)
-        .await;
-
-    match task_row {
-        Ok(task) => {
-            let template = templater::EditRowTaskTemplate::new(task);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template =
-                templater::ErrorTemplate::new(StatusCode::NOT_FOUND, "Task Not Found".to_string());
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let task = store.task(task_id).await?;
+
+    let states = workflow_states(&*store, model::WorkflowEntity::Task).await?;
+    let template = templater::EditRowTaskTemplate::new(task, states);
+    Ok(templater::HtmlTemplate(template).into_response())
+}
+
+// GET /measure/:measurement_id
+pub async fn get_measure(
+    Extension(store): Extension<DynStore>,
+    Path(measure_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let measurement = store.measurement(measure_id).await?;
+    let keyresult_title = store.keyresult(measurement.keyresult_id).await?.title;
+    let revisions = store.measurement_revisions(measure_id).await?;
+
+    let template = templater::PageMeasurementTemplate::new(measurement, keyresult_title, revisions);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /measure/:measurement_id/row
 pub async fn get_measure_row(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(measurement_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let measurement_row = sqlx::query_as(r#"SELECT * FROM measurements WHERE measurement_id = $1"#)
-        .bind(&measurement_id)
-        .fetch_one(&pool)
-        .await;
-
-    match measurement_row {
-        Ok(measurement) => {
-            let template = templater::RowMeasurementTemplate::new(measurement);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Measurement Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(measurement_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let measurement = store.measurement(measurement_id).await?;
+
+    let template = templater::RowMeasurementTemplate::new(measurement);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // GET /measure/:measurement_id/form
 pub async fn get_measure_form(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(measurement_id): extract::Path<i32>,
-) -> axum::response::Response {
-    let measurement_row = sqlx::query_as(r#"SELECT * FROM measurements WHERE measurement_id = $1"#)
-        .bind(&measurement_id)
-        .fetch_one(&pool)
-        .await;
-
-    match measurement_row {
-        Ok(measurement) => {
-            let template = templater::EditRowMeasurementTemplate::new(measurement);
-            templater::HtmlTemplate(template).into_response()
-        }
-        Err(_) => {
-            let template = templater::ErrorTemplate::new(
-                StatusCode::NOT_FOUND,
-                "Measurement Not Found".to_string(),
-            );
-            templater::HtmlTemplate(template).into_response()
-        }
-    }
+    Extension(store): Extension<DynStore>,
+    Path(measurement_id): Path<i32>,
+) -> Result<axum::response::Response, CustomError> {
+    let measurement = store.measurement(measurement_id).await?;
+
+    let template = templater::EditRowMeasurementTemplate::new(measurement);
+    Ok(templater::HtmlTemplate(template).into_response())
 }
 
 // For non-existent routes
-pub async fn get_error_404_page() -> impl IntoResponse {
-    let (error_code, error_message) = CustomError::BadRequest.get_error_message();
-    let template = templater::ErrorTemplate::new(error_code, error_message);
-    templater::HtmlTemplate(template).into_response()
+pub async fn get_error_404_page() -> CustomError {
+    CustomError::NotFound
 }
 
 // POST /theme
 pub async fn add_theme(
-    Extension(pool): Extension<PgPool>,
-    extract::Json(create_theme): extract::Json<model::CreateTheme>,
-) -> Redirect {
-    let _ = sqlx::query(r#"INSERT INTO themes (title) VALUES ($1);"#)
-        .bind(create_theme.title)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_theme): Payload<model::CreateTheme>,
+) -> Result<Redirect, CustomError> {
+    validation::create_theme(&mut create_theme)?;
+    store.add_theme(create_theme).await?;
 
-    Redirect::to("/theme")
+    Ok(Redirect::to("/theme"))
 }
 
 // POST /objective
 pub async fn add_objective(
-    Extension(pool): Extension<PgPool>,
-    extract::Json(create_objective): extract::Json<model::CreateObjective>,
-) -> impl IntoResponse {
-    let _ = sqlx::query(r#"INSERT INTO objectives (title, theme_id) VALUES ($1, $2);"#)
-        .bind(create_objective.title)
-        .bind(create_objective.theme_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Payload(create_objective): Payload<model::CreateObjective>,
+) -> Result<Redirect, CustomError> {
     let theme_id = create_objective.theme_id;
-    let uri = format!("/theme/{theme_id}/objectives");
-    Redirect::to(&uri)
+    let cycle_id = create_objective.cycle_id;
+    super::add_objective(&*store, create_objective).await?;
+    // Stay within the cycle the objective was added to
+    let uri = match cycle_id {
+        Some(cycle_id) => format!("/theme/{theme_id}/objectives?cycle={cycle_id}"),
+        None => format!("/theme/{theme_id}/objectives"),
+    };
+    Ok(Redirect::to(&uri))
 }
 
 // POST /keyresult
 pub async fn add_keyresult(
-    Extension(pool): Extension<PgPool>,
-    extract::Json(create_keyresult): extract::Json<model::CreateKeyResult>,
-) -> Redirect {
-    let _ = sqlx::query(r#"INSERT INTO keyresults (title, objective_id) VALUES ($1, $2);"#)
-        .bind(create_keyresult.title)
-        .bind(create_keyresult.objective_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_keyresult): Payload<model::CreateKeyResult>,
+) -> Result<Redirect, CustomError> {
+    validation::create_keyresult(&*store, &mut create_keyresult).await?;
+    let keyresult = store.add_keyresult(create_keyresult).await?;
     let uri = format!(
         "/objective/{objective_id}/keyresults",
-        objective_id = create_keyresult.objective_id
+        objective_id = keyresult.objective_id
     );
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // POST /initiative
 pub async fn add_initiative(
-    Extension(pool): Extension<PgPool>,
-    extract::Json(create_initiative): extract::Json<model::CreateInitiative>,
-) -> Redirect {
-    let _ = sqlx::query(r#"INSERT INTO initiatives (title, objective_id) VALUES ($1, $2);"#)
-        .bind(create_initiative.title)
-        .bind(create_initiative.objective_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_initiative): Payload<model::CreateInitiative>,
+) -> Result<Redirect, CustomError> {
+    validation::create_initiative(&*store, &mut create_initiative).await?;
+    let initiative = store.add_initiative(create_initiative).await?;
     let uri = format!(
         "/objective/{objective_id}/initiatives",
-        objective_id = create_initiative.objective_id
+        objective_id = initiative.objective_id
     );
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // POST /project
 pub async fn add_project(
-    Extension(pool): Extension<PgPool>,
-    extract::Json(create_project): extract::Json<model::CreateProject>,
-) -> Redirect {
-    let _ = sqlx::query(r#"INSERT INTO projects (title, objective_id) VALUES ($1, $2);"#)
-        .bind(create_project.title)
-        .bind(create_project.objective_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_project): Payload<model::CreateProject>,
+) -> Result<Redirect, CustomError> {
+    validation::create_project(&*store, &mut create_project).await?;
+    let project = store.add_project(create_project).await?;
     let uri = format!(
         "/objective/{objective_id}/projects",
-        objective_id = create_project.objective_id
+        objective_id = project.objective_id
     );
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // POST /task
 pub async fn add_task(
-    Extension(pool): Extension<PgPool>,
-    extract::Json(create_task): extract::Json<model::CreateTask>,
-) -> Redirect {
-    let _ = sqlx::query(r#"INSERT INTO tasks (title, project_id) VALUES ($1, $2);"#)
-        .bind(create_task.title)
-        .bind(create_task.project_id)
-        .fetch_all(&pool)
-        .await;
-    let uri = format!(
-        "/project/{project_id}/tasks",
-        project_id = create_task.project_id
-    );
-    Redirect::to(&uri)
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_task): Payload<model::CreateTask>,
+) -> Result<Redirect, CustomError> {
+    validation::create_task(&*store, &mut create_task).await?;
+    let task = store.add_task(create_task).await?;
+    let uri = format!("/project/{project_id}/tasks", project_id = task.project_id);
+    Ok(Redirect::to(&uri))
 }
 
 // POST /measure
 pub async fn add_measure(
-    Extension(pool): Extension<PgPool>,
-    extract::Json(create_measurement): extract::Json<model::CreateMeasurement>,
-) -> Redirect {
-    let _ = sqlx::query(r#"INSERT INTO measurements (title, keyresult_id) VALUES ($1, $2);"#)
-        .bind(create_measurement.title)
-        .bind(create_measurement.keyresult_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Payload(mut create_measurement): Payload<model::CreateMeasurement>,
+) -> Result<Redirect, CustomError> {
+    validation::create_measurement(&*store, &mut create_measurement).await?;
+    let measurement = store.add_measurement(create_measurement).await?;
     let uri = format!(
         "/keyresult/{keyresult_id}/measures",
-        keyresult_id = create_measurement.keyresult_id
+        keyresult_id = measurement.keyresult_id
     );
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // PUT /theme/:theme_id
 pub async fn update_theme(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(theme_id): extract::Path<i32>,
-    extract::Json(update_theme): extract::Json<model::UpdateTheme>,
-) -> Redirect {
-    let _: Result<model::Theme, sqlx::Error> = sqlx::query_as(
-        r#"UPDATE themes SET title=$1, theme_status=$2 WHERE theme_id=$3 RETURNING *"#,
-    )
-    .bind(update_theme.title)
-    .bind(update_theme.status)
-    .bind(theme_id)
-    .fetch_one(&pool)
-    .await;
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(mut update_theme): Payload<model::UpdateTheme>,
+) -> Result<Redirect, CustomError> {
+    validation::update_theme(&*store, theme_id, &mut update_theme).await?;
+    store
+        .save_theme(theme_id, update_theme, &changed_by)
+        .await?;
     let uri = format!("/theme/{theme_id}/row");
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // PUT /objective/:objective_id
 pub async fn update_objective(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-    extract::Json(update_objective): extract::Json<model::UpdateObjective>,
-) -> Redirect {
-    let _ = sqlx::query(r#"UPDATE objectives SET title=$1 WHERE objective_id=$2"#)
-        .bind(update_objective.title)
-        .bind(objective_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+    Payload(mut update_objective): Payload<model::UpdateObjective>,
+) -> Result<Redirect, CustomError> {
+    validation::update_objective(&*store, &mut update_objective).await?;
+    store.save_objective(objective_id, update_objective).await?;
     let uri = format!("/objective/{objective_id}/row");
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // PUT /keyresult/:keyresult_id
 pub async fn update_keyresult(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(keyresult_id): extract::Path<i32>,
-    extract::Json(update_keyresult): extract::Json<model::UpdateKeyResult>,
-) -> Redirect {
-    let _ = sqlx::query(r#"UPDATE keyresults SET title=$1 WHERE keyresult_id=$2"#)
-        .bind(update_keyresult.title)
-        .bind(keyresult_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+    Payload(mut update_keyresult): Payload<model::UpdateKeyResult>,
+) -> Result<Redirect, CustomError> {
+    validation::update_keyresult(&mut update_keyresult)?;
+    store.save_keyresult(keyresult_id, update_keyresult).await?;
     let uri = format!("/keyresult/{keyresult_id}/row");
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // PUT /initiative/:initiative_id
 pub async fn update_initiative(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(initiative_id): extract::Path<i32>,
-    extract::Json(update_initiative): extract::Json<model::UpdateInitiative>,
-) -> Redirect {
-    let _ = sqlx::query(
-        r#"UPDATE initiatives SET title=$1, initiative_status=$2 WHERE initiative_id=$3"#,
-    )
-    .bind(update_initiative.title)
-    .bind(update_initiative.status)
-    .bind(initiative_id)
-    .fetch_all(&pool)
-    .await;
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(mut update_initiative): Payload<model::UpdateInitiative>,
+) -> Result<Redirect, CustomError> {
+    validation::update_initiative(&*store, initiative_id, &mut update_initiative).await?;
+    store
+        .save_initiative(initiative_id, update_initiative, &changed_by)
+        .await?;
     let uri = format!("/initiative/{initiative_id}/row");
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // PUT /project/:project_id
 pub async fn update_project(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(project_id): extract::Path<i32>,
-    extract::Json(update_project): extract::Json<model::UpdateProject>,
-) -> Redirect {
-    let _ = sqlx::query(r#"UPDATE projects SET title=$1, project_status=$2 WHERE project_id=$3"#)
-        .bind(update_project.title)
-        .bind(update_project.status)
-        .bind(project_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(mut update_project): Payload<model::UpdateProject>,
+) -> Result<Redirect, CustomError> {
+    validation::update_project(&*store, project_id, &mut update_project).await?;
+    store
+        .save_project(project_id, update_project, &changed_by)
+        .await?;
     let uri = format!("/project/{project_id}/row");
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // PUT /task/:task_id
 pub async fn update_task(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(task_id): extract::Path<i32>,
-    extract::Json(update_task): extract::Json<model::UpdateTask>,
-) -> Redirect {
-    let _ = sqlx::query(r#"UPDATE tasks SET title=$1, task_status=$2 WHERE task_id=$3"#)
-        .bind(update_task.title)
-        .bind(update_task.status)
-        .bind(task_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+    ChangedBy(changed_by): ChangedBy,
+    Payload(mut update_task): Payload<model::UpdateTask>,
+) -> Result<Redirect, CustomError> {
+    validation::update_task(&*store, task_id, &mut update_task).await?;
+    store.save_task(task_id, update_task, &changed_by).await?;
     let uri = format!("/task/{task_id}/row");
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // PUT /measure/:measure_id
 pub async fn update_measure(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(measure_id): extract::Path<i32>,
-    extract::Json(update_measure): extract::Json<model::UpdateMeasurement>,
-) -> Redirect {
-    let _ = sqlx::query(r#"UPDATE measurements SET title=$1 WHERE measurement_id=$2;"#)
-        .bind(update_measure.title)
-        .bind(measure_id)
-        .fetch_all(&pool)
-        .await;
+    Extension(store): Extension<DynStore>,
+    Path(measure_id): Path<i32>,
+    Payload(mut update_measure): Payload<model::UpdateMeasurement>,
+) -> Result<Redirect, CustomError> {
+    validation::update_measurement(&mut update_measure)?;
+    store.save_measurement(measure_id, update_measure).await?;
     let uri = format!("/measure/{measure_id}/row");
-    Redirect::to(&uri)
+    Ok(Redirect::to(&uri))
 }
 
 // DELETE /theme/:theme_id
 pub async fn remove_theme(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(theme_id): extract::Path<i32>,
-) -> impl IntoResponse {
-    let _ = sqlx::query("DELETE FROM themes WHERE theme_id = $1")
-        .bind(theme_id)
-        .execute(&pool)
-        .await;
-    (StatusCode::OK, "")
+    Extension(store): Extension<DynStore>,
+    Path(theme_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_theme(theme_id).await?;
+    Ok((StatusCode::OK, ""))
 }
 
 // DELETE /objective/:objective_id
 pub async fn remove_objective(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(objective_id): extract::Path<i32>,
-) -> impl IntoResponse {
-    let _ = sqlx::query("DELETE FROM objectives WHERE objective_id = $1")
-        .bind(objective_id)
-        .execute(&pool)
-        .await;
-    (StatusCode::OK, "")
+    Extension(store): Extension<DynStore>,
+    Path(objective_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_objective(objective_id).await?;
+    Ok((StatusCode::OK, ""))
 }
 
 // DELETE /keyresult/:keyresult_id
 pub async fn remove_keyresult(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(keyresult_id): extract::Path<i32>,
-) -> impl IntoResponse {
-    let _ = sqlx::query("DELETE FROM keyresults WHERE keyresult_id = $1")
-        .bind(keyresult_id)
-        .execute(&pool)
-        .await;
-    (StatusCode::OK, "")
+    Extension(store): Extension<DynStore>,
+    Path(keyresult_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_keyresult(keyresult_id).await?;
+    Ok((StatusCode::OK, ""))
 }
 
 // DELETE /initiative/:initiative_id
 pub async fn remove_initiative(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(initiative_id): extract::Path<i32>,
-) -> impl IntoResponse {
-    let _ = sqlx::query("DELETE FROM initiatives WHERE initiative_id = $1")
-        .bind(initiative_id)
-        .execute(&pool)
-        .await;
-    (StatusCode::OK, "")
+    Extension(store): Extension<DynStore>,
+    Path(initiative_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_initiative(initiative_id).await?;
+    Ok((StatusCode::OK, ""))
 }
 
 // DELETE /project/:project_id
 pub async fn remove_project(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(project_id): extract::Path<i32>,
-) -> impl IntoResponse {
-    let _ = sqlx::query("DELETE FROM projects WHERE project_id = $1")
-        .bind(project_id)
-        .execute(&pool)
-        .await;
-    (StatusCode::OK, "")
+    Extension(store): Extension<DynStore>,
+    Path(project_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_project(project_id).await?;
+    Ok((StatusCode::OK, ""))
 }
 
 // DELETE /task/:task_id
 pub async fn remove_task(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(task_id): extract::Path<i32>,
-) -> impl IntoResponse {
-    let _ = sqlx::query("DELETE FROM tasks WHERE task_id = $1")
-        .bind(task_id)
-        .execute(&pool)
-        .await;
-    (StatusCode::OK, "")
+    Extension(store): Extension<DynStore>,
+    Path(task_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_task(task_id).await?;
+    Ok((StatusCode::OK, ""))
 }
 
 // DELETE /measure/:measure_id
 pub async fn remove_measure(
-    Extension(pool): Extension<PgPool>,
-    extract::Path(measure_id): extract::Path<i32>,
-) -> impl IntoResponse {
-    let _ = sqlx::query("DELETE FROM measurements WHERE measurement_id = $1")
-        .bind(measure_id)
-        .execute(&pool)
-        .await;
-    (StatusCode::OK, "")
+    Extension(store): Extension<DynStore>,
+    Path(measure_id): Path<i32>,
+) -> Result<impl IntoResponse, CustomError> {
+    store.remove_measurement(measure_id).await?;
+    Ok((StatusCode::OK, ""))
 }
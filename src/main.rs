@@ -1,28 +1,55 @@
 use axum::extract::Extension;
-// use axum::http::{StatusCode, Uri};
 use axum::{
-    routing::{get, post, put},
+    middleware,
+    routing::{delete, get, post, put},
     Router,
 };
 
-use sqlx::postgres::PgPoolOptions;
-use std::fs;
-use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
 use tower::ServiceBuilder;
 use tower_http::services::fs::ServeDir;
 use tower_http::trace;
-use tracing;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+pub mod chart;
+pub mod config;
+use config::{Command, Config};
 pub mod errors;
 pub mod handlers;
 use handlers::{data, hypermedia};
+pub mod markdown;
 pub mod model;
+pub mod store;
+use store::migrate;
 pub mod templater;
+pub mod validation;
+use validation::TrustForwardedUser;
+
+#[cfg(test)]
+mod tests;
 
 fn get_hypermedia_routes() -> Router {
-    let hypermedia_router = Router::new()
+    Router::new()
         .route("/", get(hypermedia::get_root))
+        .route("/overdue", get(hypermedia::get_overdue))
+        .route("/cycles", get(hypermedia::get_cycles_page))
+        .route(
+            "/cycle",
+            get(hypermedia::get_cycles).post(hypermedia::add_cycle),
+        )
+        .route("/cycle/:cycle_id", delete(hypermedia::remove_cycle))
+        .route(
+            "/cycle/:cycle_id/carry_over",
+            post(hypermedia::carry_over_cycle),
+        )
+        .route("/workflows", get(hypermedia::get_workflows_page))
+        .route("/workflow/:entity", get(hypermedia::get_workflow))
+        .route("/workflow/state", post(hypermedia::add_workflow_state))
+        .route(
+            "/workflow/state/:state_id",
+            put(hypermedia::update_workflow_state).delete(hypermedia::remove_workflow_state),
+        )
         .route(
             "/theme",
             get(hypermedia::get_root_themes).post(hypermedia::add_theme),
@@ -38,6 +65,11 @@ fn get_hypermedia_routes() -> Router {
             get(hypermedia::get_theme_objectives),
         )
         .route("/theme/:theme_id/row", get(hypermedia::get_theme_row))
+        .route("/theme/:theme_id/board", get(hypermedia::get_theme_board))
+        .route(
+            "/theme/:theme_id/board/lanes",
+            get(hypermedia::get_theme_board_lanes),
+        )
         .route("/theme/:theme_id/form", get(hypermedia::get_theme_form))
         .route("/objective", post(hypermedia::add_objective))
         .route(
@@ -46,6 +78,14 @@ fn get_hypermedia_routes() -> Router {
                 .put(hypermedia::update_objective)
                 .delete(hypermedia::remove_objective),
         )
+        .route(
+            "/objective/:objective_id/board",
+            get(hypermedia::get_objective_board),
+        )
+        .route(
+            "/objective/:objective_id/board/lanes",
+            get(hypermedia::get_objective_board_lanes),
+        )
         .route(
             "/objective/:objective_id/keyresults",
             get(hypermedia::get_objective_keyresults),
@@ -116,24 +156,35 @@ fn get_hypermedia_routes() -> Router {
             "/project/:project_id/tasks",
             get(hypermedia::get_project_tasks),
         )
+        .route(
+            "/project/:project_id/board",
+            get(hypermedia::get_project_board),
+        )
+        .route(
+            "/project/:project_id/board/columns",
+            get(hypermedia::get_project_board_columns),
+        )
         .route("/task", post(hypermedia::add_task))
         .route(
             "/task/:task_id",
-            put(hypermedia::update_task).delete(hypermedia::remove_task),
+            get(hypermedia::get_task)
+                .put(hypermedia::update_task)
+                .delete(hypermedia::remove_task),
         )
         .route("/task/:task_id/row", get(hypermedia::get_task_row))
         .route("/task/:task_id/form", get(hypermedia::get_task_form))
         .route("/measure", post(hypermedia::add_measure))
         .route(
             "/measure/:measure_id",
-            put(hypermedia::update_measure).delete(hypermedia::remove_measure),
+            get(hypermedia::get_measure)
+                .put(hypermedia::update_measure)
+                .delete(hypermedia::remove_measure),
         )
         .route("/measure/:measure_id/row", get(hypermedia::get_measure_row))
         .route(
             "/measure/:measure_id/form",
             get(hypermedia::get_measure_form),
-        );
-    hypermedia_router
+        )
 }
 
 // fn get_static_asset_routes() -> Router {
@@ -148,68 +199,270 @@ fn get_hypermedia_routes() -> Router {
 // }
 
 fn get_data_routes() -> Router {
-    let data_router = Router::new()
-        .route("/theme", get(data::get_all_themes))
-        .route("/objective", get(data::get_all_objectives))
-        .route("/keyresult", get(data::get_all_keyresults))
-        .route("/initiative", get(data::get_all_initiatives))
-        .route("/project", get(data::get_all_projects))
-        .route("/task", get(data::get_all_tasks))
-        .route("/measure", get(data::get_all_measures));
-    data_router
+    Router::new()
+        .route("/tree", get(data::get_tree))
+        .route("/theme/:theme_id/tree", get(data::get_theme_tree))
+        .route(
+            "/objective/:objective_id/tree",
+            get(data::get_objective_tree),
+        )
+        .route("/cycle", get(data::get_all_cycles).post(data::add_cycle))
+        .route(
+            "/cycle/:cycle_id",
+            get(data::get_cycle)
+                .put(data::update_cycle)
+                .patch(data::patch_cycle)
+                .delete(data::remove_cycle),
+        )
+        .route("/cycle/:cycle_id/carry_over", post(data::carry_over_cycle))
+        .route("/workflow", get(data::get_workflows))
+        .route("/workflow/state", post(data::add_workflow_state))
+        .route(
+            "/workflow/state/:state_id",
+            get(data::get_workflow_state)
+                .put(data::update_workflow_state)
+                .patch(data::patch_workflow_state)
+                .delete(data::remove_workflow_state),
+        )
+        .route("/theme", get(data::get_all_themes).post(data::add_theme))
+        .route(
+            "/theme/:theme_id",
+            get(data::get_theme)
+                .put(data::update_theme)
+                .patch(data::patch_theme)
+                .delete(data::remove_theme),
+        )
+        .route("/theme/:theme_id/history", get(data::get_theme_history))
+        .route(
+            "/objective",
+            get(data::get_all_objectives).post(data::add_objective),
+        )
+        .route(
+            "/objective/:objective_id",
+            get(data::get_objective)
+                .put(data::update_objective)
+                .patch(data::patch_objective)
+                .delete(data::remove_objective),
+        )
+        .route(
+            "/keyresult",
+            get(data::get_all_keyresults).post(data::add_keyresult),
+        )
+        .route(
+            "/keyresult/:keyresult_id",
+            get(data::get_keyresult)
+                .put(data::update_keyresult)
+                .patch(data::patch_keyresult)
+                .delete(data::remove_keyresult),
+        )
+        .route(
+            "/initiative",
+            get(data::get_all_initiatives).post(data::add_initiative),
+        )
+        .route(
+            "/initiative/:initiative_id",
+            get(data::get_initiative)
+                .put(data::update_initiative)
+                .patch(data::patch_initiative)
+                .delete(data::remove_initiative),
+        )
+        .route(
+            "/initiative/:initiative_id/history",
+            get(data::get_initiative_history),
+        )
+        .route(
+            "/project",
+            get(data::get_all_projects).post(data::add_project),
+        )
+        .route(
+            "/project/:project_id",
+            get(data::get_project)
+                .put(data::update_project)
+                .patch(data::patch_project)
+                .delete(data::remove_project),
+        )
+        .route(
+            "/project/:project_id/history",
+            get(data::get_project_history),
+        )
+        .route("/task", get(data::get_all_tasks).post(data::add_task))
+        .route(
+            "/task/:task_id",
+            get(data::get_task)
+                .put(data::update_task)
+                .patch(data::patch_task)
+                .delete(data::remove_task),
+        )
+        .route("/task/:task_id/history", get(data::get_task_history))
+        .route(
+            "/measure",
+            get(data::get_all_measures).post(data::add_measure),
+        )
+        .route(
+            "/measure/:measure_id",
+            get(data::get_measure)
+                .put(data::update_measure)
+                .patch(data::patch_measure)
+                .delete(data::remove_measure),
+        )
 }
 
-// async fn fallback(uri: Uri) -> (StatusCode, String) {
-//     (StatusCode::NOT_FOUND, format!("No route for {}", uri))
-// }
-
-#[tokio::main]
-async fn main() -> Result<(), String> {
-    // start logging
-    let tracing_layer = tracing_subscriber::fmt::layer();
-
-    let filter = tracing_subscriber::filter::Targets::new()
-        .with_target("tower_http::trace::on_response", tracing::Level::TRACE)
-        .with_target("tower_http::trace::on_request", tracing::Level::TRACE)
-        .with_target("tower_http::trace::make_span", tracing::Level::DEBUG)
-        .with_default(tracing::Level::INFO);
-
-    tracing_subscriber::registry()
-        .with(tracing_layer)
-        .with(filter)
-        .init();
-
-    // open appstate.json
-    let env = fs::read_to_string(".env").unwrap();
-    let (key, database_url) = env.split_once('=').unwrap();
-    assert_eq!(key, "DATABASE_URL");
-
-    let pool = PgPoolOptions::new()
-        .max_connections(50)
-        .connect(&database_url)
-        .await
-        .map_err(|err| format!("ERROR: Could not connect to Postgres database: {err}"))?;
-
-    // Serve
-    let app = Router::new()
+// Every route, serving from the given store
+fn app(store: store::DynStore, static_dir: &Path, trust: TrustForwardedUser) -> Router {
+    Router::new()
         .nest("/", get_hypermedia_routes())
         .nest("/api", get_data_routes())
-        .nest_service("/static", ServeDir::new("static"))
-        // .fallback(hypermedia::get_error_404_page)
+        .nest_service("/static", ServeDir::new(static_dir))
+        .fallback(hypermedia::get_error_404_page)
         .layer(
             ServiceBuilder::new()
-                .layer(Extension(pool))
-                .layer(trace::TraceLayer::new_for_http()),
-        );
+                .layer(Extension(store))
+                .layer(Extension(trust))
+                .layer(trace::TraceLayer::new_for_http())
+                .layer(middleware::from_fn(errors::negotiate)),
+        )
+}
+
+fn memory_store(config: &Config) -> Result<Option<store::DynStore>, String> {
+    let store = match &config.fixture {
+        Some(path) => store::memory::MemoryStore::load(path)
+            .map_err(|err| format!("Could not load fixture: {err}"))?,
+        None if config.memory => store::memory::MemoryStore::new(),
+        None => return Ok(None),
+    };
+    tracing::info!("keeping data in memory, nothing is saved");
+    Ok(Some(Arc::new(store)))
+}
+
+enum Database {
+    Postgres(store::postgres::PgStore),
+    Sqlite(store::sqlite::SqliteStore),
+}
+
+impl Database {
+    async fn open(config: &Config) -> Result<Database, String> {
+        let database_url = config.database_url()?;
+        // sqlite: URLs open (or create) a single database file, anything else is
+        // taken to be Postgres
+        if database_url.starts_with("sqlite:") {
+            let store = store::sqlite::SqliteStore::connect(
+                database_url,
+                config.max_connections,
+                config.acquire_timeout(),
+            )
+            .await
+            .map_err(|err| format!("Could not open SQLite database: {err}"))?;
+            Ok(Database::Sqlite(store))
+        } else {
+            let store = store::postgres::PgStore::connect(
+                database_url,
+                config.max_connections,
+                config.acquire_timeout(),
+            )
+            .await
+            .map_err(|err| format!("Could not connect to Postgres database: {err}"))?;
+            Ok(Database::Postgres(store))
+        }
+    }
+
+    async fn migration_status(&self) -> Result<Vec<migrate::MigrationStatus>, String> {
+        match self {
+            Database::Postgres(store) => store.migration_status().await,
+            Database::Sqlite(store) => store.migration_status().await,
+        }
+    }
+
+    // Applies pending migrations, or with --no-migrate refuses to use a
+    // database that has any
+    async fn migrate(self, config: &Config) -> Result<store::DynStore, String> {
+        if config.no_migrate {
+            let pending = self
+                .migration_status()
+                .await?
+                .into_iter()
+                .filter(|status| status.state != migrate::State::Applied)
+                .count();
+            if pending > 0 {
+                return Err(format!(
+                    "The database has {pending} migration(s) pending or unknown to this version. \
+                     See `tokip migration-status`, or start without --no-migrate to apply them"
+                ));
+            }
+        } else {
+            let migrated = match &self {
+                Database::Postgres(store) => store.migrate().await,
+                Database::Sqlite(store) => store.migrate().await,
+            };
+            migrated.map_err(|err| format!("Could not migrate the database: {err}"))?;
+        }
+        Ok(match self {
+            Database::Postgres(store) => Arc::new(store),
+            Database::Sqlite(store) => Arc::new(store),
+        })
+    }
+
+    async fn print_migration_status(&self) -> Result<(), String> {
+        let statuses = self.migration_status().await?;
+        println!("{:>7}  {:<24}  Status", "Version", "Description");
+        for status in statuses {
+            println!(
+                "{:>7}  {:<24}  {}",
+                status.version, status.description, status.state
+            );
+        }
+        Ok(())
+    }
+}
+
+async fn run(config: Config) -> Result<(), String> {
+    if let Some(Command::MigrationStatus) = config.command {
+        return Database::open(&config)
+            .await?
+            .print_migration_status()
+            .await;
+    }
+    if !config.static_dir.is_dir() {
+        return Err(format!(
+            "Static directory {} does not exist. Run from the project directory or set \
+             --static-dir",
+            config.static_dir.display()
+        ));
+    }
+    let store = match memory_store(&config)? {
+        Some(store) => store,
+        None => Database::open(&config).await?.migrate(&config).await?,
+    };
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
-    tracing::info!("listening on {}", addr);
+    // Serve
+    let app = app(
+        store,
+        &config.static_dir,
+        TrustForwardedUser(config.trust_forwarded_user),
+    );
 
-    // run our app with hyper, listening globally on port 3000
-    axum::Server::bind(&addr)
+    let server = axum::Server::try_bind(&config.listen)
+        .map_err(|err| format!("Could not listen on {}: {err}", config.listen))?;
+    tracing::info!("listening on {}", config.listen);
+    server
         .serve(app.into_make_service())
         .await
-        .unwrap();
+        .map_err(|err| format!("Server stopped: {err}"))
+}
 
-    Ok(())
+#[tokio::main]
+async fn main() {
+    let result = match Config::load() {
+        Ok(config) => {
+            tracing_subscriber::registry()
+                .with(tracing_subscriber::fmt::layer())
+                .with(config.log.clone())
+                .init();
+            run(config).await
+        }
+        Err(err) => Err(err),
+    };
+    if let Err(err) = result {
+        eprintln!("ERROR: {err}");
+        std::process::exit(1);
+    }
 }
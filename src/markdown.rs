@@ -0,0 +1,14 @@
+use pulldown_cmark::{html, Options, Parser};
+
+/// Renders a Markdown description to HTML that is safe to embed in a page.
+/// Raw HTML in the source is allowed through `ammonia`, which strips scripts,
+/// event handlers and anything else that could run in the browser.
+pub fn to_html(markdown: &str) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer"))
+        .clean(&unsafe_html)
+        .to_string()
+}
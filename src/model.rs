@@ -5,29 +5,136 @@ use sqlx::FromRow;
 pub struct Theme {
     pub theme_id: i32,
     pub title: String,
+    pub description: String,
     // objectives: Vec<i32>,
-    pub theme_status: Status,
+    pub theme_state_id: i32,
+    #[sqlx(flatten)]
+    pub state: CurrentState,
+    // Why the item is blocked, empty unless its state requires a reason
+    pub blocked_reason: String,
+    // Average score of the theme's objectives, filled in by the handlers
+    #[sqlx(default)]
+    pub score: Option<f64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct CreateTheme {
     pub title: String,
+    #[serde(default)]
+    pub description: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct UpdateTheme {
     pub title: String,
-    pub status: Status,
+    // Left unchanged when missing
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(deserialize_with = "de::id")]
+    pub state_id: i32,
+    // Left unchanged when missing. Required when the state requires a reason,
+    // cleared otherwise
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
+}
+
+impl From<Theme> for UpdateTheme {
+    fn from(theme: Theme) -> Self {
+        UpdateTheme {
+            title: theme.title,
+            description: Some(theme.description),
+            state_id: theme.theme_state_id,
+            blocked_reason: Some(theme.blocked_reason),
+        }
+    }
+}
+
+/// A period objectives are set for, e.g. a quarter.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct Cycle {
+    pub cycle_id: i32,
+    pub title: String,
+    pub start_date: chrono::NaiveDate,
+    pub end_date: chrono::NaiveDate,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Cycle {
+    pub fn is_current(&self) -> bool {
+        (self.start_date..=self.end_date).contains(&today())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct CreateCycle {
+    pub title: String,
+    #[serde(deserialize_with = "de::date")]
+    pub start_date: chrono::NaiveDate,
+    #[serde(deserialize_with = "de::date")]
+    pub end_date: chrono::NaiveDate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct UpdateCycle {
+    pub title: String,
+    #[serde(deserialize_with = "de::date")]
+    pub start_date: chrono::NaiveDate,
+    #[serde(deserialize_with = "de::date")]
+    pub end_date: chrono::NaiveDate,
+}
+
+impl From<Cycle> for UpdateCycle {
+    fn from(cycle: Cycle) -> Self {
+        UpdateCycle {
+            title: cycle.title,
+            start_date: cycle.start_date,
+            end_date: cycle.end_date,
+        }
+    }
+}
+
+/// What a carry over copied from one cycle into the next.
+#[derive(Debug, Serialize, Clone)]
+pub struct CarryOver {
+    pub from_cycle: Cycle,
+    pub to_cycle: Cycle,
+    pub objectives: usize,
+    pub keyresults: usize,
+}
+
+/// Which cycle's objectives to show: `all`, `current`, `previous` or a cycle id
+/// in the `cycle` query parameter.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum CycleFilter {
+    #[default]
+    All,
+    Current,
+    Previous,
+    Id(i32),
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct CycleQuery {
+    #[serde(default, deserialize_with = "de::cycle_filter")]
+    pub cycle: CycleFilter,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Objective {
     pub objective_id: i32,
     pub title: String,
+    pub description: String,
     // key_results: Vec<i32>,
     // initiatives: Vec<i32>,
     // projects: Vec<i32>,
     pub theme_id: i32,
+    pub cycle_id: Option<i32>,
+    // The objective this one was carried over from
+    pub carried_from: Option<i32>,
+    // Weighted score of the objective's key results, filled in by the handlers
+    #[sqlx(default)]
+    pub score: Option<f64>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
@@ -35,107 +142,361 @@ pub struct Objective {
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct CreateObjective {
     pub title: String,
+    #[serde(default)]
+    pub description: String,
     pub theme_id: i32,
+    // Defaults to the current cycle, if there is one
+    #[serde(default, deserialize_with = "de::optional_id")]
+    pub cycle_id: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct UpdateObjective {
     pub title: String,
+    // Left unchanged when missing
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "de::optional_id")]
+    pub cycle_id: Option<i32>,
+}
+
+impl From<Objective> for UpdateObjective {
+    fn from(objective: Objective) -> Self {
+        UpdateObjective {
+            title: objective.title,
+            description: Some(objective.description),
+            cycle_id: objective.cycle_id,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct KeyResult {
     pub keyresult_id: i32,
     pub title: String,
+    pub description: String,
     pub objective_id: i32,
     // measurements: Vec<String>,
+    pub baseline: f64,
+    pub target: f64,
+    pub unit: String,
+    pub direction: Direction,
+    pub weight: f64,
+    // Value of the most recent measurement, only present when the query selects it
+    #[sqlx(default)]
+    pub latest_value: Option<f64>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+impl KeyResult {
+    /// Progress towards the target as a percentage between 0 and 100, based on
+    /// the latest measurement. `None` until something has been measured.
+    pub fn progress(&self) -> Option<f64> {
+        self.latest_value
+            .map(|value| self.direction.progress(self.baseline, self.target, value))
+    }
+}
+
+/// Score of an objective: the average progress of its key results, weighted by
+/// each key result's weight. Key results without measurements count as 0%.
+pub fn objective_score<'a>(keyresults: impl IntoIterator<Item = &'a KeyResult>) -> Option<f64> {
+    let (weighted_progress, total_weight) = keyresults.into_iter().fold(
+        (0.0, 0.0),
+        |(weighted_progress, total_weight), keyresult| {
+            (
+                weighted_progress + keyresult.progress().unwrap_or(0.0) * keyresult.weight,
+                total_weight + keyresult.weight,
+            )
+        },
+    );
+    (total_weight > 0.0).then(|| weighted_progress / total_weight)
+}
+
+/// Score of a theme: the plain average of its scored objectives.
+pub fn theme_score<'a>(objectives: impl IntoIterator<Item = &'a Objective>) -> Option<f64> {
+    let scores: Vec<f64> = objectives
+        .into_iter()
+        .filter_map(|objective| objective.score)
+        .collect();
+    (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64)
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct CreateKeyResult {
     pub title: String,
+    #[serde(default)]
+    pub description: String,
     pub objective_id: i32,
+    #[serde(deserialize_with = "de::number")]
+    pub baseline: f64,
+    #[serde(deserialize_with = "de::number")]
+    pub target: f64,
+    #[serde(default)]
+    pub unit: String,
+    pub direction: Direction,
+    #[serde(default = "default_weight", deserialize_with = "de::number")]
+    pub weight: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct UpdateKeyResult {
     pub title: String,
+    // Left unchanged when missing
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(deserialize_with = "de::number")]
+    pub baseline: f64,
+    #[serde(deserialize_with = "de::number")]
+    pub target: f64,
+    #[serde(default)]
+    pub unit: String,
+    pub direction: Direction,
+    #[serde(default = "default_weight", deserialize_with = "de::number")]
+    pub weight: f64,
+}
+
+impl From<KeyResult> for UpdateKeyResult {
+    fn from(keyresult: KeyResult) -> Self {
+        UpdateKeyResult {
+            title: keyresult.title,
+            description: Some(keyresult.description),
+            baseline: keyresult.baseline,
+            target: keyresult.target,
+            unit: keyresult.unit,
+            direction: keyresult.direction,
+            weight: keyresult.weight,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Initiative {
     pub initiative_id: i32,
     pub title: String,
+    pub description: String,
     pub objective_id: i32,
-    // explanation: String,
-    pub initiative_status: Status,
+    pub initiative_state_id: i32,
+    #[sqlx(flatten)]
+    pub state: CurrentState,
+    // Why the item is blocked, empty unless its state requires a reason
+    pub blocked_reason: String,
+    pub start_date: Option<chrono::NaiveDate>,
+    pub due_date: Option<chrono::NaiveDate>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+impl Initiative {
+    pub fn deadline(&self) -> Deadline {
+        Deadline::of(self.due_date, &self.state.category)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct CreateInitiative {
     pub title: String,
+    #[serde(default)]
+    pub description: String,
     pub objective_id: i32,
+    #[serde(default, deserialize_with = "de::optional_date")]
+    pub start_date: Option<chrono::NaiveDate>,
+    #[serde(default, deserialize_with = "de::optional_date")]
+    pub due_date: Option<chrono::NaiveDate>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct UpdateInitiative {
     pub title: String,
-    pub status: Status,
+    // Left unchanged when missing
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(deserialize_with = "de::id")]
+    pub state_id: i32,
+    // Left unchanged when missing. Required when the state requires a reason,
+    // cleared otherwise
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
+    // Left unchanged when missing, cleared when null or empty
+    #[serde(default, deserialize_with = "de::date_change")]
+    pub start_date: Option<Option<chrono::NaiveDate>>,
+    #[serde(default, deserialize_with = "de::date_change")]
+    pub due_date: Option<Option<chrono::NaiveDate>>,
+}
+
+impl From<Initiative> for UpdateInitiative {
+    fn from(initiative: Initiative) -> Self {
+        UpdateInitiative {
+            title: initiative.title,
+            description: Some(initiative.description),
+            state_id: initiative.initiative_state_id,
+            blocked_reason: Some(initiative.blocked_reason),
+            start_date: Some(initiative.start_date),
+            due_date: Some(initiative.due_date),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Project {
     pub project_id: i32,
     pub title: String,
-    pub project_status: Status,
+    pub description: String,
+    pub project_state_id: i32,
+    #[sqlx(flatten)]
+    pub state: CurrentState,
+    // Why the item is blocked, empty unless its state requires a reason
+    pub blocked_reason: String,
     pub objective_id: i32,
+    pub start_date: Option<chrono::NaiveDate>,
+    pub due_date: Option<chrono::NaiveDate>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+impl Project {
+    pub fn deadline(&self) -> Deadline {
+        Deadline::of(self.due_date, &self.state.category)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct CreateProject {
     pub title: String,
+    #[serde(default)]
+    pub description: String,
     pub objective_id: i32,
+    #[serde(default, deserialize_with = "de::optional_date")]
+    pub start_date: Option<chrono::NaiveDate>,
+    #[serde(default, deserialize_with = "de::optional_date")]
+    pub due_date: Option<chrono::NaiveDate>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct UpdateProject {
     pub title: String,
-    pub status: Status
+    // Left unchanged when missing
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(deserialize_with = "de::id")]
+    pub state_id: i32,
+    // Left unchanged when missing. Required when the state requires a reason,
+    // cleared otherwise
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
+    // Left unchanged when missing, cleared when null or empty
+    #[serde(default, deserialize_with = "de::date_change")]
+    pub start_date: Option<Option<chrono::NaiveDate>>,
+    #[serde(default, deserialize_with = "de::date_change")]
+    pub due_date: Option<Option<chrono::NaiveDate>>,
+}
+
+impl From<Project> for UpdateProject {
+    fn from(project: Project) -> Self {
+        UpdateProject {
+            title: project.title,
+            description: Some(project.description),
+            state_id: project.project_state_id,
+            blocked_reason: Some(project.blocked_reason),
+            start_date: Some(project.start_date),
+            due_date: Some(project.due_date),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Task {
     pub task_id: i32,
     pub title: String,
-    pub task_status: Status,
+    pub task_state_id: i32,
+    #[sqlx(flatten)]
+    pub state: CurrentState,
+    // Why the item is blocked, empty unless its state requires a reason
+    pub blocked_reason: String,
+    // Who is working on the task, empty when nobody is
+    pub assignee: String,
     pub project_id: i32,
+    pub description: String,
+    pub start_date: Option<chrono::NaiveDate>,
+    pub due_date: Option<chrono::NaiveDate>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+impl Task {
+    pub fn deadline(&self) -> Deadline {
+        Deadline::of(self.due_date, &self.state.category)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct CreateTask {
     pub title: String,
     pub project_id: i32,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub assignee: String,
+    #[serde(default, deserialize_with = "de::optional_date")]
+    pub start_date: Option<chrono::NaiveDate>,
+    #[serde(default, deserialize_with = "de::optional_date")]
+    pub due_date: Option<chrono::NaiveDate>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct UpdateTask {
     pub title: String,
-    pub status: Status
+    #[serde(deserialize_with = "de::id")]
+    pub state_id: i32,
+    // Left unchanged when missing. Required when the state requires a reason,
+    // cleared otherwise
+    #[serde(default)]
+    pub blocked_reason: Option<String>,
+    // Left unchanged when missing
+    #[serde(default)]
+    pub description: Option<String>,
+    // Left unchanged when missing, cleared when null or empty
+    #[serde(default, deserialize_with = "de::date_change")]
+    pub start_date: Option<Option<chrono::NaiveDate>>,
+    #[serde(default, deserialize_with = "de::date_change")]
+    pub due_date: Option<Option<chrono::NaiveDate>>,
+    // Left unchanged when missing
+    #[serde(default)]
+    pub assignee: Option<String>,
+}
+
+impl From<Task> for UpdateTask {
+    fn from(task: Task) -> Self {
+        UpdateTask {
+            title: task.title,
+            state_id: task.task_state_id,
+            blocked_reason: Some(task.blocked_reason),
+            description: Some(task.description),
+            start_date: Some(task.start_date),
+            due_date: Some(task.due_date),
+            assignee: Some(task.assignee),
+        }
+    }
+}
+
+/// Something that happened to an item, as listed on its page.
+#[derive(Debug, Serialize, Clone)]
+pub struct Activity {
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub summary: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct Measurement {
     pub measurement_id: i32,
     pub title: String,
+    pub value: f64,
+    pub unit: String,
+    pub observed_at: chrono::DateTime<chrono::Utc>,
     pub keyresult_id: i32,
+    pub notes: String,
+    // Where the value came from, e.g. a dashboard or report
+    pub source: String,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
     pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
@@ -143,28 +504,685 @@ pub struct Measurement {
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct CreateMeasurement {
     pub title: String,
+    #[serde(deserialize_with = "de::number")]
+    pub value: f64,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default, deserialize_with = "de::optional_datetime")]
+    pub observed_at: Option<chrono::DateTime<chrono::Utc>>,
     pub keyresult_id: i32,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub source: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
 pub struct UpdateMeasurement {
     pub title: String,
+    #[serde(deserialize_with = "de::number")]
+    pub value: f64,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default, deserialize_with = "de::optional_datetime")]
+    pub observed_at: Option<chrono::DateTime<chrono::Utc>>,
+    // Left unchanged when missing
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+impl From<Measurement> for UpdateMeasurement {
+    fn from(measurement: Measurement) -> Self {
+        UpdateMeasurement {
+            title: measurement.title,
+            value: measurement.value,
+            unit: measurement.unit,
+            observed_at: Some(measurement.observed_at),
+            notes: Some(measurement.notes),
+            source: Some(measurement.source),
+        }
+    }
+}
+
+/// Items due within this many days are highlighted as due soon.
+pub const DUE_SOON_DAYS: i64 = 3;
+
+/// How an item stands against its due date, as of today.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Deadline {
+    // No due date, or the work is already done
+    None,
+    Upcoming,
+    DueSoon,
+    Overdue,
+}
+
+impl Deadline {
+    pub fn of(due_date: Option<chrono::NaiveDate>, category: &StateCategory) -> Deadline {
+        let due_date = match due_date {
+            Some(due_date) if *category != StateCategory::Done => due_date,
+            _ => return Deadline::None,
+        };
+        let days_left = (due_date - today()).num_days();
+        if days_left < 0 {
+            Deadline::Overdue
+        } else if days_left <= DUE_SOON_DAYS {
+            Deadline::DueSoon
+        } else {
+            Deadline::Upcoming
+        }
+    }
+}
+
+// Due dates are calendar days, compared against the current day in UTC
+pub fn today() -> chrono::NaiveDate {
+    chrono::Utc::now().date_naive()
+}
+
+/// A task, project or initiative that is past its due date and still open. The
+/// parent is the task's project, or the objective of a project or initiative.
+#[derive(Debug, Serialize, Clone, FromRow)]
+pub struct OverdueItem {
+    // "task", "project" or "initiative", as used in the item's URL
+    pub kind: String,
+    pub id: i32,
+    pub title: String,
+    // Title of the item's workflow state
+    pub state: String,
+    pub due_date: chrono::NaiveDate,
+    pub parent_kind: String,
+    pub parent_id: i32,
+    pub parent_title: String,
+}
+
+impl OverdueItem {
+    pub fn days_overdue(&self) -> i64 {
+        (today() - self.due_date).num_days()
+    }
+}
+
+/// A value a measurement had before it was corrected.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct MeasurementRevision {
+    pub revision_id: i32,
+    pub measurement_id: i32,
+    pub value: f64,
+    pub unit: String,
+    pub observed_at: chrono::DateTime<chrono::Utc>,
+    pub revised_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The kinds of item that move through a workflow.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "workflow_entity")]
+pub enum WorkflowEntity {
+    Theme,
+    Initiative,
+    Project,
+    Task,
+}
+
+impl WorkflowEntity {
+    pub const ALL: [WorkflowEntity; 4] = [
+        WorkflowEntity::Theme,
+        WorkflowEntity::Initiative,
+        WorkflowEntity::Project,
+        WorkflowEntity::Task,
+    ];
+}
+
+impl std::fmt::Display for WorkflowEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            WorkflowEntity::Theme => f.write_str("Theme"),
+            WorkflowEntity::Initiative => f.write_str("Initiative"),
+            WorkflowEntity::Project => f.write_str("Project"),
+            WorkflowEntity::Task => f.write_str("Task"),
+        }
+    }
+}
+
+/// How a workflow state counts in rollups: not started yet, being worked on,
+/// or finished one way or another.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "state_category")]
+pub enum StateCategory {
+    Open,
+    Active,
+    Done,
+}
+
+impl StateCategory {
+    pub const ALL: [StateCategory; 3] = [
+        StateCategory::Open,
+        StateCategory::Active,
+        StateCategory::Done,
+    ];
+}
+
+impl std::fmt::Display for StateCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StateCategory::Open => f.write_str("Open"),
+            StateCategory::Active => f.write_str("Active"),
+            StateCategory::Done => f.write_str("Done"),
+        }
+    }
+}
+
+/// A state in the workflow of one kind of item, as set up by an admin.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct WorkflowState {
+    pub state_id: i32,
+    pub entity: WorkflowEntity,
+    pub title: String,
+    pub category: StateCategory,
+    pub position: i32,
+    // Items in this state must say why, e.g. when blocked
+    pub requires_reason: bool,
+    // States an item may move on to, selected by SELECT_WORKFLOW_STATES
+    #[sqlx(default)]
+    pub next_state_ids: Vec<i32>,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub updated_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl WorkflowState {
+    pub fn can_change_to(&self, next_state_id: i32) -> bool {
+        self.state_id == next_state_id || self.next_state_ids.contains(&next_state_id)
+    }
+}
+
+/// The workflow state an item is in, joined in by the SELECT_* queries.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct CurrentState {
+    #[sqlx(rename = "state_title")]
+    pub title: String,
+    #[sqlx(rename = "state_category")]
+    pub category: StateCategory,
+    #[sqlx(rename = "state_requires_reason")]
+    pub requires_reason: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct CreateWorkflowState {
+    pub entity: WorkflowEntity,
+    pub title: String,
+    pub category: StateCategory,
+    #[serde(default, deserialize_with = "de::checkbox")]
+    pub requires_reason: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct UpdateWorkflowState {
+    pub title: String,
+    pub category: StateCategory,
+    #[serde(deserialize_with = "de::integer")]
+    pub position: i32,
+    #[serde(default, deserialize_with = "de::checkbox")]
+    pub requires_reason: bool,
+    // Replaces the states an item may move on to, left unchanged when missing
+    #[serde(default, deserialize_with = "de::optional_ids")]
+    pub next_state_ids: Option<Vec<i32>>,
+}
+
+impl From<WorkflowState> for UpdateWorkflowState {
+    fn from(state: WorkflowState) -> Self {
+        UpdateWorkflowState {
+            title: state.title,
+            category: state.category,
+            position: state.position,
+            requires_reason: state.requires_reason,
+            next_state_ids: Some(state.next_state_ids),
+        }
+    }
+}
+
+/// A move of an item from one workflow state to another. The states' titles
+/// are kept as they were, the ids are gone once a state is deleted.
+#[derive(Debug, Serialize, Deserialize, Clone, FromRow)]
+pub struct StateChange {
+    pub change_id: i32,
+    pub from_state_id: Option<i32>,
+    pub from_state: String,
+    pub to_state_id: Option<i32>,
+    pub to_state: String,
+    pub changed_by: String,
+    pub changed_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Query string accepted by the cross-project boards, where an empty value
+// means no filter
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct BoardQuery {
+    #[serde(default, deserialize_with = "de::optional_id")]
+    pub state_id: Option<i32>,
+    #[serde(default, deserialize_with = "de::optional_choice")]
+    pub category: Option<StateCategory>,
+    #[serde(default)]
+    pub assignee: String,
+}
+
+impl BoardQuery {
+    pub fn is_filtered(&self) -> bool {
+        self.state_id.is_some() || self.category.is_some() || !self.assignee.is_empty()
+    }
+}
+
+// Query string accepted by GET /api/workflow
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct WorkflowQuery {
+    pub entity: Option<WorkflowEntity>,
+}
+
+// Query string accepted by the /api list endpoints. Only the parent id that
+// applies to the listed entity is used.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct ListQuery {
+    pub theme_id: Option<i32>,
+    pub objective_id: Option<i32>,
+    pub project_id: Option<i32>,
+    pub keyresult_id: Option<i32>,
+    pub cycle_id: Option<i32>,
+    pub state_id: Option<i32>,
+    pub category: Option<StateCategory>,
+    #[serde(default, deserialize_with = "de::optional_datetime")]
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, deserialize_with = "de::optional_datetime")]
+    pub created_before: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, deserialize_with = "de::optional_datetime")]
+    pub updated_after: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, deserialize_with = "de::optional_datetime")]
+    pub updated_before: Option<chrono::DateTime<chrono::Utc>>,
+    // Column to sort by, prefixed with `-` for descending order
+    pub sort: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    // Children of any of these parents, set by the handlers loading several
+    // parents' children at once
+    #[serde(skip)]
+    pub parent_ids: Option<Vec<i32>>,
+}
+
+// Nested views of the hierarchy, as returned by the /api tree endpoints
+#[derive(Debug, Serialize, Clone)]
+pub struct ThemeTree {
+    #[serde(flatten)]
+    pub theme: Theme,
+    pub objectives: Vec<ObjectiveTree>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ObjectiveTree {
+    #[serde(flatten)]
+    pub objective: Objective,
+    pub keyresults: Vec<KeyResultTree>,
+    pub initiatives: Vec<Initiative>,
+    pub projects: Vec<ProjectTree>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct KeyResultTree {
+    #[serde(flatten)]
+    pub keyresult: KeyResult,
+    pub measurements: Vec<Measurement>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ProjectTree {
+    #[serde(flatten)]
+    pub project: Project,
+    pub tasks: Vec<Task>,
+}
+
+fn default_weight() -> f64 {
+    1.0
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "status_type")]
-pub enum Status {
-    NotStarted,
-    InProgress,
-    Completed,
+/// Which way a key result's measurements need to move to hit the target.
+/// For `Range`, the baseline and target are the lower and upper bounds.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "direction_type")]
+pub enum Direction {
+    Increase,
+    Decrease,
+    Range,
 }
 
-impl std::fmt::Display for Status {
+impl Direction {
+    pub fn progress(&self, baseline: f64, target: f64, value: f64) -> f64 {
+        let reached = match self {
+            Direction::Range => (baseline.min(target)..=baseline.max(target)).contains(&value),
+            Direction::Increase => value >= target,
+            Direction::Decrease => value <= target,
+        };
+        let progress = if reached {
+            1.0
+        } else if *self == Direction::Range || baseline == target {
+            0.0
+        } else {
+            (value - baseline) / (target - baseline)
+        };
+        (progress * 100.0).clamp(0.0, 100.0)
+    }
+}
+
+impl std::fmt::Display for Direction {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            Status::NotStarted => f.write_str("Not Started"),
-            Status::InProgress => f.write_str("In Progress"),
-            Status::Completed => f.write_str("Completed"),
+            Direction::Increase => f.write_str("Increase"),
+            Direction::Decrease => f.write_str("Decrease"),
+            Direction::Range => f.write_str("Stay Within Range"),
+        }
+    }
+}
+
+// The htmx json-enc extension sends every form field as a string, so numeric
+// and date fields coming from the edit forms need a little help to deserialize.
+mod de {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+    use serde::{
+        de::{DeserializeOwned, Error, IntoDeserializer},
+        Deserialize, Deserializer,
+    };
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(f64),
+        String(String),
+    }
+
+    pub fn number<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match NumberOrString::deserialize(deserializer)? {
+            NumberOrString::Number(number) => Ok(number),
+            NumberOrString::String(string) => string
+                .trim()
+                .parse()
+                .map_err(|_| Error::custom("must be a number")),
         }
     }
+
+    // Accepts RFC 3339 timestamps from the API, and the zone-less values
+    // produced by `<input type="datetime-local">`, which are taken as UTC.
+    pub fn optional_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = match Option::<String>::deserialize(deserializer)? {
+            Some(string) if !string.trim().is_empty() => string,
+            _ => return Ok(None),
+        };
+        let string = string.trim();
+        if let Ok(datetime) = DateTime::parse_from_rfc3339(string) {
+            return Ok(Some(datetime.with_timezone(&Utc)));
+        }
+        ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(string, format).ok())
+            .map(|naive| Some(naive.and_utc()))
+            .ok_or_else(|| Error::custom(format!("invalid date and time: {string}")))
+    }
+
+    fn parse_date<E: Error>(string: &str) -> Result<NaiveDate, E> {
+        NaiveDate::parse_from_str(string.trim(), "%Y-%m-%d")
+            .map_err(|_| Error::custom("must be a date like 2026-12-31"))
+    }
+
+    // Accepts `YYYY-MM-DD`, as sent by `<input type="date">`
+    pub fn date<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse_date(&String::deserialize(deserializer)?)
+    }
+
+    // Like `date`, but empty means no date
+    pub fn optional_date<'de, D>(deserializer: D) -> Result<Option<NaiveDate>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(string) if !string.trim().is_empty() => parse_date(&string).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    // Like `optional_date`, for fields that are left unchanged when missing:
+    // with `#[serde(default)]`, a missing field is `None` and a null or empty
+    // one `Some(None)`
+    pub fn date_change<'de, D>(deserializer: D) -> Result<Option<Option<NaiveDate>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        optional_date(deserializer).map(Some)
+    }
+
+    // Ids from form controls arrive as strings, and empty means none
+    fn parse_id<E: Error>(value: NumberOrString) -> Result<Option<i32>, E> {
+        match value {
+            NumberOrString::Number(number) if number.fract() == 0.0 => Ok(Some(number as i32)),
+            NumberOrString::String(string) if string.trim().is_empty() => Ok(None),
+            NumberOrString::String(string) => string
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|_| Error::custom("must be an id")),
+            NumberOrString::Number(_) => Err(Error::custom("must be an id")),
+        }
+    }
+
+    // Ids picked from a `<select>` or radio buttons
+    pub fn optional_id<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<NumberOrString>::deserialize(deserializer)? {
+            Some(value) => parse_id(value),
+            None => Ok(None),
+        }
+    }
+
+    pub fn id<'de, D>(deserializer: D) -> Result<i32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse_id(NumberOrString::deserialize(deserializer)?)?
+            .ok_or_else(|| Error::custom("must be an id"))
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        Many(Vec<NumberOrString>),
+        One(NumberOrString),
+    }
+
+    // Checkboxes sharing a name send a single value when only one is checked,
+    // and nothing when none are, so forms add an empty hidden input
+    pub fn optional_ids<'de, D>(deserializer: D) -> Result<Option<Vec<i32>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let values = match Option::<OneOrMany>::deserialize(deserializer)? {
+            Some(OneOrMany::Many(values)) => values,
+            Some(OneOrMany::One(value)) => vec![value],
+            None => return Ok(None),
+        };
+        let mut ids = Vec::new();
+        for value in values {
+            ids.extend(parse_id::<D::Error>(value)?);
+        }
+        Ok(Some(ids))
+    }
+
+    pub fn integer<'de, D>(deserializer: D) -> Result<i32, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let number = number(deserializer)?;
+        match number.fract() == 0.0 && number.abs() <= i32::MAX as f64 {
+            true => Ok(number as i32),
+            false => Err(Error::custom("must be a whole number")),
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    // A checked checkbox sends its value, an unchecked one sends nothing
+    pub fn checkbox<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match BoolOrString::deserialize(deserializer)? {
+            BoolOrString::Bool(checked) => Ok(checked),
+            BoolOrString::String(string) => Ok(!matches!(string.trim(), "" | "false" | "off")),
+        }
+    }
+
+    // Choices picked from a `<select>`, where the empty option means none
+    pub fn optional_choice<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: DeserializeOwned,
+    {
+        match Option::<String>::deserialize(deserializer)?
+            .as_deref()
+            .map(str::trim)
+        {
+            None | Some("") => Ok(None),
+            Some(choice) => T::deserialize(choice.into_deserializer()).map(Some),
+        }
+    }
+
+    pub fn cycle_filter<'de, D>(deserializer: D) -> Result<super::CycleFilter, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use super::CycleFilter;
+        match Option::<String>::deserialize(deserializer)?
+            .as_deref()
+            .map(str::trim)
+        {
+            None | Some("") | Some("all") => Ok(CycleFilter::All),
+            Some("current") => Ok(CycleFilter::Current),
+            Some("previous") => Ok(CycleFilter::Previous),
+            Some(id) => id
+                .parse()
+                .map(CycleFilter::Id)
+                .map_err(|_| Error::custom("must be all, current, previous or a cycle id")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{objective_score, theme_score, Direction, KeyResult, Objective};
+
+    fn keyresult(direction: Direction, weight: f64, latest_value: Option<f64>) -> KeyResult {
+        KeyResult {
+            keyresult_id: 1,
+            title: "Key result".to_string(),
+            description: String::new(),
+            objective_id: 1,
+            baseline: 0.0,
+            target: 10.0,
+            unit: String::new(),
+            direction,
+            weight,
+            latest_value,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    fn objective(score: Option<f64>) -> Objective {
+        Objective {
+            objective_id: 1,
+            title: "Objective".to_string(),
+            description: String::new(),
+            theme_id: 1,
+            cycle_id: None,
+            carried_from: None,
+            score,
+            created_at: None,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn progress_is_the_share_of_the_way_from_baseline_to_target() {
+        assert_eq!(Direction::Increase.progress(0.0, 10.0, 2.5), 25.0);
+        assert_eq!(Direction::Decrease.progress(10.0, 0.0, 2.5), 75.0);
+    }
+
+    #[test]
+    fn progress_is_clamped_between_0_and_100() {
+        assert_eq!(Direction::Increase.progress(0.0, 10.0, -5.0), 0.0);
+        assert_eq!(Direction::Increase.progress(0.0, 10.0, 15.0), 100.0);
+        assert_eq!(Direction::Decrease.progress(10.0, 0.0, 20.0), 0.0);
+        assert_eq!(Direction::Decrease.progress(10.0, 0.0, -5.0), 100.0);
+        // Nothing to measure progress across
+        assert_eq!(Direction::Increase.progress(5.0, 5.0, 4.0), 0.0);
+        assert_eq!(Direction::Increase.progress(5.0, 5.0, 5.0), 100.0);
+    }
+
+    #[test]
+    fn range_progress_is_all_or_nothing() {
+        assert_eq!(Direction::Range.progress(0.0, 10.0, 5.0), 100.0);
+        assert_eq!(Direction::Range.progress(0.0, 10.0, 10.0), 100.0);
+        assert_eq!(Direction::Range.progress(0.0, 10.0, 11.0), 0.0);
+        assert_eq!(Direction::Range.progress(0.0, 10.0, -1.0), 0.0);
+        // The bounds can be given either way round
+        assert_eq!(Direction::Range.progress(10.0, 0.0, 5.0), 100.0);
+    }
+
+    #[test]
+    fn objective_score_weights_key_results() {
+        let keyresults = [
+            keyresult(Direction::Increase, 3.0, Some(10.0)),
+            keyresult(Direction::Increase, 1.0, Some(0.0)),
+        ];
+        assert_eq!(objective_score(&keyresults), Some(75.0));
+    }
+
+    #[test]
+    fn unmeasured_key_results_count_as_zero() {
+        let keyresults = [
+            keyresult(Direction::Increase, 1.0, Some(10.0)),
+            keyresult(Direction::Range, 1.0, None),
+        ];
+        assert_eq!(keyresults[1].progress(), None);
+        assert_eq!(objective_score(&keyresults), Some(50.0));
+    }
+
+    #[test]
+    fn objective_without_weighted_key_results_has_no_score() {
+        assert_eq!(objective_score(&[]), None);
+        assert_eq!(
+            objective_score(&[keyresult(Direction::Increase, 0.0, Some(10.0))]),
+            None
+        );
+    }
+
+    #[test]
+    fn theme_score_averages_scored_objectives() {
+        let objectives = [
+            objective(Some(20.0)),
+            objective(Some(80.0)),
+            objective(None),
+        ];
+        assert_eq!(theme_score(&objectives), Some(50.0));
+        assert_eq!(theme_score(&[objective(None)]), None);
+    }
 }
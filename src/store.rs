@@ -0,0 +1,435 @@
+use std::sync::Arc;
+
+use axum::async_trait;
+
+use crate::{errors::CustomError, model, validation::FieldErrors};
+
+pub mod memory;
+pub mod migrate;
+pub mod postgres;
+pub mod sqlite;
+
+/// The store handlers are given, shared between requests.
+pub type DynStore = Arc<dyn Store>;
+
+/// One page of a listing, along with how many items there are in all.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+}
+
+/// An unfinished objective being carried over into another cycle, along with
+/// the unfinished key results to copy with it.
+#[derive(Debug, Clone)]
+pub struct CarriedObjective {
+    pub objective: model::Objective,
+    pub keyresults: Vec<model::KeyResult>,
+}
+
+/// Where the data lives. Implementations only store and look things up:
+/// validation, scores, trees and boards are worked out by the handlers, so
+/// every backend behaves the same.
+///
+/// Lookups of a single item fail with the item's `...NotFound` error, as do
+/// saves and removals of items that don't exist. Listings apply the filters,
+/// sort and page of a `model::ListQuery` as described by its `Listing`, with
+/// no limit meaning every item.
+#[async_trait]
+pub trait Store: Send + Sync {
+    // Cycles
+    async fn cycles(&self, query: &model::ListQuery) -> Result<Page<model::Cycle>, CustomError>;
+    async fn cycle(&self, cycle_id: i32) -> Result<model::Cycle, CustomError>;
+    async fn add_cycle(&self, create: model::CreateCycle) -> Result<model::Cycle, CustomError>;
+    async fn save_cycle(
+        &self,
+        cycle_id: i32,
+        update: model::UpdateCycle,
+    ) -> Result<model::Cycle, CustomError>;
+    async fn remove_cycle(&self, cycle_id: i32) -> Result<(), CustomError>;
+    /// Copies objectives and their key results into a cycle, all or nothing.
+    async fn carry_over(
+        &self,
+        cycle_id: i32,
+        objectives: Vec<CarriedObjective>,
+    ) -> Result<(), CustomError>;
+
+    // Workflows
+    /// States of one workflow, or all of them, in the order items move
+    /// through them.
+    async fn workflow_states(
+        &self,
+        entity: Option<model::WorkflowEntity>,
+    ) -> Result<Vec<model::WorkflowState>, CustomError>;
+    async fn workflow_state(&self, state_id: i32) -> Result<model::WorkflowState, CustomError>;
+    /// Adds a state at the end of its workflow, which items may move between
+    /// and every other state of the workflow.
+    async fn add_workflow_state(
+        &self,
+        create: model::CreateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError>;
+    /// Updates a state, replacing the states it leads to when given.
+    async fn save_workflow_state(
+        &self,
+        state_id: i32,
+        update: model::UpdateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError>;
+    async fn remove_workflow_state(&self, state_id: i32) -> Result<(), CustomError>;
+    /// The state changes of an item, newest first.
+    async fn state_changes(
+        &self,
+        entity: model::WorkflowEntity,
+        item_id: i32,
+    ) -> Result<Vec<model::StateChange>, CustomError>;
+
+    // Themes, initiatives, projects and tasks start in the first state of their
+    // workflow. Saving one in another state records the change, and who made it.
+    async fn themes(&self, query: &model::ListQuery) -> Result<Page<model::Theme>, CustomError>;
+    async fn theme(&self, theme_id: i32) -> Result<model::Theme, CustomError>;
+    async fn add_theme(&self, create: model::CreateTheme) -> Result<model::Theme, CustomError>;
+    async fn save_theme(
+        &self,
+        theme_id: i32,
+        update: model::UpdateTheme,
+        changed_by: &str,
+    ) -> Result<model::Theme, CustomError>;
+    async fn remove_theme(&self, theme_id: i32) -> Result<(), CustomError>;
+
+    // Objectives
+    async fn objectives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Objective>, CustomError>;
+    async fn objective(&self, objective_id: i32) -> Result<model::Objective, CustomError>;
+    async fn add_objective(
+        &self,
+        create: model::CreateObjective,
+    ) -> Result<model::Objective, CustomError>;
+    async fn save_objective(
+        &self,
+        objective_id: i32,
+        update: model::UpdateObjective,
+    ) -> Result<model::Objective, CustomError>;
+    async fn remove_objective(&self, objective_id: i32) -> Result<(), CustomError>;
+
+    // Key results come with the value of their latest measurement
+    async fn keyresults(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::KeyResult>, CustomError>;
+    async fn keyresult(&self, keyresult_id: i32) -> Result<model::KeyResult, CustomError>;
+    async fn add_keyresult(
+        &self,
+        create: model::CreateKeyResult,
+    ) -> Result<model::KeyResult, CustomError>;
+    async fn save_keyresult(
+        &self,
+        keyresult_id: i32,
+        update: model::UpdateKeyResult,
+    ) -> Result<model::KeyResult, CustomError>;
+    async fn remove_keyresult(&self, keyresult_id: i32) -> Result<(), CustomError>;
+
+    // Initiatives
+    async fn initiatives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Initiative>, CustomError>;
+    async fn initiative(&self, initiative_id: i32) -> Result<model::Initiative, CustomError>;
+    async fn add_initiative(
+        &self,
+        create: model::CreateInitiative,
+    ) -> Result<model::Initiative, CustomError>;
+    async fn save_initiative(
+        &self,
+        initiative_id: i32,
+        update: model::UpdateInitiative,
+        changed_by: &str,
+    ) -> Result<model::Initiative, CustomError>;
+    async fn remove_initiative(&self, initiative_id: i32) -> Result<(), CustomError>;
+
+    // Projects
+    async fn projects(&self, query: &model::ListQuery)
+        -> Result<Page<model::Project>, CustomError>;
+    async fn project(&self, project_id: i32) -> Result<model::Project, CustomError>;
+    async fn add_project(
+        &self,
+        create: model::CreateProject,
+    ) -> Result<model::Project, CustomError>;
+    async fn save_project(
+        &self,
+        project_id: i32,
+        update: model::UpdateProject,
+        changed_by: &str,
+    ) -> Result<model::Project, CustomError>;
+    async fn remove_project(&self, project_id: i32) -> Result<(), CustomError>;
+
+    // Tasks
+    async fn tasks(&self, query: &model::ListQuery) -> Result<Page<model::Task>, CustomError>;
+    async fn task(&self, task_id: i32) -> Result<model::Task, CustomError>;
+    async fn add_task(&self, create: model::CreateTask) -> Result<model::Task, CustomError>;
+    async fn save_task(
+        &self,
+        task_id: i32,
+        update: model::UpdateTask,
+        changed_by: &str,
+    ) -> Result<model::Task, CustomError>;
+    async fn remove_task(&self, task_id: i32) -> Result<(), CustomError>;
+
+    // Measurements. Saving one with a different value, unit or observation time
+    // keeps what it had before as a revision.
+    async fn measurements(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Measurement>, CustomError>;
+    async fn measurement(&self, measurement_id: i32) -> Result<model::Measurement, CustomError>;
+    async fn add_measurement(
+        &self,
+        create: model::CreateMeasurement,
+    ) -> Result<model::Measurement, CustomError>;
+    async fn save_measurement(
+        &self,
+        measurement_id: i32,
+        update: model::UpdateMeasurement,
+    ) -> Result<model::Measurement, CustomError>;
+    async fn remove_measurement(&self, measurement_id: i32) -> Result<(), CustomError>;
+    /// Earlier values of a measurement, newest first.
+    async fn measurement_revisions(
+        &self,
+        measurement_id: i32,
+    ) -> Result<Vec<model::MeasurementRevision>, CustomError>;
+
+    /// Open tasks, projects and initiatives due before the given day, soonest
+    /// first.
+    async fn overdue(
+        &self,
+        today: chrono::NaiveDate,
+    ) -> Result<Vec<model::OverdueItem>, CustomError>;
+}
+
+// Why an item can't be deleted yet, the same whichever store refuses it
+pub const THEME_IN_USE: &str = "This theme still has objectives, delete or move them first";
+pub const OBJECTIVE_IN_USE: &str =
+    "This objective still has key results, initiatives or projects, delete them first";
+pub const KEYRESULT_IN_USE: &str = "This key result still has measurements, delete them first";
+pub const PROJECT_IN_USE: &str = "This project still has tasks, delete them first";
+pub const WORKFLOW_STATE_IN_USE: &str = "Items are still in this state, move them first";
+
+/// What the listings need to know about each kind of item. Column names match
+/// the fields of the listed model.
+pub struct Listing {
+    pub table: &'static str,
+    pub id_column: &'static str,
+    pub parent_column: Option<&'static str>,
+    pub state_column: Option<&'static str>,
+    pub cycle_column: Option<&'static str>,
+    // Columns that may be sorted on
+    pub columns: &'static [&'static str],
+}
+
+impl Listing {
+    /// Checks the query only uses filters that apply to this listing, and
+    /// returns the column to sort on and whether to sort descending.
+    pub fn order(&self, query: &model::ListQuery) -> Result<(&'static str, bool), CustomError> {
+        let mut errors = FieldErrors::default();
+        if self.state_column.is_none() {
+            let message = format!("{} have no state to filter on", self.table);
+            if query.state_id.is_some() {
+                errors.add("state_id", &message);
+            }
+            if query.category.is_some() {
+                errors.add("category", message);
+            }
+        }
+        if query.cycle_id.is_some() && self.cycle_column.is_none() {
+            errors.add(
+                "cycle_id",
+                format!("{} are not planned in cycles", self.table),
+            );
+        }
+        let Some(sort) = query.sort.as_deref() else {
+            return errors.into_result().map(|()| (self.id_column, false));
+        };
+        let (column, descending) = match sort.strip_prefix('-') {
+            Some(column) => (column, true),
+            None => (sort, false),
+        };
+        match self.columns.iter().find(|allowed| **allowed == column) {
+            Some(column) => errors.into_result().map(|()| (*column, descending)),
+            None => {
+                errors.add(
+                    "sort",
+                    format!(
+                        "Cannot sort {} by {column}, only by {} (prefixed with - to reverse)",
+                        self.table,
+                        self.columns.join(", ")
+                    ),
+                );
+                Err(CustomError::Validation(errors))
+            }
+        }
+    }
+
+    /// The parent ids the query limits the listing to, if it does.
+    pub fn parent_ids(&self, query: &model::ListQuery) -> Option<Vec<i32>> {
+        let column = self.parent_column?;
+        let parent_id = match column {
+            "theme_id" => query.theme_id,
+            "objective_id" => query.objective_id,
+            "keyresult_id" => query.keyresult_id,
+            "project_id" => query.project_id,
+            _ => None,
+        };
+        match (parent_id, &query.parent_ids) {
+            (Some(parent_id), Some(parent_ids)) => Some(
+                parent_ids
+                    .iter()
+                    .copied()
+                    .filter(|id| *id == parent_id)
+                    .collect(),
+            ),
+            (Some(parent_id), None) => Some(vec![parent_id]),
+            (None, parent_ids) => parent_ids.clone(),
+        }
+    }
+}
+
+pub const CYCLES: Listing = Listing {
+    table: "cycles",
+    id_column: "cycle_id",
+    parent_column: None,
+    state_column: None,
+    cycle_column: None,
+    columns: &[
+        "cycle_id",
+        "title",
+        "start_date",
+        "end_date",
+        "created_at",
+        "updated_at",
+    ],
+};
+
+pub const THEMES: Listing = Listing {
+    table: "themes",
+    id_column: "theme_id",
+    parent_column: None,
+    state_column: Some("theme_state_id"),
+    cycle_column: None,
+    columns: &[
+        "theme_id",
+        "title",
+        "theme_state_id",
+        "created_at",
+        "updated_at",
+    ],
+};
+
+pub const OBJECTIVES: Listing = Listing {
+    table: "objectives",
+    id_column: "objective_id",
+    parent_column: Some("theme_id"),
+    state_column: None,
+    cycle_column: Some("cycle_id"),
+    columns: &[
+        "objective_id",
+        "title",
+        "theme_id",
+        "cycle_id",
+        "created_at",
+        "updated_at",
+    ],
+};
+
+pub const KEYRESULTS: Listing = Listing {
+    table: "keyresults",
+    id_column: "keyresult_id",
+    parent_column: Some("objective_id"),
+    state_column: None,
+    cycle_column: None,
+    columns: &[
+        "keyresult_id",
+        "title",
+        "objective_id",
+        "baseline",
+        "target",
+        "unit",
+        "direction",
+        "weight",
+        "latest_value",
+        "created_at",
+        "updated_at",
+    ],
+};
+
+pub const INITIATIVES: Listing = Listing {
+    table: "initiatives",
+    id_column: "initiative_id",
+    parent_column: Some("objective_id"),
+    state_column: Some("initiative_state_id"),
+    cycle_column: None,
+    columns: &[
+        "initiative_id",
+        "title",
+        "objective_id",
+        "initiative_state_id",
+        "start_date",
+        "due_date",
+        "created_at",
+        "updated_at",
+    ],
+};
+
+pub const PROJECTS: Listing = Listing {
+    table: "projects",
+    id_column: "project_id",
+    parent_column: Some("objective_id"),
+    state_column: Some("project_state_id"),
+    cycle_column: None,
+    columns: &[
+        "project_id",
+        "title",
+        "objective_id",
+        "project_state_id",
+        "start_date",
+        "due_date",
+        "created_at",
+        "updated_at",
+    ],
+};
+
+pub const TASKS: Listing = Listing {
+    table: "tasks",
+    id_column: "task_id",
+    parent_column: Some("project_id"),
+    state_column: Some("task_state_id"),
+    cycle_column: None,
+    columns: &[
+        "task_id",
+        "title",
+        "project_id",
+        "task_state_id",
+        "assignee",
+        "start_date",
+        "due_date",
+        "created_at",
+        "updated_at",
+    ],
+};
+
+pub const MEASUREMENTS: Listing = Listing {
+    table: "measurements",
+    id_column: "measurement_id",
+    parent_column: Some("keyresult_id"),
+    state_column: None,
+    cycle_column: None,
+    columns: &[
+        "measurement_id",
+        "title",
+        "value",
+        "unit",
+        "observed_at",
+        "keyresult_id",
+        "created_at",
+        "updated_at",
+    ],
+};
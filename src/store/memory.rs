@@ -0,0 +1,2109 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use axum::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+
+use super::{
+    CarriedObjective, Listing, Page, Store, CYCLES, INITIATIVES, KEYRESULTS, KEYRESULT_IN_USE,
+    MEASUREMENTS, OBJECTIVES, OBJECTIVE_IN_USE, PROJECTS, PROJECT_IN_USE, TASKS, THEMES,
+    THEME_IN_USE, WORKFLOW_STATE_IN_USE,
+};
+use crate::{
+    errors::CustomError,
+    model::{self, WorkflowEntity},
+};
+
+// The workflow every kind of item starts out with, as the migrations set it up
+const DEFAULT_STATES: [(&str, model::StateCategory, bool); 5] = [
+    ("Not Started", model::StateCategory::Open, false),
+    ("In Progress", model::StateCategory::Active, false),
+    ("Blocked", model::StateCategory::Active, true),
+    ("Completed", model::StateCategory::Done, false),
+    ("Cancelled", model::StateCategory::Done, false),
+];
+
+const DEFAULT_TRANSITIONS: [(&str, &str); 13] = [
+    ("Not Started", "In Progress"),
+    ("Not Started", "Blocked"),
+    ("Not Started", "Completed"),
+    ("Not Started", "Cancelled"),
+    ("In Progress", "Not Started"),
+    ("In Progress", "Blocked"),
+    ("In Progress", "Completed"),
+    ("In Progress", "Cancelled"),
+    ("Blocked", "Not Started"),
+    ("Blocked", "In Progress"),
+    ("Blocked", "Cancelled"),
+    ("Completed", "In Progress"),
+    ("Cancelled", "Not Started"),
+];
+
+/// A theme as stored, without the workflow state it is in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThemeRow {
+    pub theme_id: i32,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub theme_state_id: i32,
+    #[serde(default)]
+    pub blocked_reason: String,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An initiative as stored, without the workflow state it is in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct InitiativeRow {
+    pub initiative_id: i32,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub objective_id: i32,
+    pub initiative_state_id: i32,
+    #[serde(default)]
+    pub blocked_reason: String,
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A project as stored, without the workflow state it is in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProjectRow {
+    pub project_id: i32,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub objective_id: i32,
+    pub project_state_id: i32,
+    #[serde(default)]
+    pub blocked_reason: String,
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A task as stored, without the workflow state it is in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskRow {
+    pub task_id: i32,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub project_id: i32,
+    pub task_state_id: i32,
+    #[serde(default)]
+    pub blocked_reason: String,
+    #[serde(default)]
+    pub assignee: String,
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Items to start an in-memory store with, e.g. fixtures/demo.json. Items
+/// keep their ids, and reference their parents and workflow states by id.
+/// Without any workflow states, the default workflows are used.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Fixture {
+    pub cycles: Vec<model::Cycle>,
+    pub workflow_states: Vec<model::WorkflowState>,
+    pub themes: Vec<ThemeRow>,
+    pub objectives: Vec<model::Objective>,
+    pub keyresults: Vec<model::KeyResult>,
+    pub initiatives: Vec<InitiativeRow>,
+    pub projects: Vec<ProjectRow>,
+    pub tasks: Vec<TaskRow>,
+    pub measurements: Vec<model::Measurement>,
+}
+
+// A state change along with the item it belongs to
+#[derive(Debug, Clone)]
+struct StateChangeRow {
+    entity: WorkflowEntity,
+    item_id: i32,
+    change: model::StateChange,
+}
+
+// Rows by id. Ids are never reused, like the databases' identity columns.
+#[derive(Debug)]
+struct Table<T> {
+    rows: BTreeMap<i32, T>,
+    last_id: i32,
+}
+
+impl<T> Default for Table<T> {
+    fn default() -> Self {
+        Table {
+            rows: BTreeMap::new(),
+            last_id: 0,
+        }
+    }
+}
+
+impl<T> Table<T> {
+    fn load(name: &str, rows: Vec<T>, id: impl Fn(&T) -> i32) -> Result<Table<T>, String> {
+        let mut table = Table::default();
+        for row in rows {
+            let row_id = id(&row);
+            if table.rows.insert(row_id, row).is_some() {
+                return Err(format!("{name} {row_id} is in the fixture twice"));
+            }
+            table.last_id = table.last_id.max(row_id);
+        }
+        Ok(table)
+    }
+
+    fn insert(&mut self, row: impl FnOnce(i32) -> T) -> i32 {
+        self.last_id += 1;
+        self.rows.insert(self.last_id, row(self.last_id));
+        self.last_id
+    }
+
+    fn get(&self, id: i32, not_found: CustomError) -> Result<&T, CustomError> {
+        self.rows.get(&id).ok_or(not_found)
+    }
+
+    fn get_mut(&mut self, id: i32, not_found: CustomError) -> Result<&mut T, CustomError> {
+        self.rows.get_mut(&id).ok_or(not_found)
+    }
+
+    fn remove(&mut self, id: i32, not_found: CustomError) -> Result<T, CustomError> {
+        self.rows.remove(&id).ok_or(not_found)
+    }
+
+    fn has(&self, id: i32) -> bool {
+        self.rows.contains_key(&id)
+    }
+}
+
+// A column's value, as listings filter and sort on it
+#[derive(PartialEq, PartialOrd)]
+enum Cell<'a> {
+    Int(i64),
+    Float(f64),
+    Text(&'a str),
+    Date(NaiveDate),
+    Time(DateTime<Utc>),
+    Null,
+}
+
+impl Cell<'_> {
+    fn id(&self) -> Option<i32> {
+        match self {
+            Cell::Int(id) => i32::try_from(*id).ok(),
+            _ => None,
+        }
+    }
+
+    fn time(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Cell::Time(at) => Some(*at),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for Cell<'_> {
+    fn from(value: i32) -> Self {
+        Cell::Int(value.into())
+    }
+}
+
+impl From<Option<i32>> for Cell<'_> {
+    fn from(value: Option<i32>) -> Self {
+        value.map_or(Cell::Null, Cell::from)
+    }
+}
+
+impl From<f64> for Cell<'_> {
+    fn from(value: f64) -> Self {
+        Cell::Float(value)
+    }
+}
+
+impl<'a> From<&'a String> for Cell<'a> {
+    fn from(value: &'a String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl From<Option<NaiveDate>> for Cell<'_> {
+    fn from(value: Option<NaiveDate>) -> Self {
+        value.map_or(Cell::Null, Cell::Date)
+    }
+}
+
+impl From<NaiveDate> for Cell<'_> {
+    fn from(value: NaiveDate) -> Self {
+        Cell::Date(value)
+    }
+}
+
+impl From<DateTime<Utc>> for Cell<'_> {
+    fn from(value: DateTime<Utc>) -> Self {
+        Cell::Time(value)
+    }
+}
+
+impl From<Option<DateTime<Utc>>> for Cell<'_> {
+    fn from(value: Option<DateTime<Utc>>) -> Self {
+        value.map_or(Cell::Null, Cell::Time)
+    }
+}
+
+// Sorts like Postgres does, with nulls after everything else
+fn compare(a: &Cell, b: &Cell) -> Ordering {
+    match (a, b) {
+        (Cell::Null, Cell::Null) => Ordering::Equal,
+        (Cell::Null, _) => Ordering::Greater,
+        (_, Cell::Null) => Ordering::Less,
+        (a, b) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+    }
+}
+
+// Rows that can be listed, by the column names their Listing uses
+trait Row {
+    fn cell(&self, column: &str) -> Cell<'_>;
+}
+
+impl Row for model::Cycle {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "cycle_id" => self.cycle_id.into(),
+            "title" => (&self.title).into(),
+            "start_date" => self.start_date.into(),
+            "end_date" => self.end_date.into(),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+impl Row for ThemeRow {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "theme_id" => self.theme_id.into(),
+            "title" => (&self.title).into(),
+            "theme_state_id" => self.theme_state_id.into(),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+impl Row for model::Objective {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "objective_id" => self.objective_id.into(),
+            "title" => (&self.title).into(),
+            "theme_id" => self.theme_id.into(),
+            "cycle_id" => self.cycle_id.into(),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+impl Row for model::KeyResult {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "keyresult_id" => self.keyresult_id.into(),
+            "title" => (&self.title).into(),
+            "objective_id" => self.objective_id.into(),
+            "baseline" => self.baseline.into(),
+            "target" => self.target.into(),
+            "unit" => (&self.unit).into(),
+            // In the order of the Postgres enum
+            "direction" => Cell::Int(self.direction as i64),
+            "weight" => self.weight.into(),
+            "latest_value" => self.latest_value.map_or(Cell::Null, Cell::Float),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+impl Row for InitiativeRow {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "initiative_id" => self.initiative_id.into(),
+            "title" => (&self.title).into(),
+            "objective_id" => self.objective_id.into(),
+            "initiative_state_id" => self.initiative_state_id.into(),
+            "start_date" => self.start_date.into(),
+            "due_date" => self.due_date.into(),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+impl Row for ProjectRow {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "project_id" => self.project_id.into(),
+            "title" => (&self.title).into(),
+            "objective_id" => self.objective_id.into(),
+            "project_state_id" => self.project_state_id.into(),
+            "start_date" => self.start_date.into(),
+            "due_date" => self.due_date.into(),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+impl Row for TaskRow {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "task_id" => self.task_id.into(),
+            "title" => (&self.title).into(),
+            "project_id" => self.project_id.into(),
+            "task_state_id" => self.task_state_id.into(),
+            "assignee" => (&self.assignee).into(),
+            "start_date" => self.start_date.into(),
+            "due_date" => self.due_date.into(),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+impl Row for model::Measurement {
+    fn cell(&self, column: &str) -> Cell<'_> {
+        match column {
+            "measurement_id" => self.measurement_id.into(),
+            "title" => (&self.title).into(),
+            "value" => self.value.into(),
+            "unit" => (&self.unit).into(),
+            "observed_at" => self.observed_at.into(),
+            "keyresult_id" => self.keyresult_id.into(),
+            "created_at" => self.created_at.into(),
+            "updated_at" => self.updated_at.into(),
+            _ => Cell::Null,
+        }
+    }
+}
+
+fn map_page<R, T>(
+    page: Page<&R>,
+    convert: impl Fn(&R) -> Result<T, CustomError>,
+) -> Result<Page<T>, CustomError> {
+    Ok(Page {
+        items: page
+            .items
+            .into_iter()
+            .map(convert)
+            .collect::<Result<_, _>>()?,
+        total: page.total,
+    })
+}
+
+// Orders workflows the way the Postgres enum does
+fn entity_position(entity: WorkflowEntity) -> usize {
+    WorkflowEntity::ALL
+        .iter()
+        .position(|other| *other == entity)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+struct Tables {
+    cycles: Table<model::Cycle>,
+    workflow_states: Table<model::WorkflowState>,
+    themes: Table<ThemeRow>,
+    objectives: Table<model::Objective>,
+    keyresults: Table<model::KeyResult>,
+    initiatives: Table<InitiativeRow>,
+    projects: Table<ProjectRow>,
+    tasks: Table<TaskRow>,
+    measurements: Table<model::Measurement>,
+    measurement_revisions: Table<model::MeasurementRevision>,
+    state_changes: Table<StateChangeRow>,
+}
+
+impl Tables {
+    fn with_default_workflows() -> Tables {
+        let mut tables = Tables::default();
+        let now = Some(Utc::now());
+        for entity in WorkflowEntity::ALL {
+            let mut state_ids = BTreeMap::new();
+            for (position, (title, category, requires_reason)) in
+                DEFAULT_STATES.into_iter().enumerate()
+            {
+                let state_id = tables
+                    .workflow_states
+                    .insert(|state_id| model::WorkflowState {
+                        state_id,
+                        entity,
+                        title: title.to_owned(),
+                        category,
+                        position: position as i32,
+                        requires_reason,
+                        next_state_ids: Vec::new(),
+                        created_at: now,
+                        updated_at: now,
+                    });
+                state_ids.insert(title, state_id);
+            }
+            for (from, to) in DEFAULT_TRANSITIONS {
+                if let Some(state) = tables.workflow_states.rows.get_mut(&state_ids[from]) {
+                    state.next_state_ids.push(state_ids[to]);
+                }
+            }
+        }
+        tables
+    }
+
+    // Loads a fixture, checking that every id it references is in it
+    fn from_fixture(fixture: Fixture) -> Result<Tables, String> {
+        let defaults = Tables::with_default_workflows();
+        let mut tables = Tables {
+            cycles: Table::load("Cycle", fixture.cycles, |cycle| cycle.cycle_id)?,
+            workflow_states: if fixture.workflow_states.is_empty() {
+                defaults.workflow_states
+            } else {
+                Table::load("Workflow state", fixture.workflow_states, |state| {
+                    state.state_id
+                })?
+            },
+            themes: Table::load("Theme", fixture.themes, |theme| theme.theme_id)?,
+            objectives: Table::load("Objective", fixture.objectives, |objective| {
+                objective.objective_id
+            })?,
+            keyresults: Table::load("Key result", fixture.keyresults, |keyresult| {
+                keyresult.keyresult_id
+            })?,
+            initiatives: Table::load("Initiative", fixture.initiatives, |initiative| {
+                initiative.initiative_id
+            })?,
+            projects: Table::load("Project", fixture.projects, |project| project.project_id)?,
+            tasks: Table::load("Task", fixture.tasks, |task| task.task_id)?,
+            measurements: Table::load("Measurement", fixture.measurements, |measurement| {
+                measurement.measurement_id
+            })?,
+            ..Tables::default()
+        };
+
+        let missing = |kind: &str, id: i32, reference: &str, reference_id: i32| {
+            Err(format!(
+                "{kind} {id} refers to {reference} {reference_id}, which is not in the fixture"
+            ))
+        };
+        let state_of = |entity: WorkflowEntity, state_id: i32| {
+            tables
+                .workflow_states
+                .rows
+                .get(&state_id)
+                .is_some_and(|state| state.entity == entity)
+        };
+        for state in tables.workflow_states.rows.values() {
+            for next_state_id in &state.next_state_ids {
+                if !state_of(state.entity, *next_state_id) {
+                    return missing("Workflow state", state.state_id, "state", *next_state_id);
+                }
+            }
+        }
+        for theme in tables.themes.rows.values() {
+            if !state_of(WorkflowEntity::Theme, theme.theme_state_id) {
+                return missing("Theme", theme.theme_id, "state", theme.theme_state_id);
+            }
+        }
+        for objective in tables.objectives.rows.values() {
+            if !tables.themes.has(objective.theme_id) {
+                return missing(
+                    "Objective",
+                    objective.objective_id,
+                    "theme",
+                    objective.theme_id,
+                );
+            }
+            if let Some(cycle_id) = objective.cycle_id.filter(|id| !tables.cycles.has(*id)) {
+                return missing("Objective", objective.objective_id, "cycle", cycle_id);
+            }
+        }
+        for keyresult in tables.keyresults.rows.values() {
+            if !tables.objectives.has(keyresult.objective_id) {
+                return missing(
+                    "Key result",
+                    keyresult.keyresult_id,
+                    "objective",
+                    keyresult.objective_id,
+                );
+            }
+        }
+        for initiative in tables.initiatives.rows.values() {
+            if !tables.objectives.has(initiative.objective_id) {
+                return missing(
+                    "Initiative",
+                    initiative.initiative_id,
+                    "objective",
+                    initiative.objective_id,
+                );
+            }
+            if !state_of(WorkflowEntity::Initiative, initiative.initiative_state_id) {
+                return missing(
+                    "Initiative",
+                    initiative.initiative_id,
+                    "state",
+                    initiative.initiative_state_id,
+                );
+            }
+        }
+        for project in tables.projects.rows.values() {
+            if !tables.objectives.has(project.objective_id) {
+                return missing(
+                    "Project",
+                    project.project_id,
+                    "objective",
+                    project.objective_id,
+                );
+            }
+            if !state_of(WorkflowEntity::Project, project.project_state_id) {
+                return missing(
+                    "Project",
+                    project.project_id,
+                    "state",
+                    project.project_state_id,
+                );
+            }
+        }
+        for task in tables.tasks.rows.values() {
+            if !tables.projects.has(task.project_id) {
+                return missing("Task", task.task_id, "project", task.project_id);
+            }
+            if !state_of(WorkflowEntity::Task, task.task_state_id) {
+                return missing("Task", task.task_id, "state", task.task_state_id);
+            }
+        }
+        for measurement in tables.measurements.rows.values() {
+            if !tables.keyresults.has(measurement.keyresult_id) {
+                return missing(
+                    "Measurement",
+                    measurement.measurement_id,
+                    "key result",
+                    measurement.keyresult_id,
+                );
+            }
+        }
+
+        // Scores and latest values are worked out rather than loaded, and
+        // missing timestamps are the time of loading
+        let now = Some(Utc::now());
+        for cycle in tables.cycles.rows.values_mut() {
+            cycle.created_at = cycle.created_at.or(now);
+            cycle.updated_at = cycle.updated_at.or(now);
+        }
+        for objective in tables.objectives.rows.values_mut() {
+            objective.score = None;
+            objective.created_at = objective.created_at.or(now);
+            objective.updated_at = objective.updated_at.or(now);
+        }
+        for keyresult in tables.keyresults.rows.values_mut() {
+            keyresult.latest_value = None;
+            keyresult.created_at = keyresult.created_at.or(now);
+            keyresult.updated_at = keyresult.updated_at.or(now);
+        }
+        for measurement in tables.measurements.rows.values_mut() {
+            measurement.created_at = measurement.created_at.or(now);
+            measurement.updated_at = measurement.updated_at.or(now);
+        }
+        for state in tables.workflow_states.rows.values_mut() {
+            state.next_state_ids.sort_unstable();
+            state.next_state_ids.dedup();
+            state.created_at = state.created_at.or(now);
+            state.updated_at = state.updated_at.or(now);
+        }
+        Ok(tables)
+    }
+
+    // Shared implementation of the listings: filters, sorts and pages the rows,
+    // and counts them unpaged.
+    fn list<'a, R: Row>(
+        &self,
+        listing: &Listing,
+        rows: impl Iterator<Item = &'a R>,
+        query: &model::ListQuery,
+    ) -> Result<Page<&'a R>, CustomError> {
+        let (sort_column, descending) = listing.order(query)?;
+        let parent_ids = listing.parent_ids(query);
+        let category_state_ids: Option<Vec<i32>> = query.category.map(|category| {
+            self.workflow_states
+                .rows
+                .values()
+                .filter(|state| state.category == category)
+                .map(|state| state.state_id)
+                .collect()
+        });
+        let in_ids = |cell: Cell, ids: &[i32]| cell.id().is_some_and(|id| ids.contains(&id));
+
+        let mut rows: Vec<&R> = rows
+            .filter(|row| {
+                if let (Some(column), Some(parent_ids)) = (listing.parent_column, &parent_ids) {
+                    if !in_ids(row.cell(column), parent_ids) {
+                        return false;
+                    }
+                }
+                if let (Some(column), Some(state_id)) = (listing.state_column, query.state_id) {
+                    if row.cell(column).id() != Some(state_id) {
+                        return false;
+                    }
+                }
+                if let (Some(column), Some(state_ids)) = (listing.state_column, &category_state_ids)
+                {
+                    if !in_ids(row.cell(column), state_ids) {
+                        return false;
+                    }
+                }
+                if let (Some(column), Some(cycle_id)) = (listing.cycle_column, query.cycle_id) {
+                    if row.cell(column).id() != Some(cycle_id) {
+                        return false;
+                    }
+                }
+                let created_at = row.cell("created_at").time();
+                let updated_at = row.cell("updated_at").time();
+                [
+                    (created_at, query.created_after, Ordering::Less),
+                    (created_at, query.created_before, Ordering::Greater),
+                    (updated_at, query.updated_after, Ordering::Less),
+                    (updated_at, query.updated_before, Ordering::Greater),
+                ]
+                .into_iter()
+                .all(|(at, bound, outside)| match (at, bound) {
+                    (_, None) => true,
+                    (Some(at), Some(bound)) => at.cmp(&bound) != outside,
+                    (None, Some(_)) => false,
+                })
+            })
+            .collect();
+
+        rows.sort_by(|a, b| {
+            let order = compare(&a.cell(sort_column), &b.cell(sort_column));
+            let order = if descending { order.reverse() } else { order };
+            order.then_with(|| compare(&a.cell(listing.id_column), &b.cell(listing.id_column)))
+        });
+        let total = rows.len() as i64;
+        let offset = usize::try_from(query.offset.unwrap_or(0)).unwrap_or(0);
+        let limit = query
+            .limit
+            .map_or(usize::MAX, |limit| usize::try_from(limit).unwrap_or(0));
+        let items = rows.into_iter().skip(offset).take(limit).collect();
+        Ok(Page { items, total })
+    }
+
+    // Items referencing a missing row are refused, as a foreign key would
+    fn check(found: bool) -> Result<(), CustomError> {
+        if found {
+            Ok(())
+        } else {
+            Err(CustomError::BadRequest)
+        }
+    }
+
+    fn current_state(&self, state_id: i32) -> Result<model::CurrentState, CustomError> {
+        let state = self
+            .workflow_states
+            .get(state_id, CustomError::InternalServerError)?;
+        Ok(model::CurrentState {
+            title: state.title.clone(),
+            category: state.category,
+            requires_reason: state.requires_reason,
+        })
+    }
+
+    // New items start in the first state of their workflow
+    fn first_state(&self, entity: WorkflowEntity) -> Result<i32, CustomError> {
+        self.workflow_states
+            .rows
+            .values()
+            .filter(|state| state.entity == entity)
+            .min_by_key(|state| (state.position, state.state_id))
+            .map(|state| state.state_id)
+            .ok_or(CustomError::BadRequest)
+    }
+
+    fn record_state_change(
+        &mut self,
+        entity: WorkflowEntity,
+        item_id: i32,
+        from_state_id: i32,
+        to_state_id: i32,
+        changed_by: &str,
+    ) {
+        if from_state_id == to_state_id {
+            return;
+        }
+        let title = |state_id| {
+            self.workflow_states
+                .rows
+                .get(&state_id)
+                .map(|state| state.title.clone())
+                .unwrap_or_default()
+        };
+        let (from_state, to_state) = (title(from_state_id), title(to_state_id));
+        self.state_changes.insert(|change_id| StateChangeRow {
+            entity,
+            item_id,
+            change: model::StateChange {
+                change_id,
+                from_state_id: Some(from_state_id),
+                from_state,
+                to_state_id: Some(to_state_id),
+                to_state,
+                changed_by: changed_by.to_owned(),
+                changed_at: Utc::now(),
+            },
+        });
+    }
+
+    fn remove_state_changes(&mut self, entity: WorkflowEntity, item_id: i32) {
+        self.state_changes
+            .rows
+            .retain(|_, row| row.entity != entity || row.item_id != item_id);
+    }
+
+    fn cycle(&self, cycle_id: i32) -> Result<model::Cycle, CustomError> {
+        self.cycles
+            .get(cycle_id, CustomError::CycleNotFound)
+            .cloned()
+    }
+
+    fn add_cycle(&mut self, create: model::CreateCycle) -> Result<model::Cycle, CustomError> {
+        let now = Some(Utc::now());
+        let cycle_id = self.cycles.insert(|cycle_id| model::Cycle {
+            cycle_id,
+            title: create.title,
+            start_date: create.start_date,
+            end_date: create.end_date,
+            created_at: now,
+            updated_at: now,
+        });
+        self.cycle(cycle_id)
+    }
+
+    fn save_cycle(
+        &mut self,
+        cycle_id: i32,
+        update: model::UpdateCycle,
+    ) -> Result<model::Cycle, CustomError> {
+        let cycle = self.cycles.get_mut(cycle_id, CustomError::CycleNotFound)?;
+        cycle.title = update.title;
+        cycle.start_date = update.start_date;
+        cycle.end_date = update.end_date;
+        cycle.updated_at = Some(Utc::now());
+        self.cycle(cycle_id)
+    }
+
+    fn remove_cycle(&mut self, cycle_id: i32) -> Result<(), CustomError> {
+        self.cycles.remove(cycle_id, CustomError::CycleNotFound)?;
+        for objective in self.objectives.rows.values_mut() {
+            if objective.cycle_id == Some(cycle_id) {
+                objective.cycle_id = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn carry_over(
+        &mut self,
+        cycle_id: i32,
+        objectives: Vec<CarriedObjective>,
+    ) -> Result<(), CustomError> {
+        Tables::check(self.cycles.has(cycle_id))?;
+        let now = Some(Utc::now());
+        for CarriedObjective {
+            objective,
+            keyresults,
+        } in objectives
+        {
+            let copy_id = self.objectives.insert(|copy_id| model::Objective {
+                objective_id: copy_id,
+                cycle_id: Some(cycle_id),
+                carried_from: Some(objective.objective_id),
+                score: None,
+                created_at: now,
+                updated_at: now,
+                ..objective
+            });
+            for keyresult in keyresults {
+                self.keyresults.insert(|keyresult_id| model::KeyResult {
+                    keyresult_id,
+                    objective_id: copy_id,
+                    latest_value: None,
+                    created_at: now,
+                    updated_at: now,
+                    ..keyresult
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn workflow_states(&self, entity: Option<WorkflowEntity>) -> Vec<model::WorkflowState> {
+        let mut states: Vec<model::WorkflowState> = self
+            .workflow_states
+            .rows
+            .values()
+            .filter(|state| entity.is_none_or(|entity| state.entity == entity))
+            .cloned()
+            .collect();
+        states.sort_by_key(|state| {
+            (
+                entity_position(state.entity),
+                state.position,
+                state.state_id,
+            )
+        });
+        states
+    }
+
+    fn workflow_state(&self, state_id: i32) -> Result<model::WorkflowState, CustomError> {
+        self.workflow_states
+            .get(state_id, CustomError::WorkflowStateNotFound)
+            .cloned()
+    }
+
+    // Titles are unique within a workflow
+    fn check_state_title(
+        &self,
+        entity: WorkflowEntity,
+        title: &str,
+        state_id: Option<i32>,
+    ) -> Result<(), CustomError> {
+        Tables::check(!self.workflow_states.rows.values().any(|state| {
+            state.entity == entity && state.title == title && Some(state.state_id) != state_id
+        }))
+    }
+
+    fn add_workflow_state(
+        &mut self,
+        create: model::CreateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        self.check_state_title(create.entity, &create.title, None)?;
+        let others: Vec<i32> = self
+            .workflow_states(Some(create.entity))
+            .iter()
+            .map(|state| state.state_id)
+            .collect();
+        let position = self
+            .workflow_states
+            .rows
+            .values()
+            .filter(|state| state.entity == create.entity)
+            .map(|state| state.position + 1)
+            .max()
+            .unwrap_or(0);
+        let now = Some(Utc::now());
+        let mut next_state_ids = others.clone();
+        next_state_ids.sort_unstable();
+        let state_id = self
+            .workflow_states
+            .insert(|state_id| model::WorkflowState {
+                state_id,
+                entity: create.entity,
+                title: create.title,
+                category: create.category,
+                position,
+                requires_reason: create.requires_reason,
+                next_state_ids,
+                created_at: now,
+                updated_at: now,
+            });
+        for other in others {
+            if let Some(state) = self.workflow_states.rows.get_mut(&other) {
+                state.next_state_ids.push(state_id);
+            }
+        }
+        self.workflow_state(state_id)
+    }
+
+    fn save_workflow_state(
+        &mut self,
+        state_id: i32,
+        update: model::UpdateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        let entity = self
+            .workflow_states
+            .get(state_id, CustomError::WorkflowStateNotFound)?
+            .entity;
+        self.check_state_title(entity, &update.title, Some(state_id))?;
+        if let Some(next_state_ids) = &update.next_state_ids {
+            Tables::check(
+                next_state_ids
+                    .iter()
+                    .all(|next_state_id| self.workflow_states.has(*next_state_id)),
+            )?;
+        }
+        let state = self
+            .workflow_states
+            .get_mut(state_id, CustomError::WorkflowStateNotFound)?;
+        state.title = update.title;
+        state.category = update.category;
+        state.position = update.position;
+        state.requires_reason = update.requires_reason;
+        if let Some(mut next_state_ids) = update.next_state_ids {
+            next_state_ids.sort_unstable();
+            next_state_ids.dedup();
+            state.next_state_ids = next_state_ids;
+        }
+        state.updated_at = Some(Utc::now());
+        self.workflow_state(state_id)
+    }
+
+    fn remove_workflow_state(&mut self, state_id: i32) -> Result<(), CustomError> {
+        self.workflow_states
+            .get(state_id, CustomError::WorkflowStateNotFound)?;
+        let in_use = self
+            .themes
+            .rows
+            .values()
+            .any(|theme| theme.theme_state_id == state_id)
+            || self
+                .initiatives
+                .rows
+                .values()
+                .any(|initiative| initiative.initiative_state_id == state_id)
+            || self
+                .projects
+                .rows
+                .values()
+                .any(|project| project.project_state_id == state_id)
+            || self
+                .tasks
+                .rows
+                .values()
+                .any(|task| task.task_state_id == state_id);
+        if in_use {
+            return Err(CustomError::InUse(WORKFLOW_STATE_IN_USE));
+        }
+        self.workflow_states
+            .remove(state_id, CustomError::WorkflowStateNotFound)?;
+        for state in self.workflow_states.rows.values_mut() {
+            state.next_state_ids.retain(|id| *id != state_id);
+        }
+        for row in self.state_changes.rows.values_mut() {
+            if row.change.from_state_id == Some(state_id) {
+                row.change.from_state_id = None;
+            }
+            if row.change.to_state_id == Some(state_id) {
+                row.change.to_state_id = None;
+            }
+        }
+        Ok(())
+    }
+
+    fn state_changes(&self, entity: WorkflowEntity, item_id: i32) -> Vec<model::StateChange> {
+        let mut changes: Vec<model::StateChange> = self
+            .state_changes
+            .rows
+            .values()
+            .filter(|row| row.entity == entity && row.item_id == item_id)
+            .map(|row| row.change.clone())
+            .collect();
+        changes.sort_by_key(|change| Reverse((change.changed_at, change.change_id)));
+        changes
+    }
+
+    fn theme_of(&self, row: &ThemeRow) -> Result<model::Theme, CustomError> {
+        Ok(model::Theme {
+            theme_id: row.theme_id,
+            title: row.title.clone(),
+            description: row.description.clone(),
+            theme_state_id: row.theme_state_id,
+            state: self.current_state(row.theme_state_id)?,
+            blocked_reason: row.blocked_reason.clone(),
+            score: None,
+        })
+    }
+
+    fn themes(&self, query: &model::ListQuery) -> Result<Page<model::Theme>, CustomError> {
+        let page = self.list(&THEMES, self.themes.rows.values(), query)?;
+        map_page(page, |row| self.theme_of(row))
+    }
+
+    fn theme(&self, theme_id: i32) -> Result<model::Theme, CustomError> {
+        self.theme_of(self.themes.get(theme_id, CustomError::ThemeNotFound)?)
+    }
+
+    fn add_theme(&mut self, create: model::CreateTheme) -> Result<model::Theme, CustomError> {
+        let state_id = self.first_state(WorkflowEntity::Theme)?;
+        let now = Utc::now();
+        let theme_id = self.themes.insert(|theme_id| ThemeRow {
+            theme_id,
+            title: create.title,
+            description: create.description,
+            theme_state_id: state_id,
+            blocked_reason: String::new(),
+            created_at: now,
+            updated_at: now,
+        });
+        self.theme(theme_id)
+    }
+
+    fn save_theme(
+        &mut self,
+        theme_id: i32,
+        update: model::UpdateTheme,
+        changed_by: &str,
+    ) -> Result<model::Theme, CustomError> {
+        self.themes.get(theme_id, CustomError::ThemeNotFound)?;
+        Tables::check(self.workflow_states.has(update.state_id))?;
+        let theme = self.themes.get_mut(theme_id, CustomError::ThemeNotFound)?;
+        let previous_state_id = theme.theme_state_id;
+        theme.title = update.title;
+        theme.theme_state_id = update.state_id;
+        if let Some(blocked_reason) = update.blocked_reason {
+            theme.blocked_reason = blocked_reason;
+        }
+        if let Some(description) = update.description {
+            theme.description = description;
+        }
+        theme.updated_at = Utc::now();
+        self.record_state_change(
+            WorkflowEntity::Theme,
+            theme_id,
+            previous_state_id,
+            update.state_id,
+            changed_by,
+        );
+        self.theme(theme_id)
+    }
+
+    fn remove_theme(&mut self, theme_id: i32) -> Result<(), CustomError> {
+        self.themes.get(theme_id, CustomError::ThemeNotFound)?;
+        if self
+            .objectives
+            .rows
+            .values()
+            .any(|objective| objective.theme_id == theme_id)
+        {
+            return Err(CustomError::InUse(THEME_IN_USE));
+        }
+        self.themes.remove(theme_id, CustomError::ThemeNotFound)?;
+        self.remove_state_changes(WorkflowEntity::Theme, theme_id);
+        Ok(())
+    }
+
+    fn objectives(&self, query: &model::ListQuery) -> Result<Page<model::Objective>, CustomError> {
+        let page = self.list(&OBJECTIVES, self.objectives.rows.values(), query)?;
+        map_page(page, |objective| Ok(objective.clone()))
+    }
+
+    fn objective(&self, objective_id: i32) -> Result<model::Objective, CustomError> {
+        self.objectives
+            .get(objective_id, CustomError::ObjectiveNotFound)
+            .cloned()
+    }
+
+    fn add_objective(
+        &mut self,
+        create: model::CreateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        Tables::check(self.themes.has(create.theme_id))?;
+        Tables::check(create.cycle_id.is_none_or(|id| self.cycles.has(id)))?;
+        let now = Some(Utc::now());
+        let objective_id = self.objectives.insert(|objective_id| model::Objective {
+            objective_id,
+            title: create.title,
+            description: create.description,
+            theme_id: create.theme_id,
+            cycle_id: create.cycle_id,
+            carried_from: None,
+            score: None,
+            created_at: now,
+            updated_at: now,
+        });
+        self.objective(objective_id)
+    }
+
+    fn save_objective(
+        &mut self,
+        objective_id: i32,
+        update: model::UpdateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        self.objectives
+            .get(objective_id, CustomError::ObjectiveNotFound)?;
+        Tables::check(update.cycle_id.is_none_or(|id| self.cycles.has(id)))?;
+        let objective = self
+            .objectives
+            .get_mut(objective_id, CustomError::ObjectiveNotFound)?;
+        objective.title = update.title;
+        if let Some(description) = update.description {
+            objective.description = description;
+        }
+        if let Some(cycle_id) = update.cycle_id {
+            objective.cycle_id = Some(cycle_id);
+        }
+        objective.updated_at = Some(Utc::now());
+        self.objective(objective_id)
+    }
+
+    fn remove_objective(&mut self, objective_id: i32) -> Result<(), CustomError> {
+        self.objectives
+            .get(objective_id, CustomError::ObjectiveNotFound)?;
+        let in_use = self
+            .keyresults
+            .rows
+            .values()
+            .any(|keyresult| keyresult.objective_id == objective_id)
+            || self
+                .initiatives
+                .rows
+                .values()
+                .any(|initiative| initiative.objective_id == objective_id)
+            || self
+                .projects
+                .rows
+                .values()
+                .any(|project| project.objective_id == objective_id);
+        if in_use {
+            return Err(CustomError::InUse(OBJECTIVE_IN_USE));
+        }
+        self.objectives
+            .remove(objective_id, CustomError::ObjectiveNotFound)?;
+        for objective in self.objectives.rows.values_mut() {
+            if objective.carried_from == Some(objective_id) {
+                objective.carried_from = None;
+            }
+        }
+        Ok(())
+    }
+
+    // Key results come with the value of their latest measurement
+    fn with_latest_value(&self, keyresult: &model::KeyResult) -> model::KeyResult {
+        let latest_value = self
+            .measurements
+            .rows
+            .values()
+            .filter(|measurement| measurement.keyresult_id == keyresult.keyresult_id)
+            .max_by_key(|measurement| (measurement.observed_at, measurement.measurement_id))
+            .map(|measurement| measurement.value);
+        model::KeyResult {
+            latest_value,
+            ..keyresult.clone()
+        }
+    }
+
+    fn keyresults(&self, query: &model::ListQuery) -> Result<Page<model::KeyResult>, CustomError> {
+        // Worked out first, since key results may be sorted on it
+        let keyresults: Vec<model::KeyResult> = self
+            .keyresults
+            .rows
+            .values()
+            .map(|keyresult| self.with_latest_value(keyresult))
+            .collect();
+        let page = self.list(&KEYRESULTS, keyresults.iter(), query)?;
+        map_page(page, |keyresult| Ok(keyresult.clone()))
+    }
+
+    fn keyresult(&self, keyresult_id: i32) -> Result<model::KeyResult, CustomError> {
+        let keyresult = self
+            .keyresults
+            .get(keyresult_id, CustomError::KeyResultNotFound)?;
+        Ok(self.with_latest_value(keyresult))
+    }
+
+    fn add_keyresult(
+        &mut self,
+        create: model::CreateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        Tables::check(self.objectives.has(create.objective_id))?;
+        let now = Some(Utc::now());
+        let keyresult_id = self.keyresults.insert(|keyresult_id| model::KeyResult {
+            keyresult_id,
+            title: create.title,
+            description: create.description,
+            objective_id: create.objective_id,
+            baseline: create.baseline,
+            target: create.target,
+            unit: create.unit,
+            direction: create.direction,
+            weight: create.weight,
+            latest_value: None,
+            created_at: now,
+            updated_at: now,
+        });
+        self.keyresult(keyresult_id)
+    }
+
+    fn save_keyresult(
+        &mut self,
+        keyresult_id: i32,
+        update: model::UpdateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        let keyresult = self
+            .keyresults
+            .get_mut(keyresult_id, CustomError::KeyResultNotFound)?;
+        keyresult.title = update.title;
+        keyresult.baseline = update.baseline;
+        keyresult.target = update.target;
+        keyresult.unit = update.unit;
+        keyresult.direction = update.direction;
+        keyresult.weight = update.weight;
+        if let Some(description) = update.description {
+            keyresult.description = description;
+        }
+        keyresult.updated_at = Some(Utc::now());
+        self.keyresult(keyresult_id)
+    }
+
+    fn remove_keyresult(&mut self, keyresult_id: i32) -> Result<(), CustomError> {
+        self.keyresults
+            .get(keyresult_id, CustomError::KeyResultNotFound)?;
+        if self
+            .measurements
+            .rows
+            .values()
+            .any(|measurement| measurement.keyresult_id == keyresult_id)
+        {
+            return Err(CustomError::InUse(KEYRESULT_IN_USE));
+        }
+        self.keyresults
+            .remove(keyresult_id, CustomError::KeyResultNotFound)?;
+        Ok(())
+    }
+
+    fn initiative_of(&self, row: &InitiativeRow) -> Result<model::Initiative, CustomError> {
+        Ok(model::Initiative {
+            initiative_id: row.initiative_id,
+            title: row.title.clone(),
+            description: row.description.clone(),
+            objective_id: row.objective_id,
+            initiative_state_id: row.initiative_state_id,
+            state: self.current_state(row.initiative_state_id)?,
+            blocked_reason: row.blocked_reason.clone(),
+            start_date: row.start_date,
+            due_date: row.due_date,
+            created_at: Some(row.created_at),
+            updated_at: Some(row.updated_at),
+        })
+    }
+
+    fn initiatives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Initiative>, CustomError> {
+        let page = self.list(&INITIATIVES, self.initiatives.rows.values(), query)?;
+        map_page(page, |row| self.initiative_of(row))
+    }
+
+    fn initiative(&self, initiative_id: i32) -> Result<model::Initiative, CustomError> {
+        self.initiative_of(
+            self.initiatives
+                .get(initiative_id, CustomError::InitiativeNotFound)?,
+        )
+    }
+
+    fn add_initiative(
+        &mut self,
+        create: model::CreateInitiative,
+    ) -> Result<model::Initiative, CustomError> {
+        Tables::check(self.objectives.has(create.objective_id))?;
+        let state_id = self.first_state(WorkflowEntity::Initiative)?;
+        let now = Utc::now();
+        let initiative_id = self.initiatives.insert(|initiative_id| InitiativeRow {
+            initiative_id,
+            title: create.title,
+            description: create.description,
+            objective_id: create.objective_id,
+            initiative_state_id: state_id,
+            blocked_reason: String::new(),
+            start_date: create.start_date,
+            due_date: create.due_date,
+            created_at: now,
+            updated_at: now,
+        });
+        self.initiative(initiative_id)
+    }
+
+    fn save_initiative(
+        &mut self,
+        initiative_id: i32,
+        update: model::UpdateInitiative,
+        changed_by: &str,
+    ) -> Result<model::Initiative, CustomError> {
+        self.initiatives
+            .get(initiative_id, CustomError::InitiativeNotFound)?;
+        Tables::check(self.workflow_states.has(update.state_id))?;
+        let initiative = self
+            .initiatives
+            .get_mut(initiative_id, CustomError::InitiativeNotFound)?;
+        let previous_state_id = initiative.initiative_state_id;
+        initiative.title = update.title;
+        initiative.initiative_state_id = update.state_id;
+        if let Some(blocked_reason) = update.blocked_reason {
+            initiative.blocked_reason = blocked_reason;
+        }
+        if let Some(description) = update.description {
+            initiative.description = description;
+        }
+        if let Some(start_date) = update.start_date {
+            initiative.start_date = start_date;
+        }
+        if let Some(due_date) = update.due_date {
+            initiative.due_date = due_date;
+        }
+        initiative.updated_at = Utc::now();
+        self.record_state_change(
+            WorkflowEntity::Initiative,
+            initiative_id,
+            previous_state_id,
+            update.state_id,
+            changed_by,
+        );
+        self.initiative(initiative_id)
+    }
+
+    fn remove_initiative(&mut self, initiative_id: i32) -> Result<(), CustomError> {
+        self.initiatives
+            .remove(initiative_id, CustomError::InitiativeNotFound)?;
+        self.remove_state_changes(WorkflowEntity::Initiative, initiative_id);
+        Ok(())
+    }
+
+    fn project_of(&self, row: &ProjectRow) -> Result<model::Project, CustomError> {
+        Ok(model::Project {
+            project_id: row.project_id,
+            title: row.title.clone(),
+            description: row.description.clone(),
+            objective_id: row.objective_id,
+            project_state_id: row.project_state_id,
+            state: self.current_state(row.project_state_id)?,
+            blocked_reason: row.blocked_reason.clone(),
+            start_date: row.start_date,
+            due_date: row.due_date,
+            created_at: Some(row.created_at),
+            updated_at: Some(row.updated_at),
+        })
+    }
+
+    fn projects(&self, query: &model::ListQuery) -> Result<Page<model::Project>, CustomError> {
+        let page = self.list(&PROJECTS, self.projects.rows.values(), query)?;
+        map_page(page, |row| self.project_of(row))
+    }
+
+    fn project(&self, project_id: i32) -> Result<model::Project, CustomError> {
+        self.project_of(
+            self.projects
+                .get(project_id, CustomError::ProjectNotFound)?,
+        )
+    }
+
+    fn add_project(&mut self, create: model::CreateProject) -> Result<model::Project, CustomError> {
+        Tables::check(self.objectives.has(create.objective_id))?;
+        let state_id = self.first_state(WorkflowEntity::Project)?;
+        let now = Utc::now();
+        let project_id = self.projects.insert(|project_id| ProjectRow {
+            project_id,
+            title: create.title,
+            description: create.description,
+            objective_id: create.objective_id,
+            project_state_id: state_id,
+            blocked_reason: String::new(),
+            start_date: create.start_date,
+            due_date: create.due_date,
+            created_at: now,
+            updated_at: now,
+        });
+        self.project(project_id)
+    }
+
+    fn save_project(
+        &mut self,
+        project_id: i32,
+        update: model::UpdateProject,
+        changed_by: &str,
+    ) -> Result<model::Project, CustomError> {
+        self.projects
+            .get(project_id, CustomError::ProjectNotFound)?;
+        Tables::check(self.workflow_states.has(update.state_id))?;
+        let project = self
+            .projects
+            .get_mut(project_id, CustomError::ProjectNotFound)?;
+        let previous_state_id = project.project_state_id;
+        project.title = update.title;
+        project.project_state_id = update.state_id;
+        if let Some(blocked_reason) = update.blocked_reason {
+            project.blocked_reason = blocked_reason;
+        }
+        if let Some(description) = update.description {
+            project.description = description;
+        }
+        if let Some(start_date) = update.start_date {
+            project.start_date = start_date;
+        }
+        if let Some(due_date) = update.due_date {
+            project.due_date = due_date;
+        }
+        project.updated_at = Utc::now();
+        self.record_state_change(
+            WorkflowEntity::Project,
+            project_id,
+            previous_state_id,
+            update.state_id,
+            changed_by,
+        );
+        self.project(project_id)
+    }
+
+    fn remove_project(&mut self, project_id: i32) -> Result<(), CustomError> {
+        self.projects
+            .get(project_id, CustomError::ProjectNotFound)?;
+        if self
+            .tasks
+            .rows
+            .values()
+            .any(|task| task.project_id == project_id)
+        {
+            return Err(CustomError::InUse(PROJECT_IN_USE));
+        }
+        self.projects
+            .remove(project_id, CustomError::ProjectNotFound)?;
+        self.remove_state_changes(WorkflowEntity::Project, project_id);
+        Ok(())
+    }
+
+    fn task_of(&self, row: &TaskRow) -> Result<model::Task, CustomError> {
+        Ok(model::Task {
+            task_id: row.task_id,
+            title: row.title.clone(),
+            task_state_id: row.task_state_id,
+            state: self.current_state(row.task_state_id)?,
+            blocked_reason: row.blocked_reason.clone(),
+            assignee: row.assignee.clone(),
+            project_id: row.project_id,
+            description: row.description.clone(),
+            start_date: row.start_date,
+            due_date: row.due_date,
+            created_at: Some(row.created_at),
+            updated_at: Some(row.updated_at),
+        })
+    }
+
+    fn tasks(&self, query: &model::ListQuery) -> Result<Page<model::Task>, CustomError> {
+        let page = self.list(&TASKS, self.tasks.rows.values(), query)?;
+        map_page(page, |row| self.task_of(row))
+    }
+
+    fn task(&self, task_id: i32) -> Result<model::Task, CustomError> {
+        self.task_of(self.tasks.get(task_id, CustomError::TaskNotFound)?)
+    }
+
+    fn add_task(&mut self, create: model::CreateTask) -> Result<model::Task, CustomError> {
+        Tables::check(self.projects.has(create.project_id))?;
+        let state_id = self.first_state(WorkflowEntity::Task)?;
+        let now = Utc::now();
+        let task_id = self.tasks.insert(|task_id| TaskRow {
+            task_id,
+            title: create.title,
+            description: create.description,
+            project_id: create.project_id,
+            task_state_id: state_id,
+            blocked_reason: String::new(),
+            assignee: create.assignee,
+            start_date: create.start_date,
+            due_date: create.due_date,
+            created_at: now,
+            updated_at: now,
+        });
+        self.task(task_id)
+    }
+
+    fn save_task(
+        &mut self,
+        task_id: i32,
+        update: model::UpdateTask,
+        changed_by: &str,
+    ) -> Result<model::Task, CustomError> {
+        self.tasks.get(task_id, CustomError::TaskNotFound)?;
+        Tables::check(self.workflow_states.has(update.state_id))?;
+        let task = self.tasks.get_mut(task_id, CustomError::TaskNotFound)?;
+        let previous_state_id = task.task_state_id;
+        task.title = update.title;
+        task.task_state_id = update.state_id;
+        if let Some(blocked_reason) = update.blocked_reason {
+            task.blocked_reason = blocked_reason;
+        }
+        if let Some(description) = update.description {
+            task.description = description;
+        }
+        if let Some(start_date) = update.start_date {
+            task.start_date = start_date;
+        }
+        if let Some(due_date) = update.due_date {
+            task.due_date = due_date;
+        }
+        if let Some(assignee) = update.assignee {
+            task.assignee = assignee;
+        }
+        task.updated_at = Utc::now();
+        self.record_state_change(
+            WorkflowEntity::Task,
+            task_id,
+            previous_state_id,
+            update.state_id,
+            changed_by,
+        );
+        self.task(task_id)
+    }
+
+    fn remove_task(&mut self, task_id: i32) -> Result<(), CustomError> {
+        self.tasks.remove(task_id, CustomError::TaskNotFound)?;
+        self.remove_state_changes(WorkflowEntity::Task, task_id);
+        Ok(())
+    }
+
+    fn measurements(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Measurement>, CustomError> {
+        let page = self.list(&MEASUREMENTS, self.measurements.rows.values(), query)?;
+        map_page(page, |measurement| Ok(measurement.clone()))
+    }
+
+    fn measurement(&self, measurement_id: i32) -> Result<model::Measurement, CustomError> {
+        self.measurements
+            .get(measurement_id, CustomError::MeasurementNotFound)
+            .cloned()
+    }
+
+    fn add_measurement(
+        &mut self,
+        create: model::CreateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        Tables::check(self.keyresults.has(create.keyresult_id))?;
+        let now = Utc::now();
+        let measurement_id = self
+            .measurements
+            .insert(|measurement_id| model::Measurement {
+                measurement_id,
+                title: create.title,
+                value: create.value,
+                unit: create.unit,
+                observed_at: create.observed_at.unwrap_or(now),
+                keyresult_id: create.keyresult_id,
+                notes: create.notes,
+                source: create.source,
+                created_at: Some(now),
+                updated_at: Some(now),
+            });
+        self.measurement(measurement_id)
+    }
+
+    // Keeps the previous value, unit and observation time as a revision when
+    // any of them change.
+    fn save_measurement(
+        &mut self,
+        measurement_id: i32,
+        update: model::UpdateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        let measurement = self
+            .measurements
+            .get_mut(measurement_id, CustomError::MeasurementNotFound)?;
+        let previous = measurement.clone();
+        measurement.title = update.title;
+        measurement.value = update.value;
+        measurement.unit = update.unit;
+        if let Some(observed_at) = update.observed_at {
+            measurement.observed_at = observed_at;
+        }
+        if let Some(notes) = update.notes {
+            measurement.notes = notes;
+        }
+        if let Some(source) = update.source {
+            measurement.source = source;
+        }
+        measurement.updated_at = Some(Utc::now());
+        if (previous.value, &previous.unit, previous.observed_at)
+            != (
+                measurement.value,
+                &measurement.unit,
+                measurement.observed_at,
+            )
+        {
+            self.measurement_revisions
+                .insert(|revision_id| model::MeasurementRevision {
+                    revision_id,
+                    measurement_id,
+                    value: previous.value,
+                    unit: previous.unit,
+                    observed_at: previous.observed_at,
+                    revised_at: Utc::now(),
+                });
+        }
+        self.measurement(measurement_id)
+    }
+
+    fn remove_measurement(&mut self, measurement_id: i32) -> Result<(), CustomError> {
+        self.measurements
+            .remove(measurement_id, CustomError::MeasurementNotFound)?;
+        self.measurement_revisions
+            .rows
+            .retain(|_, revision| revision.measurement_id != measurement_id);
+        Ok(())
+    }
+
+    fn measurement_revisions(&self, measurement_id: i32) -> Vec<model::MeasurementRevision> {
+        let mut revisions: Vec<model::MeasurementRevision> = self
+            .measurement_revisions
+            .rows
+            .values()
+            .filter(|revision| revision.measurement_id == measurement_id)
+            .cloned()
+            .collect();
+        revisions.sort_by_key(|revision| Reverse((revision.revised_at, revision.revision_id)));
+        revisions
+    }
+
+    // Open items past their due date, along with their parent
+    fn overdue(&self, today: NaiveDate) -> Result<Vec<model::OverdueItem>, CustomError> {
+        let is_overdue = |due_date: Option<NaiveDate>, state_id: i32| {
+            due_date.filter(|due_date| *due_date < today).is_some()
+                && self
+                    .workflow_states
+                    .rows
+                    .get(&state_id)
+                    .is_some_and(|state| state.category != model::StateCategory::Done)
+        };
+        let item = |kind: &str,
+                    id: i32,
+                    title: &str,
+                    state_id: i32,
+                    due_date: NaiveDate,
+                    parent: (&str, i32, &str)|
+         -> Result<model::OverdueItem, CustomError> {
+            Ok(model::OverdueItem {
+                kind: kind.to_owned(),
+                id,
+                title: title.to_owned(),
+                state: self.current_state(state_id)?.title,
+                due_date,
+                parent_kind: parent.0.to_owned(),
+                parent_id: parent.1,
+                parent_title: parent.2.to_owned(),
+            })
+        };
+
+        let mut items = Vec::new();
+        for task in self.tasks.rows.values() {
+            if let (true, Some(due_date), Some(project)) = (
+                is_overdue(task.due_date, task.task_state_id),
+                task.due_date,
+                self.projects.rows.get(&task.project_id),
+            ) {
+                items.push(item(
+                    "task",
+                    task.task_id,
+                    &task.title,
+                    task.task_state_id,
+                    due_date,
+                    ("project", project.project_id, &project.title),
+                )?);
+            }
+        }
+        for project in self.projects.rows.values() {
+            if let (true, Some(due_date), Some(objective)) = (
+                is_overdue(project.due_date, project.project_state_id),
+                project.due_date,
+                self.objectives.rows.get(&project.objective_id),
+            ) {
+                items.push(item(
+                    "project",
+                    project.project_id,
+                    &project.title,
+                    project.project_state_id,
+                    due_date,
+                    ("objective", objective.objective_id, &objective.title),
+                )?);
+            }
+        }
+        for initiative in self.initiatives.rows.values() {
+            if let (true, Some(due_date), Some(objective)) = (
+                is_overdue(initiative.due_date, initiative.initiative_state_id),
+                initiative.due_date,
+                self.objectives.rows.get(&initiative.objective_id),
+            ) {
+                items.push(item(
+                    "initiative",
+                    initiative.initiative_id,
+                    &initiative.title,
+                    initiative.initiative_state_id,
+                    due_date,
+                    ("objective", objective.objective_id, &objective.title),
+                )?);
+            }
+        }
+        items.sort_by(|a, b| (a.due_date, &a.kind, a.id).cmp(&(b.due_date, &b.kind, b.id)));
+        Ok(items)
+    }
+}
+
+/// Keeps everything in memory, for demos, tests and trying out templates
+/// without a database. Nothing is kept once the app stops.
+#[derive(Debug)]
+pub struct MemoryStore {
+    tables: RwLock<Tables>,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        MemoryStore::new()
+    }
+}
+
+impl MemoryStore {
+    /// An empty store, with the default workflows.
+    pub fn new() -> MemoryStore {
+        MemoryStore {
+            tables: RwLock::new(Tables::with_default_workflows()),
+        }
+    }
+
+    pub fn from_fixture(fixture: Fixture) -> Result<MemoryStore, String> {
+        Ok(MemoryStore {
+            tables: RwLock::new(Tables::from_fixture(fixture)?),
+        })
+    }
+
+    /// A store seeded from the JSON fixture at `path`.
+    pub fn load(path: &Path) -> Result<MemoryStore, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+        let fixture =
+            serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(&json))
+                .map_err(|err| format!("{} is not a valid fixture: {err}", path.display()))?;
+        MemoryStore::from_fixture(fixture).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, Tables>, CustomError> {
+        self.tables
+            .read()
+            .map_err(|_| CustomError::InternalServerError)
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, Tables>, CustomError> {
+        self.tables
+            .write()
+            .map_err(|_| CustomError::InternalServerError)
+    }
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+    async fn cycles(&self, query: &model::ListQuery) -> Result<Page<model::Cycle>, CustomError> {
+        let tables = self.read()?;
+        let page = tables.list(&CYCLES, tables.cycles.rows.values(), query)?;
+        map_page(page, |cycle| Ok(cycle.clone()))
+    }
+
+    async fn cycle(&self, cycle_id: i32) -> Result<model::Cycle, CustomError> {
+        self.read()?.cycle(cycle_id)
+    }
+
+    async fn add_cycle(&self, create: model::CreateCycle) -> Result<model::Cycle, CustomError> {
+        self.write()?.add_cycle(create)
+    }
+
+    async fn save_cycle(
+        &self,
+        cycle_id: i32,
+        update: model::UpdateCycle,
+    ) -> Result<model::Cycle, CustomError> {
+        self.write()?.save_cycle(cycle_id, update)
+    }
+
+    async fn remove_cycle(&self, cycle_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_cycle(cycle_id)
+    }
+
+    async fn carry_over(
+        &self,
+        cycle_id: i32,
+        objectives: Vec<CarriedObjective>,
+    ) -> Result<(), CustomError> {
+        self.write()?.carry_over(cycle_id, objectives)
+    }
+
+    async fn workflow_states(
+        &self,
+        entity: Option<WorkflowEntity>,
+    ) -> Result<Vec<model::WorkflowState>, CustomError> {
+        Ok(self.read()?.workflow_states(entity))
+    }
+
+    async fn workflow_state(&self, state_id: i32) -> Result<model::WorkflowState, CustomError> {
+        self.read()?.workflow_state(state_id)
+    }
+
+    async fn add_workflow_state(
+        &self,
+        create: model::CreateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        self.write()?.add_workflow_state(create)
+    }
+
+    async fn save_workflow_state(
+        &self,
+        state_id: i32,
+        update: model::UpdateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        self.write()?.save_workflow_state(state_id, update)
+    }
+
+    async fn remove_workflow_state(&self, state_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_workflow_state(state_id)
+    }
+
+    async fn state_changes(
+        &self,
+        entity: WorkflowEntity,
+        item_id: i32,
+    ) -> Result<Vec<model::StateChange>, CustomError> {
+        Ok(self.read()?.state_changes(entity, item_id))
+    }
+
+    async fn themes(&self, query: &model::ListQuery) -> Result<Page<model::Theme>, CustomError> {
+        self.read()?.themes(query)
+    }
+
+    async fn theme(&self, theme_id: i32) -> Result<model::Theme, CustomError> {
+        self.read()?.theme(theme_id)
+    }
+
+    async fn add_theme(&self, create: model::CreateTheme) -> Result<model::Theme, CustomError> {
+        self.write()?.add_theme(create)
+    }
+
+    async fn save_theme(
+        &self,
+        theme_id: i32,
+        update: model::UpdateTheme,
+        changed_by: &str,
+    ) -> Result<model::Theme, CustomError> {
+        self.write()?.save_theme(theme_id, update, changed_by)
+    }
+
+    async fn remove_theme(&self, theme_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_theme(theme_id)
+    }
+
+    async fn objectives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Objective>, CustomError> {
+        self.read()?.objectives(query)
+    }
+
+    async fn objective(&self, objective_id: i32) -> Result<model::Objective, CustomError> {
+        self.read()?.objective(objective_id)
+    }
+
+    async fn add_objective(
+        &self,
+        create: model::CreateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        self.write()?.add_objective(create)
+    }
+
+    async fn save_objective(
+        &self,
+        objective_id: i32,
+        update: model::UpdateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        self.write()?.save_objective(objective_id, update)
+    }
+
+    async fn remove_objective(&self, objective_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_objective(objective_id)
+    }
+
+    async fn keyresults(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::KeyResult>, CustomError> {
+        self.read()?.keyresults(query)
+    }
+
+    async fn keyresult(&self, keyresult_id: i32) -> Result<model::KeyResult, CustomError> {
+        self.read()?.keyresult(keyresult_id)
+    }
+
+    async fn add_keyresult(
+        &self,
+        create: model::CreateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        self.write()?.add_keyresult(create)
+    }
+
+    async fn save_keyresult(
+        &self,
+        keyresult_id: i32,
+        update: model::UpdateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        self.write()?.save_keyresult(keyresult_id, update)
+    }
+
+    async fn remove_keyresult(&self, keyresult_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_keyresult(keyresult_id)
+    }
+
+    async fn initiatives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Initiative>, CustomError> {
+        self.read()?.initiatives(query)
+    }
+
+    async fn initiative(&self, initiative_id: i32) -> Result<model::Initiative, CustomError> {
+        self.read()?.initiative(initiative_id)
+    }
+
+    async fn add_initiative(
+        &self,
+        create: model::CreateInitiative,
+    ) -> Result<model::Initiative, CustomError> {
+        self.write()?.add_initiative(create)
+    }
+
+    async fn save_initiative(
+        &self,
+        initiative_id: i32,
+        update: model::UpdateInitiative,
+        changed_by: &str,
+    ) -> Result<model::Initiative, CustomError> {
+        self.write()?
+            .save_initiative(initiative_id, update, changed_by)
+    }
+
+    async fn remove_initiative(&self, initiative_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_initiative(initiative_id)
+    }
+
+    async fn projects(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Project>, CustomError> {
+        self.read()?.projects(query)
+    }
+
+    async fn project(&self, project_id: i32) -> Result<model::Project, CustomError> {
+        self.read()?.project(project_id)
+    }
+
+    async fn add_project(
+        &self,
+        create: model::CreateProject,
+    ) -> Result<model::Project, CustomError> {
+        self.write()?.add_project(create)
+    }
+
+    async fn save_project(
+        &self,
+        project_id: i32,
+        update: model::UpdateProject,
+        changed_by: &str,
+    ) -> Result<model::Project, CustomError> {
+        self.write()?.save_project(project_id, update, changed_by)
+    }
+
+    async fn remove_project(&self, project_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_project(project_id)
+    }
+
+    async fn tasks(&self, query: &model::ListQuery) -> Result<Page<model::Task>, CustomError> {
+        self.read()?.tasks(query)
+    }
+
+    async fn task(&self, task_id: i32) -> Result<model::Task, CustomError> {
+        self.read()?.task(task_id)
+    }
+
+    async fn add_task(&self, create: model::CreateTask) -> Result<model::Task, CustomError> {
+        self.write()?.add_task(create)
+    }
+
+    async fn save_task(
+        &self,
+        task_id: i32,
+        update: model::UpdateTask,
+        changed_by: &str,
+    ) -> Result<model::Task, CustomError> {
+        self.write()?.save_task(task_id, update, changed_by)
+    }
+
+    async fn remove_task(&self, task_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_task(task_id)
+    }
+
+    async fn measurements(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Measurement>, CustomError> {
+        self.read()?.measurements(query)
+    }
+
+    async fn measurement(&self, measurement_id: i32) -> Result<model::Measurement, CustomError> {
+        self.read()?.measurement(measurement_id)
+    }
+
+    async fn add_measurement(
+        &self,
+        create: model::CreateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        self.write()?.add_measurement(create)
+    }
+
+    async fn save_measurement(
+        &self,
+        measurement_id: i32,
+        update: model::UpdateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        self.write()?.save_measurement(measurement_id, update)
+    }
+
+    async fn remove_measurement(&self, measurement_id: i32) -> Result<(), CustomError> {
+        self.write()?.remove_measurement(measurement_id)
+    }
+
+    async fn measurement_revisions(
+        &self,
+        measurement_id: i32,
+    ) -> Result<Vec<model::MeasurementRevision>, CustomError> {
+        Ok(self.read()?.measurement_revisions(measurement_id))
+    }
+
+    async fn overdue(&self, today: NaiveDate) -> Result<Vec<model::OverdueItem>, CustomError> {
+        self.read()?.overdue(today)
+    }
+}
@@ -0,0 +1,183 @@
+use sqlx::migrate::{Migrate, Migration, Migrator};
+
+/// Postgres schema changes, numbered in the order they are applied.
+pub static POSTGRES: Migrator = sqlx::migrate!("migrations/postgres");
+
+/// The same changes for SQLite.
+pub static SQLITE: Migrator = sqlx::migrate!("migrations/sqlite");
+
+// Records a migration as applied without running it
+pub const ADOPT: &str = r#"
+    INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time)
+    VALUES ($1, $2, TRUE, $3, 0)"#;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum State {
+    Applied,
+    Pending,
+    // Applied, but failed part way through
+    Failed,
+    // Applied by a newer version of the app
+    Unknown,
+}
+
+impl std::fmt::Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            State::Applied => f.write_str("applied"),
+            State::Pending => f.write_str("pending"),
+            State::Failed => f.write_str("failed"),
+            State::Unknown => f.write_str("applied, unknown to this version"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: State,
+}
+
+/// The migration that sets up a new database.
+pub fn initial(migrator: &Migrator) -> Option<&Migration> {
+    migrator
+        .iter()
+        .find(|migration| !migration.migration_type.is_down_migration())
+}
+
+/// The columns in `expected` that `existing` lacks, as table.column, or just
+/// the table when it is missing altogether. Both list (table, column) pairs.
+pub fn missing_columns(
+    expected: &[(String, String)],
+    existing: &[(String, String)],
+) -> Vec<String> {
+    let mut missing: Vec<String> = expected
+        .iter()
+        .filter(|column| !existing.contains(column))
+        .map(|(table, column)| {
+            if existing.iter().any(|(existing, _)| existing == table) {
+                format!("{table}.{column}")
+            } else {
+                table.clone()
+            }
+        })
+        .collect();
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+/// Whether no migrations have been recorded, e.g. because the database was
+/// set up by hand before they were applied on boot.
+pub async fn untracked<C: Migrate>(conn: &mut C) -> Result<bool, String> {
+    conn.ensure_migrations_table()
+        .await
+        .map_err(|err| format!("could not read applied migrations: {err}"))?;
+    let applied = conn
+        .list_applied_migrations()
+        .await
+        .map_err(|err| format!("could not read applied migrations: {err}"))?;
+    Ok(applied.is_empty())
+}
+
+/// Every migration this version knows about, and any others the database has
+/// had applied, by version.
+pub async fn status<C: Migrate>(
+    migrator: &Migrator,
+    conn: &mut C,
+) -> Result<Vec<MigrationStatus>, String> {
+    let read_error = |err| format!("could not read applied migrations: {err}");
+    conn.ensure_migrations_table().await.map_err(read_error)?;
+    let failed = conn.dirty_version().await.map_err(read_error)?;
+    let applied = conn.list_applied_migrations().await.map_err(read_error)?;
+
+    let mut statuses: Vec<MigrationStatus> = migrator
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            state: if failed == Some(migration.version) {
+                State::Failed
+            } else if applied
+                .iter()
+                .any(|applied| applied.version == migration.version)
+            {
+                State::Applied
+            } else {
+                State::Pending
+            },
+        })
+        .collect();
+    for applied in applied {
+        if !migrator
+            .iter()
+            .any(|migration| migration.version == applied.version)
+        {
+            statuses.push(MigrationStatus {
+                version: applied.version,
+                description: String::new(),
+                state: State::Unknown,
+            });
+        }
+    }
+    statuses.sort_by_key(|status| status.version);
+    Ok(statuses)
+}
+
+/// Applies pending migrations in order. A database with migrations this
+/// version doesn't know about is left alone, since the app would not
+/// understand its schema.
+pub async fn run<C: Migrate>(migrator: &Migrator, conn: &mut C) -> Result<(), String> {
+    let statuses = status(migrator, conn).await?;
+    if let Some(newer) = statuses
+        .iter()
+        .find(|status| status.state == State::Unknown)
+    {
+        return Err(format!(
+            "the database has migration {} applied, which this version doesn't know about. \
+             It was migrated by a newer version, so run that instead",
+            newer.version
+        ));
+    }
+    let pending = statuses
+        .iter()
+        .filter(|status| status.state == State::Pending)
+        .count();
+    if pending > 0 {
+        tracing::info!("applying {pending} database migration(s)");
+    }
+    migrator
+        .run_direct(conn)
+        .await
+        .map_err(|err| format!("could not apply migrations: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::missing_columns;
+
+    fn columns(names: &[(&str, &str)]) -> Vec<(String, String)> {
+        names
+            .iter()
+            .map(|(table, column)| (table.to_string(), column.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn names_missing_columns_and_whole_missing_tables() {
+        let expected = columns(&[
+            ("tasks", "task_id"),
+            ("tasks", "assignee"),
+            ("state_changes", "change_id"),
+            ("state_changes", "to_state"),
+        ]);
+        let existing = columns(&[("tasks", "task_id"), ("tasks", "task_status")]);
+        assert_eq!(
+            missing_columns(&expected, &existing),
+            ["state_changes", "tasks.assignee"]
+        );
+        assert!(missing_columns(&expected, &expected).is_empty());
+    }
+}
@@ -0,0 +1,1166 @@
+use std::time::Duration;
+
+use axum::async_trait;
+use sqlx::{
+    migrate::Migration,
+    postgres::{PgConnection, PgPoolOptions, PgRow},
+    Connection, Executor, FromRow, PgPool, Postgres, QueryBuilder,
+};
+
+use super::{
+    migrate, CarriedObjective, Listing, Page, Store, CYCLES, INITIATIVES, KEYRESULTS,
+    KEYRESULT_IN_USE, MEASUREMENTS, OBJECTIVES, OBJECTIVE_IN_USE, PROJECTS, PROJECT_IN_USE, TASKS,
+    THEMES, THEME_IN_USE, WORKFLOW_STATE_IN_USE,
+};
+use crate::{errors::CustomError, model};
+
+// Key results are selected along with their latest measurement, so that their
+// progress can be shown wherever they are rendered.
+const SELECT_KEYRESULTS: &str = r#"
+    SELECT keyresults.*, (
+        SELECT measurements.value
+        FROM measurements
+        WHERE measurements.keyresult_id = keyresults.keyresult_id
+        ORDER BY measurements.observed_at DESC
+        LIMIT 1
+    ) AS latest_value
+    FROM keyresults"#;
+
+// Themes, initiatives, projects and tasks are selected along with the workflow
+// state they are in, under the names model::CurrentState expects.
+const SELECT_THEMES: &str = r#"
+    SELECT themes.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM themes
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = themes.theme_state_id"#;
+
+const SELECT_INITIATIVES: &str = r#"
+    SELECT initiatives.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM initiatives
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = initiatives.initiative_state_id"#;
+
+const SELECT_PROJECTS: &str = r#"
+    SELECT projects.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM projects
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = projects.project_state_id"#;
+
+const SELECT_TASKS: &str = r#"
+    SELECT tasks.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM tasks
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = tasks.task_state_id"#;
+
+// Workflow states are selected along with the states they lead to.
+const SELECT_WORKFLOW_STATES: &str = r#"
+    SELECT workflow_states.*, ARRAY(
+        SELECT to_state_id FROM workflow_transitions
+        WHERE from_state_id = workflow_states.state_id
+        ORDER BY to_state_id
+    ) AS next_state_ids
+    FROM workflow_states"#;
+
+// Updates a measurement, keeping its previous value, unit and observation time
+// as a revision when any of them change. Binds title, value, unit, observed_at,
+// notes, source and measurement_id, and returns the updated row.
+const UPDATE_MEASUREMENT: &str = r#"
+    WITH previous AS (
+        SELECT * FROM measurements WHERE measurement_id = $7
+    ), updated AS (
+        UPDATE measurements
+        SET title=$1, value=$2, unit=$3, observed_at=COALESCE($4, observed_at),
+            notes=COALESCE($5, notes), source=COALESCE($6, source), updated_at=NOW()
+        WHERE measurement_id=$7
+        RETURNING *
+    ), revision AS (
+        INSERT INTO measurement_revisions (measurement_id, value, unit, observed_at)
+        SELECT previous.measurement_id, previous.value, previous.unit, previous.observed_at
+        FROM previous JOIN updated USING (measurement_id)
+        WHERE (previous.value, previous.unit, previous.observed_at)
+            IS DISTINCT FROM (updated.value, updated.unit, updated.observed_at)
+    )
+    SELECT * FROM updated"#;
+
+// Inserts themes, initiatives, projects and tasks in the first state of their
+// workflow, returning the new id.
+const INSERT_THEME: &str = r#"
+    INSERT INTO themes (title, description, theme_state_id)
+    VALUES ($1, $2, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Theme'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))
+    RETURNING theme_id"#;
+
+const INSERT_INITIATIVE: &str = r#"
+    INSERT INTO initiatives (title, objective_id, description, start_date, due_date, initiative_state_id)
+    VALUES ($1, $2, $3, $4, $5, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Initiative'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))
+    RETURNING initiative_id"#;
+
+const INSERT_PROJECT: &str = r#"
+    INSERT INTO projects (title, objective_id, description, start_date, due_date, project_state_id)
+    VALUES ($1, $2, $3, $4, $5, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Project'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))
+    RETURNING project_id"#;
+
+const INSERT_TASK: &str = r#"
+    INSERT INTO tasks (title, project_id, description, start_date, due_date, assignee, task_state_id)
+    VALUES ($1, $2, $3, $4, $5, $6, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Task'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))
+    RETURNING task_id"#;
+
+// Updates themes, initiatives, projects and tasks, recording a state change
+// along with who made it when their state moves. Themes bind title, state_id,
+// blocked_reason, description, theme_id and changed_by; the others also bind
+// start_date and due_date before their id, and tasks their assignee after those.
+// Dated items then bind whether start_date and due_date were given, so that
+// dates left out of an update stay as they are; a missing reason, description
+// or assignee does too. Returns the id, if the item exists.
+const UPDATE_THEME: &str = r#"
+    WITH previous AS (
+        SELECT theme_id, theme_state_id FROM themes WHERE theme_id = $5
+    ), updated AS (
+        UPDATE themes
+        SET title=$1, theme_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+            updated_at=NOW()
+        WHERE theme_id=$5
+        RETURNING theme_id, theme_state_id
+    ), change AS (
+        INSERT INTO state_changes (theme_id, from_state_id, from_state, to_state_id, to_state, changed_by)
+        SELECT updated.theme_id, from_states.state_id, from_states.title,
+            to_states.state_id, to_states.title, $6
+        FROM previous JOIN updated USING (theme_id)
+        JOIN workflow_states AS from_states ON from_states.state_id = previous.theme_state_id
+        JOIN workflow_states AS to_states ON to_states.state_id = updated.theme_state_id
+        WHERE previous.theme_state_id <> updated.theme_state_id
+    )
+    SELECT theme_id FROM updated"#;
+
+const UPDATE_INITIATIVE: &str = r#"
+    WITH previous AS (
+        SELECT initiative_id, initiative_state_id FROM initiatives WHERE initiative_id = $7
+    ), updated AS (
+        UPDATE initiatives
+        SET title=$1, initiative_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+            start_date=CASE WHEN $9 THEN $5 ELSE start_date END,
+            due_date=CASE WHEN $10 THEN $6 ELSE due_date END, updated_at=NOW()
+        WHERE initiative_id=$7
+        RETURNING initiative_id, initiative_state_id
+    ), change AS (
+        INSERT INTO state_changes (initiative_id, from_state_id, from_state, to_state_id, to_state, changed_by)
+        SELECT updated.initiative_id, from_states.state_id, from_states.title,
+            to_states.state_id, to_states.title, $8
+        FROM previous JOIN updated USING (initiative_id)
+        JOIN workflow_states AS from_states ON from_states.state_id = previous.initiative_state_id
+        JOIN workflow_states AS to_states ON to_states.state_id = updated.initiative_state_id
+        WHERE previous.initiative_state_id <> updated.initiative_state_id
+    )
+    SELECT initiative_id FROM updated"#;
+
+const UPDATE_PROJECT: &str = r#"
+    WITH previous AS (
+        SELECT project_id, project_state_id FROM projects WHERE project_id = $7
+    ), updated AS (
+        UPDATE projects
+        SET title=$1, project_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+            start_date=CASE WHEN $9 THEN $5 ELSE start_date END,
+            due_date=CASE WHEN $10 THEN $6 ELSE due_date END, updated_at=NOW()
+        WHERE project_id=$7
+        RETURNING project_id, project_state_id
+    ), change AS (
+        INSERT INTO state_changes (project_id, from_state_id, from_state, to_state_id, to_state, changed_by)
+        SELECT updated.project_id, from_states.state_id, from_states.title,
+            to_states.state_id, to_states.title, $8
+        FROM previous JOIN updated USING (project_id)
+        JOIN workflow_states AS from_states ON from_states.state_id = previous.project_state_id
+        JOIN workflow_states AS to_states ON to_states.state_id = updated.project_state_id
+        WHERE previous.project_state_id <> updated.project_state_id
+    )
+    SELECT project_id FROM updated"#;
+
+const UPDATE_TASK: &str = r#"
+    WITH previous AS (
+        SELECT task_id, task_state_id FROM tasks WHERE task_id = $8
+    ), updated AS (
+        UPDATE tasks
+        SET title=$1, task_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+            start_date=CASE WHEN $10 THEN $5 ELSE start_date END,
+            due_date=CASE WHEN $11 THEN $6 ELSE due_date END, assignee=COALESCE($7, assignee),
+            updated_at=NOW()
+        WHERE task_id=$8
+        RETURNING task_id, task_state_id
+    ), change AS (
+        INSERT INTO state_changes (task_id, from_state_id, from_state, to_state_id, to_state, changed_by)
+        SELECT updated.task_id, from_states.state_id, from_states.title,
+            to_states.state_id, to_states.title, $9
+        FROM previous JOIN updated USING (task_id)
+        JOIN workflow_states AS from_states ON from_states.state_id = previous.task_state_id
+        JOIN workflow_states AS to_states ON to_states.state_id = updated.task_state_id
+        WHERE previous.task_state_id <> updated.task_state_id
+    )
+    SELECT task_id FROM updated"#;
+
+// Open items past their due date, along with their parent. Binds today's date.
+const SELECT_OVERDUE: &str = r#"
+    SELECT 'task' AS kind, tasks.task_id AS id, tasks.title, states.title AS state,
+        tasks.due_date, 'project' AS parent_kind, projects.project_id AS parent_id,
+        projects.title AS parent_title
+    FROM tasks
+    JOIN projects ON tasks.project_id = projects.project_id
+    JOIN workflow_states AS states ON tasks.task_state_id = states.state_id
+    WHERE tasks.due_date < $1 AND states.category <> 'Done'
+    UNION ALL
+    SELECT 'project', projects.project_id, projects.title, states.title,
+        projects.due_date, 'objective', objectives.objective_id, objectives.title
+    FROM projects
+    JOIN objectives ON projects.objective_id = objectives.objective_id
+    JOIN workflow_states AS states ON projects.project_state_id = states.state_id
+    WHERE projects.due_date < $1 AND states.category <> 'Done'
+    UNION ALL
+    SELECT 'initiative', initiatives.initiative_id, initiatives.title, states.title,
+        initiatives.due_date, 'objective', objectives.objective_id, objectives.title
+    FROM initiatives
+    JOIN objectives ON initiatives.objective_id = objectives.objective_id
+    JOIN workflow_states AS states ON initiatives.initiative_state_id = states.state_id
+    WHERE initiatives.due_date < $1 AND states.category <> 'Done'
+    ORDER BY due_date, kind, id"#;
+
+// The tables and columns of the schema in use
+const SELECT_COLUMNS: &str = r#"
+    SELECT table_name::TEXT, column_name::TEXT
+    FROM information_schema.columns
+    WHERE table_schema = current_schema()"#;
+
+/// Stores everything in a Postgres database, set up by the migrations in
+/// migrations/postgres.
+pub struct PgStore {
+    pool: PgPool,
+}
+
+impl PgStore {
+    pub fn new(pool: PgPool) -> PgStore {
+        PgStore { pool }
+    }
+
+    /// Connects to the database at `url`. Call `migrate` before using it.
+    pub async fn connect(
+        url: &str,
+        max_connections: u32,
+        acquire_timeout: Duration,
+    ) -> Result<PgStore, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(acquire_timeout)
+            .connect(url)
+            .await?;
+        Ok(PgStore::new(pool))
+    }
+
+    /// Brings the schema up to date.
+    pub async fn migrate(&self) -> Result<(), String> {
+        let mut conn = self.pool.acquire().await.map_err(|err| err.to_string())?;
+        let (set_up,): (bool,) = sqlx::query_as("SELECT to_regclass('themes') IS NOT NULL")
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|err| err.to_string())?;
+        if let Some(initial) = migrate::initial(&migrate::POSTGRES) {
+            // Databases set up by hand before migrations were tracked have the
+            // initial schema, and the later migrations upgrade them from there
+            if set_up && migrate::untracked(&mut *conn).await? {
+                let missing = migrate::missing_columns(
+                    &initial_columns(&mut conn, initial).await?,
+                    &columns(&mut conn).await?,
+                );
+                if !missing.is_empty() {
+                    return Err(format!(
+                        "the database has tables, but not the initial migration's {}, so it \
+                         was not set up by this app",
+                        missing.join(", ")
+                    ));
+                }
+                tracing::info!(
+                    "recording the existing schema as migration {}",
+                    initial.version
+                );
+                sqlx::query(migrate::ADOPT)
+                    .bind(initial.version)
+                    .bind(&*initial.description)
+                    .bind(&*initial.checksum)
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+        migrate::run(&migrate::POSTGRES, &mut *conn).await
+    }
+
+    pub async fn migration_status(&self) -> Result<Vec<migrate::MigrationStatus>, String> {
+        let mut conn = self.pool.acquire().await.map_err(|err| err.to_string())?;
+        migrate::status(&migrate::POSTGRES, &mut *conn).await
+    }
+
+    async fn fetch<T>(&self, sql: &str, id: i32, not_found: CustomError) -> Result<T, CustomError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        sqlx::query_as(sql)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, not_found))
+    }
+
+    // Deletes a row, refusing with `in_use` while other rows refer to it
+    async fn delete(
+        &self,
+        sql: &str,
+        id: i32,
+        not_found: CustomError,
+        in_use: Option<&'static str>,
+    ) -> Result<(), CustomError> {
+        match sqlx::query(sql).bind(id).execute(&self.pool).await {
+            Ok(result) if result.rows_affected() > 0 => Ok(()),
+            Ok(_) => Err(not_found),
+            Err(err) => Err(CustomError::from_sqlx_delete(err, not_found, in_use)),
+        }
+    }
+
+    // Shared implementation of the listings: filters, sorts and pages the rows,
+    // and counts them unpaged.
+    async fn list<T>(
+        &self,
+        listing: &Listing,
+        select: &str,
+        query: &model::ListQuery,
+    ) -> Result<Page<T>, CustomError>
+    where
+        T: for<'r> FromRow<'r, PgRow> + Send + Unpin,
+    {
+        let (sort_column, descending) = listing.order(query)?;
+
+        let mut count = QueryBuilder::new(format!("SELECT COUNT(*) FROM {}", listing.table));
+        push_filters(&mut count, listing, query);
+        let (total,): (i64,) = count
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::InternalServerError))?;
+
+        let mut select = QueryBuilder::new(select);
+        push_filters(&mut select, listing, query);
+        select.push(format!(
+            " ORDER BY {sort_column} {direction}, {id_column}",
+            direction = if descending { "DESC" } else { "ASC" },
+            id_column = listing.id_column,
+        ));
+        select.push(" LIMIT ").push_bind(query.limit);
+        select
+            .push(" OFFSET ")
+            .push_bind(query.offset.unwrap_or(0).max(0));
+        let items = select
+            .build_query_as::<T>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::InternalServerError))?;
+        Ok(Page { items, total })
+    }
+}
+
+async fn columns(conn: &mut PgConnection) -> Result<Vec<(String, String)>, String> {
+    sqlx::query_as(SELECT_COLUMNS)
+        .fetch_all(conn)
+        .await
+        .map_err(|err| format!("could not read the schema: {err}"))
+}
+
+// The columns the initial migration creates, read from a scratch schema that it
+// is applied to and which is then rolled back
+async fn initial_columns(
+    conn: &mut PgConnection,
+    initial: &Migration,
+) -> Result<Vec<(String, String)>, String> {
+    let error =
+        |err: sqlx::Error| format!("could not apply the initial migration to compare: {err}");
+    let mut tx = conn.begin().await.map_err(error)?;
+    tx.execute("CREATE SCHEMA _tokip_initial; SET LOCAL search_path TO _tokip_initial")
+        .await
+        .map_err(error)?;
+    tx.execute(&*initial.sql).await.map_err(error)?;
+    let expected = columns(&mut tx).await?;
+    tx.rollback().await.map_err(error)?;
+    Ok(expected)
+}
+
+fn push_filters(
+    builder: &mut QueryBuilder<'_, Postgres>,
+    listing: &Listing,
+    query: &model::ListQuery,
+) {
+    builder.push(" WHERE TRUE");
+    if let (Some(column), Some(parent_ids)) = (listing.parent_column, listing.parent_ids(query)) {
+        builder
+            .push(format!(" AND {column} = ANY("))
+            .push_bind(parent_ids)
+            .push(")");
+    }
+    if let (Some(column), Some(state_id)) = (listing.state_column, query.state_id) {
+        builder
+            .push(format!(" AND {column} = "))
+            .push_bind(state_id);
+    }
+    if let (Some(column), Some(category)) = (listing.state_column, query.category) {
+        builder
+            .push(format!(
+                " AND {column} IN (SELECT state_id FROM workflow_states WHERE category = "
+            ))
+            .push_bind(category)
+            .push(")");
+    }
+    if let (Some(column), Some(cycle_id)) = (listing.cycle_column, query.cycle_id) {
+        builder
+            .push(format!(" AND {column} = "))
+            .push_bind(cycle_id);
+    }
+    for (condition, bound) in [
+        (" AND created_at >= ", query.created_after),
+        (" AND created_at <= ", query.created_before),
+        (" AND updated_at >= ", query.updated_after),
+        (" AND updated_at <= ", query.updated_before),
+    ] {
+        if let Some(bound) = bound {
+            builder.push(condition).push_bind(bound);
+        }
+    }
+}
+
+#[async_trait]
+impl Store for PgStore {
+    async fn cycles(&self, query: &model::ListQuery) -> Result<Page<model::Cycle>, CustomError> {
+        self.list(&CYCLES, "SELECT * FROM cycles", query).await
+    }
+
+    async fn cycle(&self, cycle_id: i32) -> Result<model::Cycle, CustomError> {
+        self.fetch(
+            "SELECT * FROM cycles WHERE cycle_id = $1",
+            cycle_id,
+            CustomError::CycleNotFound,
+        )
+        .await
+    }
+
+    async fn add_cycle(&self, create: model::CreateCycle) -> Result<model::Cycle, CustomError> {
+        sqlx::query_as(
+            r#"INSERT INTO cycles (title, start_date, end_date) VALUES ($1, $2, $3) RETURNING *;"#,
+        )
+        .bind(create.title)
+        .bind(create.start_date)
+        .bind(create.end_date)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))
+    }
+
+    async fn save_cycle(
+        &self,
+        cycle_id: i32,
+        update: model::UpdateCycle,
+    ) -> Result<model::Cycle, CustomError> {
+        sqlx::query_as(
+            r#"UPDATE cycles
+            SET title=$1, start_date=$2, end_date=$3, updated_at=NOW()
+            WHERE cycle_id=$4 RETURNING *;"#,
+        )
+        .bind(update.title)
+        .bind(update.start_date)
+        .bind(update.end_date)
+        .bind(cycle_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::CycleNotFound))
+    }
+
+    async fn remove_cycle(&self, cycle_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM cycles WHERE cycle_id = $1",
+            cycle_id,
+            CustomError::CycleNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn carry_over(
+        &self,
+        cycle_id: i32,
+        objectives: Vec<CarriedObjective>,
+    ) -> Result<(), CustomError> {
+        let mut transaction = self.pool.begin().await?;
+        for CarriedObjective {
+            objective,
+            keyresults,
+        } in objectives
+        {
+            let (copy_id,): (i32,) = sqlx::query_as(
+                r#"INSERT INTO objectives (title, description, theme_id, cycle_id, carried_from)
+                VALUES ($1, $2, $3, $4, $5) RETURNING objective_id;"#,
+            )
+            .bind(&objective.title)
+            .bind(&objective.description)
+            .bind(objective.theme_id)
+            .bind(cycle_id)
+            .bind(objective.objective_id)
+            .fetch_one(&mut *transaction)
+            .await?;
+            for keyresult in keyresults {
+                sqlx::query(
+                    r#"INSERT INTO keyresults (title, description, objective_id, baseline, target, unit, direction, weight)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8);"#,
+                )
+                .bind(&keyresult.title)
+                .bind(&keyresult.description)
+                .bind(copy_id)
+                .bind(keyresult.baseline)
+                .bind(keyresult.target)
+                .bind(&keyresult.unit)
+                .bind(keyresult.direction)
+                .bind(keyresult.weight)
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn workflow_states(
+        &self,
+        entity: Option<model::WorkflowEntity>,
+    ) -> Result<Vec<model::WorkflowState>, CustomError> {
+        sqlx::query_as(&format!(
+            "{SELECT_WORKFLOW_STATES} WHERE $1::workflow_entity IS NULL OR entity = $1 \
+            ORDER BY entity, position, state_id"
+        ))
+        .bind(entity)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::InternalServerError))
+    }
+
+    async fn workflow_state(&self, state_id: i32) -> Result<model::WorkflowState, CustomError> {
+        self.fetch(
+            &format!("{SELECT_WORKFLOW_STATES} WHERE state_id = $1"),
+            state_id,
+            CustomError::WorkflowStateNotFound,
+        )
+        .await
+    }
+
+    async fn add_workflow_state(
+        &self,
+        create: model::CreateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        let mut transaction = self.pool.begin().await?;
+        let state_id: i32 = sqlx::query_scalar(
+            r#"INSERT INTO workflow_states (entity, title, category, requires_reason, position)
+            VALUES ($1, $2, $3, $4, (
+                SELECT COALESCE(MAX(position) + 1, 0) FROM workflow_states WHERE entity = $1
+            ))
+            RETURNING state_id;"#,
+        )
+        .bind(create.entity)
+        .bind(create.title)
+        .bind(create.category)
+        .bind(create.requires_reason)
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        sqlx::query(
+            r#"INSERT INTO workflow_transitions (from_state_id, to_state_id)
+            SELECT $1, state_id FROM workflow_states WHERE entity = $2 AND state_id <> $1
+            UNION ALL
+            SELECT state_id, $1 FROM workflow_states WHERE entity = $2 AND state_id <> $1;"#,
+        )
+        .bind(state_id)
+        .bind(create.entity)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        self.workflow_state(state_id).await
+    }
+
+    async fn save_workflow_state(
+        &self,
+        state_id: i32,
+        update: model::UpdateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"UPDATE workflow_states
+            SET title=$1, category=$2, position=$3, requires_reason=$4, updated_at=NOW()
+            WHERE state_id=$5;"#,
+        )
+        .bind(update.title)
+        .bind(update.category)
+        .bind(update.position)
+        .bind(update.requires_reason)
+        .bind(state_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::WorkflowStateNotFound))?;
+        if result.rows_affected() == 0 {
+            return Err(CustomError::WorkflowStateNotFound);
+        }
+        if let Some(next_state_ids) = update.next_state_ids {
+            sqlx::query(r#"DELETE FROM workflow_transitions WHERE from_state_id = $1;"#)
+                .bind(state_id)
+                .execute(&mut *transaction)
+                .await?;
+            sqlx::query(
+                r#"INSERT INTO workflow_transitions (from_state_id, to_state_id)
+                SELECT $1, UNNEST($2::INT[]);"#,
+            )
+            .bind(state_id)
+            .bind(next_state_ids)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        self.workflow_state(state_id).await
+    }
+
+    async fn remove_workflow_state(&self, state_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM workflow_states WHERE state_id = $1",
+            state_id,
+            CustomError::WorkflowStateNotFound,
+            Some(WORKFLOW_STATE_IN_USE),
+        )
+        .await
+    }
+
+    async fn state_changes(
+        &self,
+        entity: model::WorkflowEntity,
+        item_id: i32,
+    ) -> Result<Vec<model::StateChange>, CustomError> {
+        let id_column = match entity {
+            model::WorkflowEntity::Theme => "theme_id",
+            model::WorkflowEntity::Initiative => "initiative_id",
+            model::WorkflowEntity::Project => "project_id",
+            model::WorkflowEntity::Task => "task_id",
+        };
+        let sql = format!(
+            r#"SELECT change_id, from_state_id, from_state, to_state_id, to_state, changed_by, changed_at
+            FROM state_changes
+            WHERE {id_column} = $1
+            ORDER BY changed_at DESC, change_id DESC"#
+        );
+        Ok(sqlx::query_as(&sql)
+            .bind(item_id)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    async fn themes(&self, query: &model::ListQuery) -> Result<Page<model::Theme>, CustomError> {
+        self.list(&THEMES, SELECT_THEMES, query).await
+    }
+
+    async fn theme(&self, theme_id: i32) -> Result<model::Theme, CustomError> {
+        self.fetch(
+            &format!("{SELECT_THEMES} WHERE theme_id = $1"),
+            theme_id,
+            CustomError::ThemeNotFound,
+        )
+        .await
+    }
+
+    async fn add_theme(&self, create: model::CreateTheme) -> Result<model::Theme, CustomError> {
+        let theme_id: i32 = sqlx::query_scalar(INSERT_THEME)
+            .bind(create.title)
+            .bind(create.description)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        self.theme(theme_id).await
+    }
+
+    async fn save_theme(
+        &self,
+        theme_id: i32,
+        update: model::UpdateTheme,
+        changed_by: &str,
+    ) -> Result<model::Theme, CustomError> {
+        sqlx::query(UPDATE_THEME)
+            .bind(update.title)
+            .bind(update.state_id)
+            .bind(update.blocked_reason)
+            .bind(update.description)
+            .bind(theme_id)
+            .bind(changed_by)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::ThemeNotFound))?
+            .ok_or(CustomError::ThemeNotFound)?;
+        self.theme(theme_id).await
+    }
+
+    async fn remove_theme(&self, theme_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM themes WHERE theme_id = $1",
+            theme_id,
+            CustomError::ThemeNotFound,
+            Some(THEME_IN_USE),
+        )
+        .await
+    }
+
+    async fn objectives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Objective>, CustomError> {
+        self.list(&OBJECTIVES, "SELECT * FROM objectives", query)
+            .await
+    }
+
+    async fn objective(&self, objective_id: i32) -> Result<model::Objective, CustomError> {
+        self.fetch(
+            "SELECT * FROM objectives WHERE objective_id = $1",
+            objective_id,
+            CustomError::ObjectiveNotFound,
+        )
+        .await
+    }
+
+    async fn add_objective(
+        &self,
+        create: model::CreateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        sqlx::query_as(
+            r#"INSERT INTO objectives (title, theme_id, description, cycle_id)
+            VALUES ($1, $2, $3, $4) RETURNING *;"#,
+        )
+        .bind(create.title)
+        .bind(create.theme_id)
+        .bind(create.description)
+        .bind(create.cycle_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))
+    }
+
+    async fn save_objective(
+        &self,
+        objective_id: i32,
+        update: model::UpdateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        sqlx::query_as(
+            r#"UPDATE objectives
+            SET title=$1, description=COALESCE($2, description), cycle_id=COALESCE($3, cycle_id),
+                updated_at=NOW()
+            WHERE objective_id=$4 RETURNING *;"#,
+        )
+        .bind(update.title)
+        .bind(update.description)
+        .bind(update.cycle_id)
+        .bind(objective_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::ObjectiveNotFound))
+    }
+
+    async fn remove_objective(&self, objective_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM objectives WHERE objective_id = $1",
+            objective_id,
+            CustomError::ObjectiveNotFound,
+            Some(OBJECTIVE_IN_USE),
+        )
+        .await
+    }
+
+    async fn keyresults(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::KeyResult>, CustomError> {
+        self.list(&KEYRESULTS, SELECT_KEYRESULTS, query).await
+    }
+
+    async fn keyresult(&self, keyresult_id: i32) -> Result<model::KeyResult, CustomError> {
+        self.fetch(
+            &format!("{SELECT_KEYRESULTS} WHERE keyresult_id = $1"),
+            keyresult_id,
+            CustomError::KeyResultNotFound,
+        )
+        .await
+    }
+
+    async fn add_keyresult(
+        &self,
+        create: model::CreateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        let keyresult_id: i32 = sqlx::query_scalar(
+            r#"INSERT INTO keyresults (title, objective_id, baseline, target, unit, direction, weight, description)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8) RETURNING keyresult_id;"#,
+        )
+        .bind(create.title)
+        .bind(create.objective_id)
+        .bind(create.baseline)
+        .bind(create.target)
+        .bind(create.unit)
+        .bind(create.direction)
+        .bind(create.weight)
+        .bind(create.description)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        self.keyresult(keyresult_id).await
+    }
+
+    async fn save_keyresult(
+        &self,
+        keyresult_id: i32,
+        update: model::UpdateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        let result = sqlx::query(
+            r#"UPDATE keyresults
+            SET title=$1, baseline=$2, target=$3, unit=$4, direction=$5, weight=$6,
+                description=COALESCE($7, description), updated_at=NOW()
+            WHERE keyresult_id=$8;"#,
+        )
+        .bind(update.title)
+        .bind(update.baseline)
+        .bind(update.target)
+        .bind(update.unit)
+        .bind(update.direction)
+        .bind(update.weight)
+        .bind(update.description)
+        .bind(keyresult_id)
+        .execute(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::KeyResultNotFound))?;
+        if result.rows_affected() == 0 {
+            return Err(CustomError::KeyResultNotFound);
+        }
+        self.keyresult(keyresult_id).await
+    }
+
+    async fn remove_keyresult(&self, keyresult_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM keyresults WHERE keyresult_id = $1",
+            keyresult_id,
+            CustomError::KeyResultNotFound,
+            Some(KEYRESULT_IN_USE),
+        )
+        .await
+    }
+
+    async fn initiatives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Initiative>, CustomError> {
+        self.list(&INITIATIVES, SELECT_INITIATIVES, query).await
+    }
+
+    async fn initiative(&self, initiative_id: i32) -> Result<model::Initiative, CustomError> {
+        self.fetch(
+            &format!("{SELECT_INITIATIVES} WHERE initiative_id = $1"),
+            initiative_id,
+            CustomError::InitiativeNotFound,
+        )
+        .await
+    }
+
+    async fn add_initiative(
+        &self,
+        create: model::CreateInitiative,
+    ) -> Result<model::Initiative, CustomError> {
+        let initiative_id: i32 = sqlx::query_scalar(INSERT_INITIATIVE)
+            .bind(create.title)
+            .bind(create.objective_id)
+            .bind(create.description)
+            .bind(create.start_date)
+            .bind(create.due_date)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        self.initiative(initiative_id).await
+    }
+
+    async fn save_initiative(
+        &self,
+        initiative_id: i32,
+        update: model::UpdateInitiative,
+        changed_by: &str,
+    ) -> Result<model::Initiative, CustomError> {
+        sqlx::query(UPDATE_INITIATIVE)
+            .bind(update.title)
+            .bind(update.state_id)
+            .bind(update.blocked_reason)
+            .bind(update.description)
+            .bind(update.start_date.flatten())
+            .bind(update.due_date.flatten())
+            .bind(initiative_id)
+            .bind(changed_by)
+            .bind(update.start_date.is_some())
+            .bind(update.due_date.is_some())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::InitiativeNotFound))?
+            .ok_or(CustomError::InitiativeNotFound)?;
+        self.initiative(initiative_id).await
+    }
+
+    async fn remove_initiative(&self, initiative_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM initiatives WHERE initiative_id = $1",
+            initiative_id,
+            CustomError::InitiativeNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn projects(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Project>, CustomError> {
+        self.list(&PROJECTS, SELECT_PROJECTS, query).await
+    }
+
+    async fn project(&self, project_id: i32) -> Result<model::Project, CustomError> {
+        self.fetch(
+            &format!("{SELECT_PROJECTS} WHERE project_id = $1"),
+            project_id,
+            CustomError::ProjectNotFound,
+        )
+        .await
+    }
+
+    async fn add_project(
+        &self,
+        create: model::CreateProject,
+    ) -> Result<model::Project, CustomError> {
+        let project_id: i32 = sqlx::query_scalar(INSERT_PROJECT)
+            .bind(create.title)
+            .bind(create.objective_id)
+            .bind(create.description)
+            .bind(create.start_date)
+            .bind(create.due_date)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        self.project(project_id).await
+    }
+
+    async fn save_project(
+        &self,
+        project_id: i32,
+        update: model::UpdateProject,
+        changed_by: &str,
+    ) -> Result<model::Project, CustomError> {
+        sqlx::query(UPDATE_PROJECT)
+            .bind(update.title)
+            .bind(update.state_id)
+            .bind(update.blocked_reason)
+            .bind(update.description)
+            .bind(update.start_date.flatten())
+            .bind(update.due_date.flatten())
+            .bind(project_id)
+            .bind(changed_by)
+            .bind(update.start_date.is_some())
+            .bind(update.due_date.is_some())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::ProjectNotFound))?
+            .ok_or(CustomError::ProjectNotFound)?;
+        self.project(project_id).await
+    }
+
+    async fn remove_project(&self, project_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM projects WHERE project_id = $1",
+            project_id,
+            CustomError::ProjectNotFound,
+            Some(PROJECT_IN_USE),
+        )
+        .await
+    }
+
+    async fn tasks(&self, query: &model::ListQuery) -> Result<Page<model::Task>, CustomError> {
+        self.list(&TASKS, SELECT_TASKS, query).await
+    }
+
+    async fn task(&self, task_id: i32) -> Result<model::Task, CustomError> {
+        self.fetch(
+            &format!("{SELECT_TASKS} WHERE task_id = $1"),
+            task_id,
+            CustomError::TaskNotFound,
+        )
+        .await
+    }
+
+    async fn add_task(&self, create: model::CreateTask) -> Result<model::Task, CustomError> {
+        let task_id: i32 = sqlx::query_scalar(INSERT_TASK)
+            .bind(create.title)
+            .bind(create.project_id)
+            .bind(create.description)
+            .bind(create.start_date)
+            .bind(create.due_date)
+            .bind(create.assignee)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        self.task(task_id).await
+    }
+
+    async fn save_task(
+        &self,
+        task_id: i32,
+        update: model::UpdateTask,
+        changed_by: &str,
+    ) -> Result<model::Task, CustomError> {
+        sqlx::query(UPDATE_TASK)
+            .bind(update.title)
+            .bind(update.state_id)
+            .bind(update.blocked_reason)
+            .bind(update.description)
+            .bind(update.start_date.flatten())
+            .bind(update.due_date.flatten())
+            .bind(update.assignee)
+            .bind(task_id)
+            .bind(changed_by)
+            .bind(update.start_date.is_some())
+            .bind(update.due_date.is_some())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::TaskNotFound))?
+            .ok_or(CustomError::TaskNotFound)?;
+        self.task(task_id).await
+    }
+
+    async fn remove_task(&self, task_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM tasks WHERE task_id = $1",
+            task_id,
+            CustomError::TaskNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn measurements(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Measurement>, CustomError> {
+        self.list(&MEASUREMENTS, "SELECT * FROM measurements", query)
+            .await
+    }
+
+    async fn measurement(&self, measurement_id: i32) -> Result<model::Measurement, CustomError> {
+        self.fetch(
+            "SELECT * FROM measurements WHERE measurement_id = $1",
+            measurement_id,
+            CustomError::MeasurementNotFound,
+        )
+        .await
+    }
+
+    async fn add_measurement(
+        &self,
+        create: model::CreateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        sqlx::query_as(
+            r#"INSERT INTO measurements (title, value, unit, observed_at, keyresult_id, notes, source)
+            VALUES ($1, $2, $3, COALESCE($4, NOW()), $5, $6, $7) RETURNING *;"#,
+        )
+        .bind(create.title)
+        .bind(create.value)
+        .bind(create.unit)
+        .bind(create.observed_at)
+        .bind(create.keyresult_id)
+        .bind(create.notes)
+        .bind(create.source)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))
+    }
+
+    async fn save_measurement(
+        &self,
+        measurement_id: i32,
+        update: model::UpdateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        sqlx::query_as(UPDATE_MEASUREMENT)
+            .bind(update.title)
+            .bind(update.value)
+            .bind(update.unit)
+            .bind(update.observed_at)
+            .bind(update.notes)
+            .bind(update.source)
+            .bind(measurement_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::MeasurementNotFound))
+    }
+
+    async fn remove_measurement(&self, measurement_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM measurements WHERE measurement_id = $1",
+            measurement_id,
+            CustomError::MeasurementNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn measurement_revisions(
+        &self,
+        measurement_id: i32,
+    ) -> Result<Vec<model::MeasurementRevision>, CustomError> {
+        Ok(sqlx::query_as(
+            r#"SELECT * FROM measurement_revisions
+            WHERE measurement_id = $1
+            ORDER BY revised_at DESC;"#,
+        )
+        .bind(measurement_id)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn overdue(
+        &self,
+        today: chrono::NaiveDate,
+    ) -> Result<Vec<model::OverdueItem>, CustomError> {
+        Ok(sqlx::query_as(SELECT_OVERDUE)
+            .bind(today)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+}
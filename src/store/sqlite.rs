@@ -0,0 +1,1242 @@
+use std::{str::FromStr, time::Duration};
+
+use axum::async_trait;
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::{
+    migrate::Migration,
+    query::Query,
+    sqlite::{
+        SqliteArguments, SqliteConnectOptions, SqliteConnection, SqlitePoolOptions, SqliteRow,
+    },
+    Connection, Executor, FromRow, QueryBuilder, Sqlite, SqlitePool,
+};
+
+use super::{
+    migrate, CarriedObjective, Listing, Page, Store, CYCLES, INITIATIVES, KEYRESULTS,
+    KEYRESULT_IN_USE, MEASUREMENTS, OBJECTIVES, OBJECTIVE_IN_USE, PROJECTS, PROJECT_IN_USE, TASKS,
+    THEMES, THEME_IN_USE, WORKFLOW_STATE_IN_USE,
+};
+use crate::{errors::CustomError, model};
+
+// Key results are selected along with their latest measurement, so that their
+// progress can be shown wherever they are rendered.
+const SELECT_KEYRESULTS: &str = r#"
+    SELECT keyresults.*, (
+        SELECT measurements.value
+        FROM measurements
+        WHERE measurements.keyresult_id = keyresults.keyresult_id
+        ORDER BY measurements.observed_at DESC
+        LIMIT 1
+    ) AS latest_value
+    FROM keyresults"#;
+
+// Themes, initiatives, projects and tasks are selected along with the workflow
+// state they are in, under the names model::CurrentState expects.
+const SELECT_THEMES: &str = r#"
+    SELECT themes.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM themes
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = themes.theme_state_id"#;
+
+const SELECT_INITIATIVES: &str = r#"
+    SELECT initiatives.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM initiatives
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = initiatives.initiative_state_id"#;
+
+const SELECT_PROJECTS: &str = r#"
+    SELECT projects.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM projects
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = projects.project_state_id"#;
+
+const SELECT_TASKS: &str = r#"
+    SELECT tasks.*, states.state_title, states.state_category, states.state_requires_reason
+    FROM tasks
+    JOIN (
+        SELECT state_id, title AS state_title, category AS state_category,
+            requires_reason AS state_requires_reason
+        FROM workflow_states
+    ) AS states ON states.state_id = tasks.task_state_id"#;
+
+// Workflow states are selected along with the states they lead to, as a comma
+// separated list since SQLite has no arrays.
+const SELECT_WORKFLOW_STATES: &str = r#"
+    SELECT workflow_states.*, (
+        SELECT GROUP_CONCAT(to_state_id) FROM workflow_transitions
+        WHERE from_state_id = workflow_states.state_id
+    ) AS next_state_ids
+    FROM workflow_states"#;
+
+// Inserts themes, initiatives, projects and tasks in the first state of their
+// workflow.
+const INSERT_THEME: &str = r#"
+    INSERT INTO themes (title, description, theme_state_id)
+    VALUES ($1, $2, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Theme'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))"#;
+
+const INSERT_INITIATIVE: &str = r#"
+    INSERT INTO initiatives (title, objective_id, description, start_date, due_date, initiative_state_id)
+    VALUES ($1, $2, $3, $4, $5, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Initiative'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))"#;
+
+const INSERT_PROJECT: &str = r#"
+    INSERT INTO projects (title, objective_id, description, start_date, due_date, project_state_id)
+    VALUES ($1, $2, $3, $4, $5, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Project'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))"#;
+
+const INSERT_TASK: &str = r#"
+    INSERT INTO tasks (title, project_id, description, start_date, due_date, assignee, task_state_id)
+    VALUES ($1, $2, $3, $4, $5, $6, (
+        SELECT state_id FROM workflow_states
+        WHERE entity = 'Task'
+        ORDER BY position, state_id
+        LIMIT 1
+    ))"#;
+
+// Open items past their due date, along with their parent. Binds today's date.
+const SELECT_OVERDUE: &str = r#"
+    SELECT 'task' AS kind, tasks.task_id AS id, tasks.title, states.title AS state,
+        tasks.due_date, 'project' AS parent_kind, projects.project_id AS parent_id,
+        projects.title AS parent_title
+    FROM tasks
+    JOIN projects ON tasks.project_id = projects.project_id
+    JOIN workflow_states AS states ON tasks.task_state_id = states.state_id
+    WHERE tasks.due_date < $1 AND states.category <> 'Done'
+    UNION ALL
+    SELECT 'project', projects.project_id, projects.title, states.title,
+        projects.due_date, 'objective', objectives.objective_id, objectives.title
+    FROM projects
+    JOIN objectives ON projects.objective_id = objectives.objective_id
+    JOIN workflow_states AS states ON projects.project_state_id = states.state_id
+    WHERE projects.due_date < $1 AND states.category <> 'Done'
+    UNION ALL
+    SELECT 'initiative', initiatives.initiative_id, initiatives.title, states.title,
+        initiatives.due_date, 'objective', objectives.objective_id, objectives.title
+    FROM initiatives
+    JOIN objectives ON initiatives.objective_id = objectives.objective_id
+    JOIN workflow_states AS states ON initiatives.initiative_state_id = states.state_id
+    WHERE initiatives.due_date < $1 AND states.category <> 'Done'
+    ORDER BY due_date, kind, id"#;
+
+// Timestamps are stored as text in the format the schema's defaults use, so
+// that comparing them compares the times.
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// A workflow state as stored, with the states it leads to still joined up.
+#[derive(FromRow)]
+struct WorkflowStateRow {
+    state_id: i32,
+    entity: model::WorkflowEntity,
+    title: String,
+    category: model::StateCategory,
+    position: i32,
+    requires_reason: bool,
+    next_state_ids: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<WorkflowStateRow> for model::WorkflowState {
+    fn from(row: WorkflowStateRow) -> Self {
+        let mut next_state_ids: Vec<i32> = row
+            .next_state_ids
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        next_state_ids.sort_unstable();
+        model::WorkflowState {
+            state_id: row.state_id,
+            entity: row.entity,
+            title: row.title,
+            category: row.category,
+            position: row.position,
+            requires_reason: row.requires_reason,
+            next_state_ids,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+// The tables and columns of the database, leaving out SQLite's own and the
+// record of applied migrations
+const SELECT_COLUMNS: &str = r#"
+    SELECT tables.name, columns.name
+    FROM sqlite_master AS tables
+    JOIN pragma_table_info(tables.name) AS columns
+    WHERE tables.type = 'table' AND tables.name NOT LIKE 'sqlite_%'
+        AND tables.name <> '_sqlx_migrations'"#;
+
+/// Stores everything in a single SQLite file, set up by the migrations in
+/// migrations/sqlite.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(pool: SqlitePool) -> SqliteStore {
+        SqliteStore { pool }
+    }
+
+    /// Opens the database at `url`, creating the file when it doesn't exist
+    /// yet. Call `migrate` before using it.
+    pub async fn connect(
+        url: &str,
+        max_connections: u32,
+        acquire_timeout: Duration,
+    ) -> Result<SqliteStore, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(url)?
+            .create_if_missing(true)
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .acquire_timeout(acquire_timeout)
+            .connect_with(options)
+            .await?;
+        Ok(SqliteStore::new(pool))
+    }
+
+    /// Brings the schema up to date.
+    pub async fn migrate(&self) -> Result<(), String> {
+        let mut conn = self.pool.acquire().await.map_err(|err| err.to_string())?;
+        let (set_up,): (bool,) = sqlx::query_as(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'workflow_states'",
+        )
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|err| err.to_string())?;
+        if let Some(initial) = migrate::initial(&migrate::SQLITE) {
+            // Files set up before migrations were tracked have the initial schema
+            if set_up && migrate::untracked(&mut *conn).await? {
+                let missing = migrate::missing_columns(
+                    &initial_columns(initial).await?,
+                    &columns(&mut conn).await?,
+                );
+                if !missing.is_empty() {
+                    return Err(format!(
+                        "the database has tables, but not the initial migration's {}, so it \
+                         was not set up by this app",
+                        missing.join(", ")
+                    ));
+                }
+                tracing::info!(
+                    "recording the existing schema as migration {}",
+                    initial.version
+                );
+                sqlx::query(migrate::ADOPT)
+                    .bind(initial.version)
+                    .bind(&*initial.description)
+                    .bind(&*initial.checksum)
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(|err| err.to_string())?;
+            }
+        }
+        migrate::run(&migrate::SQLITE, &mut *conn).await
+    }
+
+    pub async fn migration_status(&self) -> Result<Vec<migrate::MigrationStatus>, String> {
+        let mut conn = self.pool.acquire().await.map_err(|err| err.to_string())?;
+        migrate::status(&migrate::SQLITE, &mut *conn).await
+    }
+
+    async fn fetch<T>(&self, sql: &str, id: i32, not_found: CustomError) -> Result<T, CustomError>
+    where
+        T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        sqlx::query_as(sql)
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, not_found))
+    }
+
+    // Runs an insert, returning the new row's id. SQLite only finishes a
+    // statement once all the rows it returns are read, so new and updated rows
+    // are read back afterwards rather than with RETURNING.
+    async fn insert<'q>(
+        &self,
+        query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    ) -> Result<i32, CustomError> {
+        let result = query
+            .execute(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        Ok(result.last_insert_rowid() as i32)
+    }
+
+    async fn update<'q>(
+        &self,
+        query: Query<'q, Sqlite, SqliteArguments<'q>>,
+        not_found: CustomError,
+    ) -> Result<(), CustomError> {
+        match query.execute(&self.pool).await {
+            Ok(result) if result.rows_affected() > 0 => Ok(()),
+            Ok(_) => Err(not_found),
+            Err(err) => Err(CustomError::from_sqlx(err, CustomError::BadRequest)),
+        }
+    }
+
+    // Deletes a row, refusing with `in_use` while other rows refer to it
+    async fn delete(
+        &self,
+        sql: &str,
+        id: i32,
+        not_found: CustomError,
+        in_use: Option<&'static str>,
+    ) -> Result<(), CustomError> {
+        match sqlx::query(sql).bind(id).execute(&self.pool).await {
+            Ok(result) if result.rows_affected() > 0 => Ok(()),
+            Ok(_) => Err(not_found),
+            Err(err) => Err(CustomError::from_sqlx_delete(err, not_found, in_use)),
+        }
+    }
+
+    // Shared implementation of the listings: filters, sorts and pages the rows,
+    // and counts them unpaged.
+    async fn list<T>(
+        &self,
+        listing: &Listing,
+        select: &str,
+        query: &model::ListQuery,
+    ) -> Result<Page<T>, CustomError>
+    where
+        T: for<'r> FromRow<'r, SqliteRow> + Send + Unpin,
+    {
+        let (sort_column, descending) = listing.order(query)?;
+
+        let mut count = QueryBuilder::new(format!("SELECT COUNT(*) FROM {}", listing.table));
+        push_filters(&mut count, listing, query);
+        let (total,): (i64,) = count
+            .build_query_as()
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::InternalServerError))?;
+
+        let mut select = QueryBuilder::new(select);
+        push_filters(&mut select, listing, query);
+        select.push(format!(
+            " ORDER BY {sort_column} {direction}, {id_column}",
+            direction = if descending { "DESC" } else { "ASC" },
+            id_column = listing.id_column,
+        ));
+        // A negative limit is no limit at all
+        select.push(" LIMIT ").push_bind(query.limit.unwrap_or(-1));
+        select
+            .push(" OFFSET ")
+            .push_bind(query.offset.unwrap_or(0).max(0));
+        let items = select
+            .build_query_as::<T>()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::InternalServerError))?;
+        Ok(Page { items, total })
+    }
+
+    // Runs the update of a theme, initiative, project or task, recording a
+    // state change along with who made it when the item's state moves.
+    async fn save_item<'q>(
+        &self,
+        listing: &Listing,
+        item_id: i32,
+        state_id: i32,
+        update: Query<'q, Sqlite, SqliteArguments<'q>>,
+        changed_by: &str,
+        not_found: CustomError,
+    ) -> Result<(), CustomError> {
+        let Some(state_column) = listing.state_column else {
+            return Err(CustomError::InternalServerError);
+        };
+        let mut transaction = self.pool.begin().await?;
+        let previous_state_id: i32 = sqlx::query_scalar(&format!(
+            "SELECT {state_column} FROM {table} WHERE {id_column} = $1",
+            table = listing.table,
+            id_column = listing.id_column,
+        ))
+        .bind(item_id)
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, not_found))?;
+        update
+            .execute(&mut *transaction)
+            .await
+            .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        if previous_state_id != state_id {
+            sqlx::query(&format!(
+                r#"INSERT INTO state_changes ({id_column}, from_state_id, from_state, to_state_id, to_state, changed_by)
+                SELECT $1, from_states.state_id, from_states.title, to_states.state_id, to_states.title, $4
+                FROM workflow_states AS from_states, workflow_states AS to_states
+                WHERE from_states.state_id = $2 AND to_states.state_id = $3"#,
+                id_column = listing.id_column,
+            ))
+            .bind(item_id)
+            .bind(previous_state_id)
+            .bind(state_id)
+            .bind(changed_by)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+async fn columns(conn: &mut SqliteConnection) -> Result<Vec<(String, String)>, String> {
+    sqlx::query_as(SELECT_COLUMNS)
+        .fetch_all(conn)
+        .await
+        .map_err(|err| format!("could not read the schema: {err}"))
+}
+
+// The columns the initial migration creates, read from a scratch in-memory
+// database that it is applied to
+async fn initial_columns(initial: &Migration) -> Result<Vec<(String, String)>, String> {
+    let error =
+        |err: sqlx::Error| format!("could not apply the initial migration to compare: {err}");
+    let mut scratch = SqliteConnection::connect("sqlite::memory:")
+        .await
+        .map_err(error)?;
+    scratch.execute(&*initial.sql).await.map_err(error)?;
+    columns(&mut scratch).await
+}
+
+fn push_filters(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    listing: &Listing,
+    query: &model::ListQuery,
+) {
+    builder.push(" WHERE TRUE");
+    if let (Some(column), Some(parent_ids)) = (listing.parent_column, listing.parent_ids(query)) {
+        builder.push(format!(" AND {column} IN ("));
+        let mut ids = builder.separated(", ");
+        for parent_id in parent_ids {
+            ids.push_bind(parent_id);
+        }
+        builder.push(")");
+    }
+    if let (Some(column), Some(state_id)) = (listing.state_column, query.state_id) {
+        builder
+            .push(format!(" AND {column} = "))
+            .push_bind(state_id);
+    }
+    if let (Some(column), Some(category)) = (listing.state_column, query.category) {
+        builder
+            .push(format!(
+                " AND {column} IN (SELECT state_id FROM workflow_states WHERE category = "
+            ))
+            .push_bind(category)
+            .push(")");
+    }
+    if let (Some(column), Some(cycle_id)) = (listing.cycle_column, query.cycle_id) {
+        builder
+            .push(format!(" AND {column} = "))
+            .push_bind(cycle_id);
+    }
+    for (condition, bound) in [
+        (" AND created_at >= ", query.created_after),
+        (" AND created_at <= ", query.created_before),
+        (" AND updated_at >= ", query.updated_after),
+        (" AND updated_at <= ", query.updated_before),
+    ] {
+        if let Some(bound) = bound {
+            builder.push(condition).push_bind(timestamp(bound));
+        }
+    }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+    async fn cycles(&self, query: &model::ListQuery) -> Result<Page<model::Cycle>, CustomError> {
+        self.list(&CYCLES, "SELECT * FROM cycles", query).await
+    }
+
+    async fn cycle(&self, cycle_id: i32) -> Result<model::Cycle, CustomError> {
+        self.fetch(
+            "SELECT * FROM cycles WHERE cycle_id = $1",
+            cycle_id,
+            CustomError::CycleNotFound,
+        )
+        .await
+    }
+
+    async fn add_cycle(&self, create: model::CreateCycle) -> Result<model::Cycle, CustomError> {
+        let query =
+            sqlx::query(r#"INSERT INTO cycles (title, start_date, end_date) VALUES ($1, $2, $3);"#)
+                .bind(create.title)
+                .bind(create.start_date)
+                .bind(create.end_date);
+        let cycle_id = self.insert(query).await?;
+        self.cycle(cycle_id).await
+    }
+
+    async fn save_cycle(
+        &self,
+        cycle_id: i32,
+        update: model::UpdateCycle,
+    ) -> Result<model::Cycle, CustomError> {
+        let query = sqlx::query(
+            r#"UPDATE cycles
+            SET title=$1, start_date=$2, end_date=$3,
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE cycle_id=$4;"#,
+        )
+        .bind(update.title)
+        .bind(update.start_date)
+        .bind(update.end_date)
+        .bind(cycle_id);
+        self.update(query, CustomError::CycleNotFound).await?;
+        self.cycle(cycle_id).await
+    }
+
+    async fn remove_cycle(&self, cycle_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM cycles WHERE cycle_id = $1",
+            cycle_id,
+            CustomError::CycleNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn carry_over(
+        &self,
+        cycle_id: i32,
+        objectives: Vec<CarriedObjective>,
+    ) -> Result<(), CustomError> {
+        let mut transaction = self.pool.begin().await?;
+        for CarriedObjective {
+            objective,
+            keyresults,
+        } in objectives
+        {
+            let copy_id = sqlx::query(
+                r#"INSERT INTO objectives (title, description, theme_id, cycle_id, carried_from)
+                VALUES ($1, $2, $3, $4, $5);"#,
+            )
+            .bind(&objective.title)
+            .bind(&objective.description)
+            .bind(objective.theme_id)
+            .bind(cycle_id)
+            .bind(objective.objective_id)
+            .execute(&mut *transaction)
+            .await?
+            .last_insert_rowid() as i32;
+            for keyresult in keyresults {
+                sqlx::query(
+                    r#"INSERT INTO keyresults (title, description, objective_id, baseline, target, unit, direction, weight)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8);"#,
+                )
+                .bind(&keyresult.title)
+                .bind(&keyresult.description)
+                .bind(copy_id)
+                .bind(keyresult.baseline)
+                .bind(keyresult.target)
+                .bind(&keyresult.unit)
+                .bind(keyresult.direction)
+                .bind(keyresult.weight)
+                .execute(&mut *transaction)
+                .await?;
+            }
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn workflow_states(
+        &self,
+        entity: Option<model::WorkflowEntity>,
+    ) -> Result<Vec<model::WorkflowState>, CustomError> {
+        // Workflows come in the order of the Postgres enum rather than by name
+        let rows: Vec<WorkflowStateRow> = sqlx::query_as(&format!(
+            "{SELECT_WORKFLOW_STATES} WHERE $1 IS NULL OR entity = $1 \
+            ORDER BY CASE entity WHEN 'Theme' THEN 0 WHEN 'Initiative' THEN 1 \
+                WHEN 'Project' THEN 2 ELSE 3 END, position, state_id"
+        ))
+        .bind(entity)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::InternalServerError))?;
+        Ok(rows.into_iter().map(model::WorkflowState::from).collect())
+    }
+
+    async fn workflow_state(&self, state_id: i32) -> Result<model::WorkflowState, CustomError> {
+        let row: WorkflowStateRow = self
+            .fetch(
+                &format!("{SELECT_WORKFLOW_STATES} WHERE state_id = $1"),
+                state_id,
+                CustomError::WorkflowStateNotFound,
+            )
+            .await?;
+        Ok(row.into())
+    }
+
+    async fn add_workflow_state(
+        &self,
+        create: model::CreateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        let mut transaction = self.pool.begin().await?;
+        let state_id = sqlx::query(
+            r#"INSERT INTO workflow_states (entity, title, category, requires_reason, position)
+            VALUES ($1, $2, $3, $4, (
+                SELECT COALESCE(MAX(position) + 1, 0) FROM workflow_states WHERE entity = $1
+            ));"#,
+        )
+        .bind(create.entity)
+        .bind(create.title)
+        .bind(create.category)
+        .bind(create.requires_reason)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?
+        .last_insert_rowid() as i32;
+        sqlx::query(
+            r#"INSERT INTO workflow_transitions (from_state_id, to_state_id)
+            SELECT $1, state_id FROM workflow_states WHERE entity = $2 AND state_id <> $1
+            UNION ALL
+            SELECT state_id, $1 FROM workflow_states WHERE entity = $2 AND state_id <> $1;"#,
+        )
+        .bind(state_id)
+        .bind(create.entity)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+        self.workflow_state(state_id).await
+    }
+
+    async fn save_workflow_state(
+        &self,
+        state_id: i32,
+        update: model::UpdateWorkflowState,
+    ) -> Result<model::WorkflowState, CustomError> {
+        let mut transaction = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"UPDATE workflow_states
+            SET title=$1, category=$2, position=$3, requires_reason=$4,
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE state_id=$5;"#,
+        )
+        .bind(update.title)
+        .bind(update.category)
+        .bind(update.position)
+        .bind(update.requires_reason)
+        .bind(state_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::WorkflowStateNotFound))?;
+        if result.rows_affected() == 0 {
+            return Err(CustomError::WorkflowStateNotFound);
+        }
+        if let Some(next_state_ids) = update.next_state_ids {
+            sqlx::query(r#"DELETE FROM workflow_transitions WHERE from_state_id = $1;"#)
+                .bind(state_id)
+                .execute(&mut *transaction)
+                .await?;
+            if !next_state_ids.is_empty() {
+                QueryBuilder::new("INSERT INTO workflow_transitions (from_state_id, to_state_id) ")
+                    .push_values(next_state_ids, |mut values, next_state_id| {
+                        values.push_bind(state_id).push_bind(next_state_id);
+                    })
+                    .build()
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+        }
+        transaction.commit().await?;
+        self.workflow_state(state_id).await
+    }
+
+    async fn remove_workflow_state(&self, state_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM workflow_states WHERE state_id = $1",
+            state_id,
+            CustomError::WorkflowStateNotFound,
+            Some(WORKFLOW_STATE_IN_USE),
+        )
+        .await
+    }
+
+    async fn state_changes(
+        &self,
+        entity: model::WorkflowEntity,
+        item_id: i32,
+    ) -> Result<Vec<model::StateChange>, CustomError> {
+        let id_column = match entity {
+            model::WorkflowEntity::Theme => "theme_id",
+            model::WorkflowEntity::Initiative => "initiative_id",
+            model::WorkflowEntity::Project => "project_id",
+            model::WorkflowEntity::Task => "task_id",
+        };
+        let sql = format!(
+            r#"SELECT change_id, from_state_id, from_state, to_state_id, to_state, changed_by, changed_at
+            FROM state_changes
+            WHERE {id_column} = $1
+            ORDER BY changed_at DESC, change_id DESC"#
+        );
+        Ok(sqlx::query_as(&sql)
+            .bind(item_id)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+
+    async fn themes(&self, query: &model::ListQuery) -> Result<Page<model::Theme>, CustomError> {
+        self.list(&THEMES, SELECT_THEMES, query).await
+    }
+
+    async fn theme(&self, theme_id: i32) -> Result<model::Theme, CustomError> {
+        self.fetch(
+            &format!("{SELECT_THEMES} WHERE theme_id = $1"),
+            theme_id,
+            CustomError::ThemeNotFound,
+        )
+        .await
+    }
+
+    async fn add_theme(&self, create: model::CreateTheme) -> Result<model::Theme, CustomError> {
+        let query = sqlx::query(INSERT_THEME)
+            .bind(create.title)
+            .bind(create.description);
+        let theme_id = self.insert(query).await?;
+        self.theme(theme_id).await
+    }
+
+    async fn save_theme(
+        &self,
+        theme_id: i32,
+        update: model::UpdateTheme,
+        changed_by: &str,
+    ) -> Result<model::Theme, CustomError> {
+        let state_id = update.state_id;
+        let query = sqlx::query(
+            r#"UPDATE themes
+            SET title=$1, theme_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE theme_id=$5;"#,
+        )
+        .bind(update.title)
+        .bind(update.state_id)
+        .bind(update.blocked_reason)
+        .bind(update.description)
+        .bind(theme_id);
+        self.save_item(
+            &THEMES,
+            theme_id,
+            state_id,
+            query,
+            changed_by,
+            CustomError::ThemeNotFound,
+        )
+        .await?;
+        self.theme(theme_id).await
+    }
+
+    async fn remove_theme(&self, theme_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM themes WHERE theme_id = $1",
+            theme_id,
+            CustomError::ThemeNotFound,
+            Some(THEME_IN_USE),
+        )
+        .await
+    }
+
+    async fn objectives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Objective>, CustomError> {
+        self.list(&OBJECTIVES, "SELECT * FROM objectives", query)
+            .await
+    }
+
+    async fn objective(&self, objective_id: i32) -> Result<model::Objective, CustomError> {
+        self.fetch(
+            "SELECT * FROM objectives WHERE objective_id = $1",
+            objective_id,
+            CustomError::ObjectiveNotFound,
+        )
+        .await
+    }
+
+    async fn add_objective(
+        &self,
+        create: model::CreateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        let query = sqlx::query(
+            r#"INSERT INTO objectives (title, theme_id, description, cycle_id)
+            VALUES ($1, $2, $3, $4);"#,
+        )
+        .bind(create.title)
+        .bind(create.theme_id)
+        .bind(create.description)
+        .bind(create.cycle_id);
+        let objective_id = self.insert(query).await?;
+        self.objective(objective_id).await
+    }
+
+    async fn save_objective(
+        &self,
+        objective_id: i32,
+        update: model::UpdateObjective,
+    ) -> Result<model::Objective, CustomError> {
+        let query = sqlx::query(
+            r#"UPDATE objectives
+            SET title=$1, description=COALESCE($2, description), cycle_id=COALESCE($3, cycle_id),
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE objective_id=$4;"#,
+        )
+        .bind(update.title)
+        .bind(update.description)
+        .bind(update.cycle_id)
+        .bind(objective_id);
+        self.update(query, CustomError::ObjectiveNotFound).await?;
+        self.objective(objective_id).await
+    }
+
+    async fn remove_objective(&self, objective_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM objectives WHERE objective_id = $1",
+            objective_id,
+            CustomError::ObjectiveNotFound,
+            Some(OBJECTIVE_IN_USE),
+        )
+        .await
+    }
+
+    async fn keyresults(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::KeyResult>, CustomError> {
+        self.list(&KEYRESULTS, SELECT_KEYRESULTS, query).await
+    }
+
+    async fn keyresult(&self, keyresult_id: i32) -> Result<model::KeyResult, CustomError> {
+        self.fetch(
+            &format!("{SELECT_KEYRESULTS} WHERE keyresult_id = $1"),
+            keyresult_id,
+            CustomError::KeyResultNotFound,
+        )
+        .await
+    }
+
+    async fn add_keyresult(
+        &self,
+        create: model::CreateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        let query = sqlx::query(
+            r#"INSERT INTO keyresults (title, objective_id, baseline, target, unit, direction, weight, description)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8);"#,
+        )
+        .bind(create.title)
+        .bind(create.objective_id)
+        .bind(create.baseline)
+        .bind(create.target)
+        .bind(create.unit)
+        .bind(create.direction)
+        .bind(create.weight)
+        .bind(create.description);
+        let keyresult_id = self.insert(query).await?;
+        self.keyresult(keyresult_id).await
+    }
+
+    async fn save_keyresult(
+        &self,
+        keyresult_id: i32,
+        update: model::UpdateKeyResult,
+    ) -> Result<model::KeyResult, CustomError> {
+        let query = sqlx::query(
+            r#"UPDATE keyresults
+            SET title=$1, baseline=$2, target=$3, unit=$4, direction=$5, weight=$6,
+                description=COALESCE($7, description),
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE keyresult_id=$8;"#,
+        )
+        .bind(update.title)
+        .bind(update.baseline)
+        .bind(update.target)
+        .bind(update.unit)
+        .bind(update.direction)
+        .bind(update.weight)
+        .bind(update.description)
+        .bind(keyresult_id);
+        self.update(query, CustomError::KeyResultNotFound).await?;
+        self.keyresult(keyresult_id).await
+    }
+
+    async fn remove_keyresult(&self, keyresult_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM keyresults WHERE keyresult_id = $1",
+            keyresult_id,
+            CustomError::KeyResultNotFound,
+            Some(KEYRESULT_IN_USE),
+        )
+        .await
+    }
+
+    async fn initiatives(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Initiative>, CustomError> {
+        self.list(&INITIATIVES, SELECT_INITIATIVES, query).await
+    }
+
+    async fn initiative(&self, initiative_id: i32) -> Result<model::Initiative, CustomError> {
+        self.fetch(
+            &format!("{SELECT_INITIATIVES} WHERE initiative_id = $1"),
+            initiative_id,
+            CustomError::InitiativeNotFound,
+        )
+        .await
+    }
+
+    async fn add_initiative(
+        &self,
+        create: model::CreateInitiative,
+    ) -> Result<model::Initiative, CustomError> {
+        let query = sqlx::query(INSERT_INITIATIVE)
+            .bind(create.title)
+            .bind(create.objective_id)
+            .bind(create.description)
+            .bind(create.start_date)
+            .bind(create.due_date);
+        let initiative_id = self.insert(query).await?;
+        self.initiative(initiative_id).await
+    }
+
+    async fn save_initiative(
+        &self,
+        initiative_id: i32,
+        update: model::UpdateInitiative,
+        changed_by: &str,
+    ) -> Result<model::Initiative, CustomError> {
+        let state_id = update.state_id;
+        let query = sqlx::query(
+            r#"UPDATE initiatives
+            SET title=$1, initiative_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+                start_date=CASE WHEN $8 THEN $5 ELSE start_date END,
+                due_date=CASE WHEN $9 THEN $6 ELSE due_date END,
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE initiative_id=$7;"#,
+        )
+        .bind(update.title)
+        .bind(update.state_id)
+        .bind(update.blocked_reason)
+        .bind(update.description)
+        .bind(update.start_date.flatten())
+        .bind(update.due_date.flatten())
+        .bind(initiative_id)
+        .bind(update.start_date.is_some())
+        .bind(update.due_date.is_some());
+        self.save_item(
+            &INITIATIVES,
+            initiative_id,
+            state_id,
+            query,
+            changed_by,
+            CustomError::InitiativeNotFound,
+        )
+        .await?;
+        self.initiative(initiative_id).await
+    }
+
+    async fn remove_initiative(&self, initiative_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM initiatives WHERE initiative_id = $1",
+            initiative_id,
+            CustomError::InitiativeNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn projects(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Project>, CustomError> {
+        self.list(&PROJECTS, SELECT_PROJECTS, query).await
+    }
+
+    async fn project(&self, project_id: i32) -> Result<model::Project, CustomError> {
+        self.fetch(
+            &format!("{SELECT_PROJECTS} WHERE project_id = $1"),
+            project_id,
+            CustomError::ProjectNotFound,
+        )
+        .await
+    }
+
+    async fn add_project(
+        &self,
+        create: model::CreateProject,
+    ) -> Result<model::Project, CustomError> {
+        let query = sqlx::query(INSERT_PROJECT)
+            .bind(create.title)
+            .bind(create.objective_id)
+            .bind(create.description)
+            .bind(create.start_date)
+            .bind(create.due_date);
+        let project_id = self.insert(query).await?;
+        self.project(project_id).await
+    }
+
+    async fn save_project(
+        &self,
+        project_id: i32,
+        update: model::UpdateProject,
+        changed_by: &str,
+    ) -> Result<model::Project, CustomError> {
+        let state_id = update.state_id;
+        let query = sqlx::query(
+            r#"UPDATE projects
+            SET title=$1, project_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+                start_date=CASE WHEN $8 THEN $5 ELSE start_date END,
+                due_date=CASE WHEN $9 THEN $6 ELSE due_date END,
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE project_id=$7;"#,
+        )
+        .bind(update.title)
+        .bind(update.state_id)
+        .bind(update.blocked_reason)
+        .bind(update.description)
+        .bind(update.start_date.flatten())
+        .bind(update.due_date.flatten())
+        .bind(project_id)
+        .bind(update.start_date.is_some())
+        .bind(update.due_date.is_some());
+        self.save_item(
+            &PROJECTS,
+            project_id,
+            state_id,
+            query,
+            changed_by,
+            CustomError::ProjectNotFound,
+        )
+        .await?;
+        self.project(project_id).await
+    }
+
+    async fn remove_project(&self, project_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM projects WHERE project_id = $1",
+            project_id,
+            CustomError::ProjectNotFound,
+            Some(PROJECT_IN_USE),
+        )
+        .await
+    }
+
+    async fn tasks(&self, query: &model::ListQuery) -> Result<Page<model::Task>, CustomError> {
+        self.list(&TASKS, SELECT_TASKS, query).await
+    }
+
+    async fn task(&self, task_id: i32) -> Result<model::Task, CustomError> {
+        self.fetch(
+            &format!("{SELECT_TASKS} WHERE task_id = $1"),
+            task_id,
+            CustomError::TaskNotFound,
+        )
+        .await
+    }
+
+    async fn add_task(&self, create: model::CreateTask) -> Result<model::Task, CustomError> {
+        let query = sqlx::query(INSERT_TASK)
+            .bind(create.title)
+            .bind(create.project_id)
+            .bind(create.description)
+            .bind(create.start_date)
+            .bind(create.due_date)
+            .bind(create.assignee);
+        let task_id = self.insert(query).await?;
+        self.task(task_id).await
+    }
+
+    async fn save_task(
+        &self,
+        task_id: i32,
+        update: model::UpdateTask,
+        changed_by: &str,
+    ) -> Result<model::Task, CustomError> {
+        let state_id = update.state_id;
+        let query = sqlx::query(
+            r#"UPDATE tasks
+            SET title=$1, task_state_id=$2, blocked_reason=COALESCE($3, blocked_reason), description=COALESCE($4, description),
+                start_date=CASE WHEN $9 THEN $5 ELSE start_date END,
+                due_date=CASE WHEN $10 THEN $6 ELSE due_date END, assignee=COALESCE($7, assignee),
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE task_id=$8;"#,
+        )
+        .bind(update.title)
+        .bind(update.state_id)
+        .bind(update.blocked_reason)
+        .bind(update.description)
+        .bind(update.start_date.flatten())
+        .bind(update.due_date.flatten())
+        .bind(update.assignee)
+        .bind(task_id)
+        .bind(update.start_date.is_some())
+        .bind(update.due_date.is_some());
+        self.save_item(
+            &TASKS,
+            task_id,
+            state_id,
+            query,
+            changed_by,
+            CustomError::TaskNotFound,
+        )
+        .await?;
+        self.task(task_id).await
+    }
+
+    async fn remove_task(&self, task_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM tasks WHERE task_id = $1",
+            task_id,
+            CustomError::TaskNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn measurements(
+        &self,
+        query: &model::ListQuery,
+    ) -> Result<Page<model::Measurement>, CustomError> {
+        self.list(&MEASUREMENTS, "SELECT * FROM measurements", query)
+            .await
+    }
+
+    async fn measurement(&self, measurement_id: i32) -> Result<model::Measurement, CustomError> {
+        self.fetch(
+            "SELECT * FROM measurements WHERE measurement_id = $1",
+            measurement_id,
+            CustomError::MeasurementNotFound,
+        )
+        .await
+    }
+
+    async fn add_measurement(
+        &self,
+        create: model::CreateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        let query = sqlx::query(
+            r#"INSERT INTO measurements (title, value, unit, observed_at, keyresult_id, notes, source)
+            VALUES ($1, $2, $3, COALESCE($4, strftime('%Y-%m-%dT%H:%M:%fZ', 'now')), $5, $6, $7);"#,
+        )
+        .bind(create.title)
+        .bind(create.value)
+        .bind(create.unit)
+        .bind(create.observed_at.map(timestamp))
+        .bind(create.keyresult_id)
+        .bind(create.notes)
+        .bind(create.source);
+        let measurement_id = self.insert(query).await?;
+        self.measurement(measurement_id).await
+    }
+
+    // Keeps the previous value, unit and observation time as a revision when
+    // any of them change.
+    async fn save_measurement(
+        &self,
+        measurement_id: i32,
+        update: model::UpdateMeasurement,
+    ) -> Result<model::Measurement, CustomError> {
+        let mut transaction = self.pool.begin().await?;
+        let previous: model::Measurement =
+            sqlx::query_as("SELECT * FROM measurements WHERE measurement_id = $1")
+                .bind(measurement_id)
+                .fetch_one(&mut *transaction)
+                .await
+                .map_err(|err| CustomError::from_sqlx(err, CustomError::MeasurementNotFound))?;
+        let observed_at = update.observed_at.unwrap_or(previous.observed_at);
+        let changed = (previous.value, &previous.unit, previous.observed_at)
+            != (update.value, &update.unit, observed_at);
+        sqlx::query(
+            r#"UPDATE measurements
+            SET title=$1, value=$2, unit=$3, observed_at=$4,
+                notes=COALESCE($5, notes), source=COALESCE($6, source),
+                updated_at=strftime('%Y-%m-%dT%H:%M:%fZ', 'now')
+            WHERE measurement_id=$7;"#,
+        )
+        .bind(update.title)
+        .bind(update.value)
+        .bind(update.unit)
+        .bind(timestamp(observed_at))
+        .bind(update.notes)
+        .bind(update.source)
+        .bind(measurement_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(|err| CustomError::from_sqlx(err, CustomError::BadRequest))?;
+        if changed {
+            sqlx::query(
+                r#"INSERT INTO measurement_revisions (measurement_id, value, unit, observed_at)
+                VALUES ($1, $2, $3, $4);"#,
+            )
+            .bind(measurement_id)
+            .bind(previous.value)
+            .bind(previous.unit)
+            .bind(timestamp(previous.observed_at))
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+        self.measurement(measurement_id).await
+    }
+
+    async fn remove_measurement(&self, measurement_id: i32) -> Result<(), CustomError> {
+        self.delete(
+            "DELETE FROM measurements WHERE measurement_id = $1",
+            measurement_id,
+            CustomError::MeasurementNotFound,
+            None,
+        )
+        .await
+    }
+
+    async fn measurement_revisions(
+        &self,
+        measurement_id: i32,
+    ) -> Result<Vec<model::MeasurementRevision>, CustomError> {
+        Ok(sqlx::query_as(
+            r#"SELECT * FROM measurement_revisions
+            WHERE measurement_id = $1
+            ORDER BY revised_at DESC;"#,
+        )
+        .bind(measurement_id)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    async fn overdue(
+        &self,
+        today: chrono::NaiveDate,
+    ) -> Result<Vec<model::OverdueItem>, CustomError> {
+        Ok(sqlx::query_as(SELECT_OVERDUE)
+            .bind(today)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+}
@@ -1,21 +1,144 @@
-use crate::model;
+use crate::{chart, model};
 use askama;
 use axum::{
     http::StatusCode,
     response::{Html, IntoResponse},
 };
-use model::Status;
+use model::{Deadline, Direction, StateCategory};
+
+// Custom filters available to every template
+mod filters {
+    use crate::markdown;
+
+    // Renders a Markdown description, use together with `safe`
+    pub fn markdown_html<T: std::fmt::Display>(source: T) -> askama::Result<String> {
+        Ok(markdown::to_html(&source.to_string()))
+    }
+}
+
+/// A state offered by an item's edit form, disabled when the item's workflow
+/// doesn't allow moving there from its current state.
+pub struct StateOption {
+    pub state: model::WorkflowState,
+    pub allowed: bool,
+}
+
+fn state_options(states: Vec<model::WorkflowState>, current_state_id: i32) -> Vec<StateOption> {
+    let current = states
+        .iter()
+        .find(|state| state.state_id == current_state_id)
+        .cloned();
+    states
+        .into_iter()
+        .map(|state| StateOption {
+            allowed: current
+                .as_ref()
+                .is_some_and(|current| current.can_change_to(state.state_id)),
+            state,
+        })
+        .collect()
+}
 
 // ROOT TEMPLATES
 #[derive(askama::Template)]
 #[template(path = "page/root.html")]
 pub struct PageRootTemplate {
-    pub themes: Option<Vec<model::Theme>>,
+    pub themes: Vec<model::Theme>,
+    pub cycles: Vec<model::Cycle>,
+    // Cycle the theme scores are limited to, if any
+    pub cycle_id: Option<i32>,
 }
 
 impl PageRootTemplate {
-    pub fn new(themes: Option<Vec<model::Theme>>) -> PageRootTemplate {
-        PageRootTemplate { themes }
+    pub fn new(
+        themes: Vec<model::Theme>,
+        cycles: Vec<model::Cycle>,
+        cycle_id: Option<i32>,
+    ) -> PageRootTemplate {
+        PageRootTemplate {
+            themes,
+            cycles,
+            cycle_id,
+        }
+    }
+}
+
+// OVERDUE TEMPLATES
+#[derive(askama::Template)]
+#[template(path = "page/overdue.html")]
+pub struct PageOverdueTemplate {
+    pub items: Vec<model::OverdueItem>,
+}
+
+impl PageOverdueTemplate {
+    pub fn new(items: Vec<model::OverdueItem>) -> PageOverdueTemplate {
+        PageOverdueTemplate { items }
+    }
+}
+
+// CYCLE TEMPLATES
+#[derive(askama::Template)]
+#[template(path = "page/cycles.html")]
+pub struct PageCyclesTemplate {
+    pub cycles: Vec<model::Cycle>,
+    pub notice: Option<String>,
+}
+
+impl PageCyclesTemplate {
+    pub fn new(cycles: Vec<model::Cycle>) -> PageCyclesTemplate {
+        PageCyclesTemplate {
+            cycles,
+            notice: None,
+        }
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "table/cycles.html")]
+pub struct TableCyclesTemplate {
+    pub cycles: Vec<model::Cycle>,
+    // Outcome of the last action, e.g. a carry over
+    pub notice: Option<String>,
+}
+
+impl TableCyclesTemplate {
+    pub fn new(cycles: Vec<model::Cycle>, notice: Option<String>) -> TableCyclesTemplate {
+        TableCyclesTemplate { cycles, notice }
+    }
+}
+
+// WORKFLOW TEMPLATES
+/// The states of one kind of item, in workflow order.
+pub struct Workflow {
+    pub entity: model::WorkflowEntity,
+    pub states: Vec<model::WorkflowState>,
+}
+
+#[derive(askama::Template)]
+#[template(path = "page/workflows.html")]
+pub struct PageWorkflowsTemplate {
+    pub workflows: Vec<Workflow>,
+}
+
+impl PageWorkflowsTemplate {
+    pub fn new(workflows: Vec<Workflow>) -> PageWorkflowsTemplate {
+        PageWorkflowsTemplate { workflows }
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "table/workflow.html")]
+pub struct TableWorkflowTemplate {
+    pub entity: model::WorkflowEntity,
+    pub states: Vec<model::WorkflowState>,
+}
+
+impl TableWorkflowTemplate {
+    pub fn new(
+        entity: model::WorkflowEntity,
+        states: Vec<model::WorkflowState>,
+    ) -> TableWorkflowTemplate {
+        TableWorkflowTemplate { entity, states }
     }
 }
 
@@ -24,23 +147,38 @@ impl PageRootTemplate {
 #[template(path = "page/theme.html")]
 pub struct PageThemeTemplate {
     pub title: String,
+    pub description: String,
     pub theme_id: i32,
-    pub theme_status: model::Status,
-    pub objectives: Option<Vec<model::Objective>>,
+    pub state: model::CurrentState,
+    pub blocked_reason: String,
+    pub objectives: Vec<model::Objective>,
+    pub score: Option<f64>,
+    pub cycles: Vec<model::Cycle>,
+    // Cycle the objectives are limited to, if any
+    pub cycle_id: Option<i32>,
+    pub state_changes: Vec<model::StateChange>,
 }
 
 impl PageThemeTemplate {
     pub fn new(
-        title: String,
-        theme_id: i32,
-        theme_status: model::Status,
-        objectives: Option<Vec<model::Objective>>,
+        theme: model::Theme,
+        objectives: Vec<model::Objective>,
+        cycles: Vec<model::Cycle>,
+        cycle_id: Option<i32>,
+        state_changes: Vec<model::StateChange>,
     ) -> PageThemeTemplate {
+        let score = model::theme_score(&objectives);
         PageThemeTemplate {
-            title: title,
-            theme_id: theme_id,
-            theme_status: theme_status,
-            objectives: objectives,
+            title: theme.title,
+            description: theme.description,
+            theme_id: theme.theme_id,
+            state: theme.state,
+            blocked_reason: theme.blocked_reason,
+            objectives,
+            score,
+            cycles,
+            cycle_id,
+            state_changes,
         }
     }
 }
@@ -48,11 +186,11 @@ impl PageThemeTemplate {
 #[derive(askama::Template)]
 #[template(path = "table/themes.html")]
 pub struct TableThemesTemplate {
-    pub themes: Option<Vec<model::Theme>>,
+    pub themes: Vec<model::Theme>,
 }
 
 impl TableThemesTemplate {
-    pub fn new(themes: Option<Vec<model::Theme>>) -> TableThemesTemplate {
+    pub fn new(themes: Vec<model::Theme>) -> TableThemesTemplate {
         TableThemesTemplate { themes }
     }
 }
@@ -73,11 +211,13 @@ impl RowThemeTemplate {
 #[template(path = "form/edit_theme.html")]
 pub struct EditRowThemeTemplate {
     pub theme: model::Theme,
+    pub states: Vec<StateOption>,
 }
 
 impl EditRowThemeTemplate {
-    pub fn new(theme: model::Theme) -> EditRowThemeTemplate {
-        EditRowThemeTemplate { theme }
+    pub fn new(theme: model::Theme, states: Vec<model::WorkflowState>) -> EditRowThemeTemplate {
+        let states = state_options(states, theme.theme_state_id);
+        EditRowThemeTemplate { theme, states }
     }
 }
 
@@ -86,32 +226,40 @@ impl EditRowThemeTemplate {
 #[template(path = "page/objective.html")]
 pub struct PageObjectiveTemplate {
     pub title: String,
+    pub description: String,
     pub objective_id: i32,
     pub theme_id: i32,
     pub theme_title: String,
-    pub keyresults: Option<Vec<model::KeyResult>>,
-    pub initiatives: Option<Vec<model::Initiative>>,
-    pub projects: Option<Vec<model::Project>>,
+    pub cycle: Option<model::Cycle>,
+    pub carried_from: Option<i32>,
+    pub keyresults: Vec<model::KeyResult>,
+    pub initiatives: Vec<model::Initiative>,
+    pub projects: Vec<model::Project>,
+    pub score: Option<f64>,
 }
 
 impl PageObjectiveTemplate {
     pub fn new(
-        title: String,
-        objective_id: i32,
-        theme_id: i32,
+        objective: model::Objective,
         theme_title: String,
-        keyresults: Option<Vec<model::KeyResult>>,
-        initiatives: Option<Vec<model::Initiative>>,
-        projects: Option<Vec<model::Project>>,
+        cycle: Option<model::Cycle>,
+        keyresults: Vec<model::KeyResult>,
+        initiatives: Vec<model::Initiative>,
+        projects: Vec<model::Project>,
     ) -> PageObjectiveTemplate {
+        let score = model::objective_score(&keyresults);
         PageObjectiveTemplate {
-            title,
-            objective_id,
-            theme_id,
+            title: objective.title,
+            description: objective.description,
+            objective_id: objective.objective_id,
+            theme_id: objective.theme_id,
             theme_title,
+            cycle,
+            carried_from: objective.carried_from,
             keyresults,
             initiatives,
             projects,
+            score,
         }
     }
 }
@@ -119,18 +267,21 @@ impl PageObjectiveTemplate {
 #[derive(askama::Template)]
 #[template(path = "table/objectives.html")]
 pub struct TableObjectivesTemplate {
-    pub objectives: Option<Vec<model::Objective>>,
+    pub objectives: Vec<model::Objective>,
     pub theme_id: i32,
+    pub cycle_id: Option<i32>,
 }
 
 impl TableObjectivesTemplate {
     pub fn new(
-        objectives: Option<Vec<model::Objective>>,
+        objectives: Vec<model::Objective>,
         theme_id: i32,
+        cycle_id: Option<i32>,
     ) -> TableObjectivesTemplate {
         TableObjectivesTemplate {
             objectives,
             theme_id,
+            cycle_id,
         }
     }
 }
@@ -151,11 +302,12 @@ impl RowObjectiveTemplate {
 #[template(path = "form/edit_objective.html")]
 pub struct EditRowObjectiveTemplate {
     pub objective: model::Objective,
+    pub cycles: Vec<model::Cycle>,
 }
 
 impl EditRowObjectiveTemplate {
-    pub fn new(objective: model::Objective) -> EditRowObjectiveTemplate {
-        EditRowObjectiveTemplate { objective }
+    pub fn new(objective: model::Objective, cycles: Vec<model::Cycle>) -> EditRowObjectiveTemplate {
+        EditRowObjectiveTemplate { objective, cycles }
     }
 }
 
@@ -163,27 +315,26 @@ impl EditRowObjectiveTemplate {
 #[derive(askama::Template)]
 #[template(path = "page/keyresult.html")]
 pub struct PageKeyResultTemplate {
-    pub title: String,
     pub keyresult_id: i32,
-    pub objective_id: i32,
+    pub keyresult: model::KeyResult,
     pub objective_title: String,
-    pub measurements: Option<Vec<model::Measurement>>,
+    pub measurements: Vec<model::Measurement>,
+    pub chart: Option<String>,
 }
 
 impl PageKeyResultTemplate {
     pub fn new(
-        title: String,
-        objective_id: i32,
-        keyresult_id: i32,
+        keyresult: model::KeyResult,
         objective_title: String,
-        measurements: Option<Vec<model::Measurement>>,
+        measurements: Vec<model::Measurement>,
     ) -> PageKeyResultTemplate {
+        let chart = chart::measurements_svg(&keyresult, &measurements);
         PageKeyResultTemplate {
-            title,
-            keyresult_id,
-            objective_id,
+            keyresult_id: keyresult.keyresult_id,
+            keyresult,
             objective_title,
             measurements,
+            chart,
         }
     }
 }
@@ -191,15 +342,12 @@ impl PageKeyResultTemplate {
 #[derive(askama::Template)]
 #[template(path = "table/keyresults.html")]
 pub struct TableKeyResultsTemplate {
-    pub keyresults: Option<Vec<model::KeyResult>>,
+    pub keyresults: Vec<model::KeyResult>,
     pub objective_id: i32,
 }
 
 impl TableKeyResultsTemplate {
-    pub fn new(
-        keyresults: Option<Vec<model::KeyResult>>,
-        objective_id: i32,
-    ) -> TableKeyResultsTemplate {
+    pub fn new(keyresults: Vec<model::KeyResult>, objective_id: i32) -> TableKeyResultsTemplate {
         TableKeyResultsTemplate {
             keyresults,
             objective_id,
@@ -236,20 +384,26 @@ impl EditRowKeyResultTemplate {
 #[template(path = "page/initiative.html")]
 pub struct PageInitiativeTemplate {
     pub title: String,
+    pub description: String,
     pub objective_id: i32,
     pub objective_title: String,
+    pub state_changes: Vec<model::StateChange>,
 }
 
 impl PageInitiativeTemplate {
     pub fn new(
         title: String,
+        description: String,
         objective_id: i32,
         objective_title: String,
+        state_changes: Vec<model::StateChange>,
     ) -> PageInitiativeTemplate {
         PageInitiativeTemplate {
             title,
+            description,
             objective_id,
             objective_title,
+            state_changes,
         }
     }
 }
@@ -257,15 +411,12 @@ impl PageInitiativeTemplate {
 #[derive(askama::Template)]
 #[template(path = "table/initiatives.html")]
 pub struct TableInitiativesTemplate {
-    pub initiatives: Option<Vec<model::Initiative>>,
+    pub initiatives: Vec<model::Initiative>,
     pub objective_id: i32,
 }
 
 impl TableInitiativesTemplate {
-    pub fn new(
-        initiatives: Option<Vec<model::Initiative>>,
-        objective_id: i32,
-    ) -> TableInitiativesTemplate {
+    pub fn new(initiatives: Vec<model::Initiative>, objective_id: i32) -> TableInitiativesTemplate {
         TableInitiativesTemplate {
             initiatives,
             objective_id,
@@ -289,11 +440,16 @@ impl RowInitiativeTemplate {
 #[template(path = "form/edit_initiative.html")]
 pub struct EditRowInitiativeTemplate {
     pub initiative: model::Initiative,
+    pub states: Vec<StateOption>,
 }
 
 impl EditRowInitiativeTemplate {
-    pub fn new(initiative: model::Initiative) -> EditRowInitiativeTemplate {
-        EditRowInitiativeTemplate { initiative }
+    pub fn new(
+        initiative: model::Initiative,
+        states: Vec<model::WorkflowState>,
+    ) -> EditRowInitiativeTemplate {
+        let states = state_options(states, initiative.initiative_state_id);
+        EditRowInitiativeTemplate { initiative, states }
     }
 }
 
@@ -302,39 +458,232 @@ impl EditRowInitiativeTemplate {
 #[template(path = "page/project.html")]
 pub struct PageProjectTemplate {
     pub title: String,
+    pub description: String,
     pub project_id: i32,
     pub objective_id: i32,
     pub objective_title: String,
-    pub tasks: Option<Vec<model::Task>>,
+    pub tasks: Vec<model::Task>,
+    pub state_changes: Vec<model::StateChange>,
 }
 
 impl PageProjectTemplate {
     pub fn new(
         title: String,
+        description: String,
         project_id: i32,
         objective_id: i32,
         objective_title: String,
-        tasks: Option<Vec<model::Task>>,
+        tasks: Vec<model::Task>,
+        state_changes: Vec<model::StateChange>,
     ) -> PageProjectTemplate {
         PageProjectTemplate {
             title,
+            description,
             project_id,
             objective_id,
             objective_title,
             tasks,
+            state_changes,
+        }
+    }
+}
+
+// BOARD TEMPLATES
+/// A column of a project's board, holding the tasks in one workflow state.
+pub struct BoardColumn {
+    pub state: model::WorkflowState,
+    pub cards: Vec<BoardCard>,
+}
+
+/// A task on a board, along with the states its card can be moved to.
+pub struct BoardCard {
+    pub task: model::Task,
+    pub states: Vec<StateOption>,
+}
+
+// One column per given state, holding the tasks in it. Cards can be moved to
+// any state of the workflow, whether it has a column or not.
+fn columns_of(
+    workflow: &[model::WorkflowState],
+    column_states: &[model::WorkflowState],
+    tasks: Vec<model::Task>,
+) -> Vec<BoardColumn> {
+    let mut columns: Vec<BoardColumn> = column_states
+        .iter()
+        .map(|state| BoardColumn {
+            state: state.clone(),
+            cards: Vec::new(),
+        })
+        .collect();
+    for task in tasks {
+        if let Some(column) = columns
+            .iter_mut()
+            .find(|column| column.state.state_id == task.task_state_id)
+        {
+            column.cards.push(BoardCard {
+                states: state_options(workflow.to_vec(), task.task_state_id),
+                task,
+            });
         }
     }
+    columns
+}
+
+/// One column per state of the task workflow, in workflow order.
+pub fn board_columns(
+    states: Vec<model::WorkflowState>,
+    tasks: Vec<model::Task>,
+) -> Vec<BoardColumn> {
+    columns_of(&states, &states, tasks)
+}
+
+/// The tasks of one project on a cross-project board.
+pub struct Swimlane {
+    pub project_id: i32,
+    pub title: String,
+    pub columns: Vec<BoardColumn>,
+}
+
+/// One swimlane per project, with a column for each state the query lets
+/// through. Projects without matching tasks are left out once filtered.
+pub fn swimlanes(
+    states: &[model::WorkflowState],
+    projects: Vec<model::Project>,
+    tasks: Vec<model::Task>,
+    query: &model::BoardQuery,
+) -> Vec<Swimlane> {
+    let column_states: Vec<model::WorkflowState> = states
+        .iter()
+        .filter(|state| query.state_id.is_none_or(|id| id == state.state_id))
+        .filter(|state| {
+            query
+                .category
+                .is_none_or(|category| category == state.category)
+        })
+        .cloned()
+        .collect();
+    projects
+        .into_iter()
+        .map(|project| {
+            let project_tasks = tasks
+                .iter()
+                .filter(|task| task.project_id == project.project_id)
+                .cloned()
+                .collect();
+            Swimlane {
+                project_id: project.project_id,
+                title: project.title,
+                columns: columns_of(states, &column_states, project_tasks),
+            }
+        })
+        .filter(|lane| {
+            !query.is_filtered() || lane.columns.iter().any(|column| !column.cards.is_empty())
+        })
+        .collect()
+}
+
+#[derive(askama::Template)]
+#[template(path = "page/board.html")]
+pub struct PageBoardTemplate {
+    pub title: String,
+    pub project_id: i32,
+    pub objective_id: i32,
+    pub objective_title: String,
+    pub columns: Vec<BoardColumn>,
+}
+
+impl PageBoardTemplate {
+    pub fn new(
+        title: String,
+        project_id: i32,
+        objective_id: i32,
+        objective_title: String,
+        columns: Vec<BoardColumn>,
+    ) -> PageBoardTemplate {
+        PageBoardTemplate {
+            title,
+            project_id,
+            objective_id,
+            objective_title,
+            columns,
+        }
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "table/board.html")]
+pub struct TableBoardTemplate {
+    pub project_id: i32,
+    pub columns: Vec<BoardColumn>,
+}
+
+impl TableBoardTemplate {
+    pub fn new(project_id: i32, columns: Vec<BoardColumn>) -> TableBoardTemplate {
+        TableBoardTemplate {
+            project_id,
+            columns,
+        }
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "page/swimlanes.html")]
+pub struct PageSwimlanesTemplate {
+    // What the board covers, e.g. "Objective: Grow revenue"
+    pub title: String,
+    pub parent_url: String,
+    pub board_url: String,
+    pub states: Vec<model::WorkflowState>,
+    pub assignees: Vec<String>,
+    pub query: model::BoardQuery,
+    pub lanes: Vec<Swimlane>,
+}
+
+impl PageSwimlanesTemplate {
+    pub fn new(
+        title: String,
+        parent_url: String,
+        board_url: String,
+        states: Vec<model::WorkflowState>,
+        assignees: Vec<String>,
+        query: model::BoardQuery,
+        lanes: Vec<Swimlane>,
+    ) -> PageSwimlanesTemplate {
+        PageSwimlanesTemplate {
+            title,
+            parent_url,
+            board_url,
+            states,
+            assignees,
+            query,
+            lanes,
+        }
+    }
+}
+
+#[derive(askama::Template)]
+#[template(path = "table/swimlanes.html")]
+pub struct TableSwimlanesTemplate {
+    // Page of the board, whose lanes are reloaded from `{board_url}/lanes`
+    pub board_url: String,
+    pub lanes: Vec<Swimlane>,
+}
+
+impl TableSwimlanesTemplate {
+    pub fn new(board_url: String, lanes: Vec<Swimlane>) -> TableSwimlanesTemplate {
+        TableSwimlanesTemplate { board_url, lanes }
+    }
 }
 
 #[derive(askama::Template)]
 #[template(path = "table/projects.html")]
 pub struct TableProjectsTemplate {
-    pub projects: Option<Vec<model::Project>>,
+    pub projects: Vec<model::Project>,
     pub objective_id: i32,
 }
 
 impl TableProjectsTemplate {
-    pub fn new(projects: Option<Vec<model::Project>>, objective_id: i32) -> TableProjectsTemplate {
+    pub fn new(projects: Vec<model::Project>, objective_id: i32) -> TableProjectsTemplate {
         TableProjectsTemplate {
             projects,
             objective_id,
@@ -358,25 +707,52 @@ impl RowProjectTemplate {
 #[template(path = "form/edit_project.html")]
 pub struct EditRowProjectTemplate {
     pub project: model::Project,
+    pub states: Vec<StateOption>,
 }
 
 impl EditRowProjectTemplate {
-    pub fn new(project: model::Project) -> EditRowProjectTemplate {
-        EditRowProjectTemplate { project }
+    pub fn new(
+        project: model::Project,
+        states: Vec<model::WorkflowState>,
+    ) -> EditRowProjectTemplate {
+        let states = state_options(states, project.project_state_id);
+        EditRowProjectTemplate { project, states }
     }
 }
 
 // MEASUREMENT TEMPLATES
+#[derive(askama::Template)]
+#[template(path = "page/measurement.html")]
+pub struct PageMeasurementTemplate {
+    pub measurement: model::Measurement,
+    pub keyresult_title: String,
+    pub revisions: Vec<model::MeasurementRevision>,
+}
+
+impl PageMeasurementTemplate {
+    pub fn new(
+        measurement: model::Measurement,
+        keyresult_title: String,
+        revisions: Vec<model::MeasurementRevision>,
+    ) -> PageMeasurementTemplate {
+        PageMeasurementTemplate {
+            measurement,
+            keyresult_title,
+            revisions,
+        }
+    }
+}
+
 #[derive(askama::Template)]
 #[template(path = "table/measurements.html")]
 pub struct TableMeasurementsTemplate {
-    pub measurements: Option<Vec<model::Measurement>>,
+    pub measurements: Vec<model::Measurement>,
     pub keyresult_id: i32,
 }
 
 impl TableMeasurementsTemplate {
     pub fn new(
-        measurements: Option<Vec<model::Measurement>>,
+        measurements: Vec<model::Measurement>,
         keyresult_id: i32,
     ) -> TableMeasurementsTemplate {
         TableMeasurementsTemplate {
@@ -411,15 +787,67 @@ impl EditRowMeasurementTemplate {
 }
 
 // TASK TEMPLATES
+#[derive(askama::Template)]
+#[template(path = "page/task.html")]
+pub struct PageTaskTemplate {
+    pub title: String,
+    pub task: model::Task,
+    pub project_title: String,
+    pub objective_id: i32,
+    pub objective_title: String,
+    pub theme_id: i32,
+    pub theme_title: String,
+    pub activity: Vec<model::Activity>,
+    pub state_changes: Vec<model::StateChange>,
+}
+
+impl PageTaskTemplate {
+    pub fn new(
+        task: model::Task,
+        project_title: String,
+        objective_id: i32,
+        objective_title: String,
+        theme_id: i32,
+        theme_title: String,
+        state_changes: Vec<model::StateChange>,
+    ) -> PageTaskTemplate {
+        // Newest first
+        let mut activity = Vec::new();
+        if let Some(updated_at) = task.updated_at.filter(|at| Some(*at) != task.created_at) {
+            activity.push(model::Activity {
+                at: updated_at,
+                summary: "Last updated".to_owned(),
+            });
+        }
+        if let Some(created_at) = task.created_at {
+            activity.push(model::Activity {
+                at: created_at,
+                summary: "Created".to_owned(),
+            });
+        }
+        PageTaskTemplate {
+            title: task.title.clone(),
+            task,
+            project_title,
+            objective_id,
+            objective_title,
+            theme_id,
+            theme_title,
+            activity,
+            state_changes,
+        }
+    }
+}
+
 #[derive(askama::Template)]
 #[template(path = "table/tasks.html")]
 pub struct TableTasksTemplate {
-    pub tasks: Option<Vec<model::Task>>,
+    pub tasks: Vec<model::Task>,
     pub project_id: i32,
 }
 
 impl TableTasksTemplate {
-    pub fn new(tasks: Option<Vec<model::Task>>, project_id: i32) -> TableTasksTemplate {
+    pub fn new(tasks: Vec<model::Task>, project_id: i32) -> TableTasksTemplate {
         TableTasksTemplate { tasks, project_id }
     }
 }
@@ -440,11 +868,13 @@ impl RowTaskTemplate {
 #[template(path = "form/edit_task.html")]
 pub struct EditRowTaskTemplate {
     pub task: model::Task,
+    pub states: Vec<StateOption>,
 }
 
 impl EditRowTaskTemplate {
-    pub fn new(task: model::Task) -> EditRowTaskTemplate {
-        EditRowTaskTemplate { task }
+    pub fn new(task: model::Task, states: Vec<model::WorkflowState>) -> EditRowTaskTemplate {
+        let states = state_options(states, task.task_state_id);
+        EditRowTaskTemplate { task, states }
     }
 }
 
@@ -465,6 +895,19 @@ impl ErrorTemplate {
     }
 }
 
+// Inline alert for failed htmx requests
+#[derive(askama::Template)]
+#[template(path = "partial/alert.html")]
+pub struct ErrorAlertTemplate {
+    pub error_message: String,
+}
+
+impl ErrorAlertTemplate {
+    pub fn new(error_message: String) -> ErrorAlertTemplate {
+        ErrorAlertTemplate { error_message }
+    }
+}
+
 // Convert templates into HTML
 pub struct HtmlTemplate<T>(pub T);
 
@@ -0,0 +1,311 @@
+// Requests through the whole router, against the demo fixture kept in memory
+use std::path::Path;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use crate::{store::memory::MemoryStore, validation::TrustForwardedUser};
+
+fn fixture_app(trust: TrustForwardedUser) -> Router {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let store = MemoryStore::load(&root.join("fixtures/demo.json")).unwrap();
+    crate::app(Arc::new(store), &root.join("static"), trust)
+}
+
+struct Client {
+    app: Router,
+}
+
+impl Client {
+    fn new() -> Client {
+        Client {
+            app: fixture_app(TrustForwardedUser(false)),
+        }
+    }
+
+    // Sends a request as an API client would, returning the status and JSON body
+    async fn send(
+        &self,
+        method: Method,
+        uri: &str,
+        headers: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::ACCEPT, "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+        let response = self.app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body = match bytes.is_empty() {
+            true => Value::Null,
+            false => serde_json::from_slice(&bytes).unwrap(),
+        };
+        (status, body)
+    }
+
+    async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        self.send(Method::GET, uri, &[], None).await
+    }
+
+    async fn post(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.send(Method::POST, uri, &[], Some(body)).await
+    }
+
+    async fn put(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.send(Method::PUT, uri, &[], Some(body)).await
+    }
+}
+
+#[tokio::test]
+async fn tasks_can_be_created_read_updated_and_deleted() {
+    let client = Client::new();
+    let (status, task) = client
+        .post(
+            "/api/task",
+            json!({"title": " Review the copy ", "project_id": 1, "due_date": "2026-11-01"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(task["title"], "Review the copy");
+    let uri = format!("/api/task/{}", task["task_id"]);
+
+    let (status, fetched) = client.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched["title"], "Review the copy");
+    assert_eq!(fetched["state"]["title"], "Not Started");
+
+    let (status, updated) = client
+        .put(
+            &uri,
+            json!({"title": "Review the tour copy", "state_id": task["task_state_id"]}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["title"], "Review the tour copy");
+    // Left out of the PUT, so kept
+    assert_eq!(updated["due_date"], "2026-11-01");
+
+    let (status, patched) = client
+        .send(Method::PATCH, &uri, &[], Some(json!({"assignee": "carol"})))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(patched["assignee"], "carol");
+    assert_eq!(patched["title"], "Review the tour copy");
+
+    let (status, _) = client.send(Method::DELETE, &uri, &[], None).await;
+    assert!(status.is_success());
+    let (status, body) = client.get(&uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"], "Task Not Found");
+}
+
+#[tokio::test]
+async fn lists_are_filtered_and_paged() {
+    let client = Client::new();
+    let (status, tasks) = client.get("/api/task?project_id=1&limit=2").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(tasks.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn scores_match_the_tree() {
+    let client = Client::new();
+    let (_, tree) = client.get("/api/theme/1/tree").await;
+    let (_, theme) = client.get("/api/theme/1").await;
+    assert!(theme["score"].is_number());
+    assert_eq!(theme["score"], tree["score"]);
+    let (_, objective) = client.get("/api/objective/1").await;
+    assert!(objective["score"].is_number());
+    let (_, objectives) = client.get("/api/objective").await;
+    assert!(objectives
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|listed| listed == &objective));
+}
+
+#[tokio::test]
+async fn missing_items_are_not_found() {
+    let client = Client::new();
+    for uri in ["/api/theme/99", "/api/objective/99", "/api/keyresult/99"] {
+        let (status, body) = client.get(uri).await;
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+        assert!(body["error"].as_str().unwrap().ends_with("Not Found"));
+    }
+    let (status, _) = client
+        .put("/api/theme/99", json!({"title": "Gone", "state_id": 1}))
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn ids_that_are_not_numbers_are_invalid() {
+    let client = Client::new();
+    let (status, body) = client.get("/api/task/abc").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"]["task_id"], "Invalid task_id: abc");
+}
+
+#[tokio::test]
+async fn items_with_children_are_not_deleted() {
+    let client = Client::new();
+    let (status, body) = client.send(Method::DELETE, "/api/theme/1", &[], None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(
+        body["error"],
+        "This theme still has objectives, delete or move them first"
+    );
+    let (status, body) = client
+        .send(Method::DELETE, "/api/keyresult/1", &[], None)
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(
+        body["error"],
+        "This key result still has measurements, delete them first"
+    );
+    let (status, _) = client.get("/api/theme/1").await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn invalid_payloads_name_their_fields() {
+    let client = Client::new();
+    let (status, body) = client
+        .post("/api/task", json!({"title": " ", "project_id": 99}))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"]["title"], "Title cannot be empty");
+    assert_eq!(body["fields"]["project_id"], "Project 99 does not exist");
+
+    // Values that don't deserialize are reported the same way
+    let (status, body) = client
+        .post("/api/task", json!({"title": "Task", "project_id": "first"}))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["fields"]["project_id"].is_string());
+
+    let (status, body) = client.get("/api/task?limit=lots").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["fields"]["limit"].is_string());
+
+    // Listings say what they can be sorted and filtered by
+    let (status, body) = client.get("/api/task?sort=-colour").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body["fields"]["sort"]
+        .as_str()
+        .unwrap()
+        .starts_with("Cannot sort tasks by colour, only by task_id, title"));
+    let (status, body) = client.get("/api/theme?cycle_id=1").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body["fields"]["cycle_id"],
+        "themes are not planned in cycles"
+    );
+}
+
+#[tokio::test]
+async fn state_changes_the_workflow_forbids_are_rejected() {
+    let client = Client::new();
+    // Task 1 is completed, which can't become blocked
+    let (status, body) = client
+        .put(
+            "/api/task/1",
+            json!({"title": "Write the tour copy", "state_id": 18, "blocked_reason": "Waiting"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body["fields"]["state_id"],
+        "Cannot change state from Completed to Blocked"
+    );
+    let (_, history) = client.get("/api/task/1/history").await;
+    assert_eq!(history, json!([]));
+
+    // But it can go back to in progress
+    let (status, _) = client
+        .put(
+            "/api/task/1",
+            json!({"title": "Write the tour copy", "state_id": 17}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, history) = client.get("/api/task/1/history").await;
+    assert_eq!(history[0]["from_state"], "Completed");
+    assert_eq!(history[0]["to_state"], "In Progress");
+}
+
+#[tokio::test]
+async fn forwarded_user_is_only_recorded_when_trusted() {
+    for (trust, changed_by) in [(false, ""), (true, "alice")] {
+        let client = Client {
+            app: fixture_app(TrustForwardedUser(trust)),
+        };
+        let (status, _) = client
+            .send(
+                Method::PUT,
+                "/api/task/2",
+                &[("X-Forwarded-User", "alice")],
+                Some(json!({"title": "Build the tour overlay", "state_id": 19})),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, history) = client.get("/api/task/2/history").await;
+        assert_eq!(history[0]["changed_by"], changed_by);
+    }
+}
+
+#[tokio::test]
+async fn unfinished_objectives_carry_over_once() {
+    let client = Client::new();
+    // The fixture's objectives are all in its last cycle, so there is nowhere
+    // to carry them yet
+    let (status, body) = client.post("/api/cycle/2/carry_over", json!({})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        body["fields"]["cycle_id"],
+        "There is no cycle after 2026 Q4 to carry over into"
+    );
+
+    let (status, next) = client
+        .post(
+            "/api/cycle",
+            json!({"title": "2027 Q1", "start_date": "2027-01-01", "end_date": "2027-03-31"}),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let (status, carried) = client.post("/api/cycle/2/carry_over", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(carried["to_cycle"]["cycle_id"], next["cycle_id"]);
+    let carried_count = carried["objectives"].as_u64().unwrap();
+    assert!(carried_count > 0);
+
+    let (_, copies) = client
+        .get(&format!("/api/objective?cycle_id={}", next["cycle_id"]))
+        .await;
+    let copies = copies.as_array().unwrap();
+    assert_eq!(copies.len() as u64, carried_count);
+    assert!(copies.iter().all(|copy| copy["carried_from"].is_number()));
+
+    // Carrying over again doesn't copy them twice
+    let (status, again) = client.post("/api/cycle/2/carry_over", json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(again["objectives"], 0);
+}
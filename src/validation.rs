@@ -0,0 +1,1056 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    async_trait,
+    body::{Bytes, HttpBody},
+    extract::{self, path::ErrorKind, rejection::PathRejection, FromRequest, FromRequestParts},
+    http::{request::Parts, Request},
+    BoxError,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::{errors::CustomError, model, store::Store};
+
+pub const MAX_TITLE_LENGTH: usize = 255;
+pub const MAX_UNIT_LENGTH: usize = 32;
+
+/// Messages explaining why fields of a payload were rejected, keyed by field name.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct FieldErrors(BTreeMap<String, String>);
+
+impl FieldErrors {
+    // Only the first problem with each field is reported
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.0
+            .entry(field.to_owned())
+            .or_insert_with(|| message.into());
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = &String> {
+        self.0.values()
+    }
+
+    pub fn into_result(self) -> Result<(), CustomError> {
+        match self.0.is_empty() {
+            true => Ok(()),
+            false => Err(CustomError::Validation(self)),
+        }
+    }
+}
+
+/// JSON body extractor like `axum::Json`, but deserialization failures are
+/// reported as a `CustomError::Validation` against the offending field.
+pub struct Payload<T>(pub T);
+
+#[async_trait]
+impl<T, S, B> FromRequest<S, B> for Payload<T>
+where
+    T: DeserializeOwned,
+    B: HttpBody + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    S: Send + Sync,
+{
+    type Rejection = CustomError;
+
+    async fn from_request(request: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(|_| CustomError::BadRequest)?;
+        let value: Value = serde_json::from_slice(&bytes).map_err(|_| CustomError::BadRequest)?;
+        from_value(value).map(Payload)
+    }
+}
+
+/// Deserializes a JSON value into a payload, naming the field that failed.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T, CustomError> {
+    serde_path_to_error::deserialize(value).map_err(field_error)
+}
+
+// Reports a deserialization failure against the field it happened in
+fn field_error<E: std::fmt::Display>(err: serde_path_to_error::Error<E>) -> CustomError {
+    let path = err.path().to_string();
+    let message = err.into_inner().to_string();
+    let mut errors = FieldErrors::default();
+    // Missing fields are reported against their parent, dig the name out
+    match message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split_once('`'))
+    {
+        Some((field, _)) => errors.add(field, format!("Missing {field}")),
+        None => errors.add(&path, format!("Invalid {path}: {message}")),
+    }
+    CustomError::Validation(errors)
+}
+
+/// Query string extractor like `axum::extract::Query`, but parameters that
+/// don't parse are reported as a `CustomError::Validation` against their name.
+pub struct Params<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Params<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = CustomError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+        serde_path_to_error::deserialize(deserializer)
+            .map(Params)
+            .map_err(field_error)
+    }
+}
+
+/// Path parameter extractor like `axum::extract::Path`, but parameters that
+/// don't parse are reported as a `CustomError::Validation` against their name.
+pub struct Path<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = CustomError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let err = match extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(extract::Path(value)) => return Ok(Path(value)),
+            Err(PathRejection::FailedToDeserializePathParams(err)) => err,
+            // The route and the handler don't agree on the parameters
+            Err(rejection) => {
+                tracing::error!("path rejected: {rejection}");
+                return Err(CustomError::InternalServerError);
+            }
+        };
+        // A lone parameter is parsed without its name, so look it up
+        let (key, value) = match err.into_kind() {
+            ErrorKind::ParseErrorAtKey { key, value, .. } => (key, value),
+            ErrorKind::ParseError { value, .. } => {
+                let params =
+                    extract::Path::<Vec<(String, String)>>::from_request_parts(parts, state)
+                        .await
+                        .map(|extract::Path(params)| params)
+                        .unwrap_or_default();
+                let key = params
+                    .into_iter()
+                    .find(|(_, param)| *param == value)
+                    .map_or_else(|| "path".to_owned(), |(key, _)| key);
+                (key, value)
+            }
+            kind => ("path".to_owned(), kind.to_string()),
+        };
+        let mut errors = FieldErrors::default();
+        errors.add(&key, format!("Invalid {key}: {value}"));
+        Err(CustomError::Validation(errors))
+    }
+}
+
+/// Whether the `X-Forwarded-User` header can be believed, because an
+/// authenticating proxy in front of the app sets it. Off unless configured.
+#[derive(Clone, Copy, Default)]
+pub struct TrustForwardedUser(pub bool);
+
+/// Who is making a change, as named by the `X-Forwarded-User` header an
+/// authenticating proxy in front of the app sets. Empty when nobody is named,
+/// or when the header is not trusted.
+pub struct ChangedBy(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ChangedBy
+where
+    S: Send + Sync,
+{
+    type Rejection = CustomError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let trusted = parts
+            .extensions
+            .get::<TrustForwardedUser>()
+            .copied()
+            .unwrap_or_default();
+        if !trusted.0 {
+            return Ok(ChangedBy(String::new()));
+        }
+        let changed_by = parts
+            .headers
+            .get("X-Forwarded-User")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().chars().take(MAX_TITLE_LENGTH).collect())
+            .unwrap_or_default();
+        Ok(ChangedBy(changed_by))
+    }
+}
+
+// Titles are stored trimmed, and must fit their VARCHAR(255) column
+fn check_title(errors: &mut FieldErrors, title: &mut String) {
+    *title = title.trim().to_owned();
+    if title.is_empty() {
+        errors.add("title", "Title cannot be empty");
+    } else if title.chars().count() > MAX_TITLE_LENGTH {
+        errors.add(
+            "title",
+            format!("Title must be at most {MAX_TITLE_LENGTH} characters"),
+        );
+    }
+}
+
+// Assignees are stored trimmed, and must fit their VARCHAR(255) column
+fn check_assignee(errors: &mut FieldErrors, assignee: &mut String) {
+    *assignee = assignee.trim().to_owned();
+    if assignee.chars().count() > MAX_TITLE_LENGTH {
+        errors.add(
+            "assignee",
+            format!("Assignee must be at most {MAX_TITLE_LENGTH} characters"),
+        );
+    }
+}
+
+fn check_unit(errors: &mut FieldErrors, unit: &mut String) {
+    *unit = unit.trim().to_owned();
+    if unit.chars().count() > MAX_UNIT_LENGTH {
+        errors.add(
+            "unit",
+            format!("Unit must be at most {MAX_UNIT_LENGTH} characters"),
+        );
+    }
+}
+
+fn check_number(errors: &mut FieldErrors, field: &str, label: &str, number: f64) {
+    if !number.is_finite() {
+        errors.add(field, format!("{label} must be a finite number"));
+    }
+}
+
+fn check_keyresult(
+    errors: &mut FieldErrors,
+    title: &mut String,
+    unit: &mut String,
+    direction: model::Direction,
+    baseline: f64,
+    target: f64,
+    weight: f64,
+) {
+    check_title(errors, title);
+    check_unit(errors, unit);
+    check_number(errors, "baseline", "Baseline", baseline);
+    check_number(errors, "target", "Target", target);
+    check_number(errors, "weight", "Weight", weight);
+    if weight < 0.0 {
+        errors.add("weight", "Weight cannot be negative");
+    }
+    // A range's baseline and target are its bounds, in either order
+    match direction {
+        model::Direction::Increase if target <= baseline => {
+            errors.add("target", "Target must be above the baseline to increase")
+        }
+        model::Direction::Decrease if target >= baseline => {
+            errors.add("target", "Target must be below the baseline to decrease")
+        }
+        _ => {}
+    }
+}
+
+fn check_measurement(errors: &mut FieldErrors, title: &mut String, unit: &mut String, value: f64) {
+    check_title(errors, title);
+    check_unit(errors, unit);
+    check_number(errors, "value", "Value", value);
+}
+
+// Sources share the title's VARCHAR(255) limit, but may be empty
+fn check_source(errors: &mut FieldErrors, source: &mut String) {
+    *source = source.trim().to_owned();
+    if source.chars().count() > MAX_TITLE_LENGTH {
+        errors.add(
+            "source",
+            format!("Source must be at most {MAX_TITLE_LENGTH} characters"),
+        );
+    }
+}
+
+fn check_dates(
+    errors: &mut FieldErrors,
+    start_date: Option<chrono::NaiveDate>,
+    due_date: Option<chrono::NaiveDate>,
+) {
+    if let (Some(start_date), Some(due_date)) = (start_date, due_date) {
+        if due_date < start_date {
+            errors.add("due_date", "Due date cannot be before the start date");
+        }
+    }
+}
+
+// How each kind of parent is named in payloads and messages
+struct Parent {
+    id_field: &'static str,
+    label: &'static str,
+}
+
+const CYCLE: Parent = Parent {
+    id_field: "cycle_id",
+    label: "Cycle",
+};
+const THEME: Parent = Parent {
+    id_field: "theme_id",
+    label: "Theme",
+};
+const OBJECTIVE: Parent = Parent {
+    id_field: "objective_id",
+    label: "Objective",
+};
+const KEYRESULT: Parent = Parent {
+    id_field: "keyresult_id",
+    label: "Key result",
+};
+const PROJECT: Parent = Parent {
+    id_field: "project_id",
+    label: "Project",
+};
+
+// Takes the result of looking the parent up, which only fails the check when
+// the parent is missing
+fn check_parent<T>(
+    errors: &mut FieldErrors,
+    parent: &Parent,
+    id: i32,
+    found: Result<T, CustomError>,
+) -> Result<(), CustomError> {
+    match found {
+        Ok(_) => Ok(()),
+        Err(err) if err.is_not_found() => {
+            errors.add(
+                parent.id_field,
+                format!("{} {id} does not exist", parent.label),
+            );
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// The state an item is in, which also catches updates to items that don't exist
+async fn current_state(
+    store: &dyn Store,
+    entity: model::WorkflowEntity,
+    id: i32,
+) -> Result<(i32, String, String), CustomError> {
+    Ok(match entity {
+        model::WorkflowEntity::Theme => {
+            let theme = store.theme(id).await?;
+            (
+                theme.theme_state_id,
+                theme.state.title,
+                theme.blocked_reason,
+            )
+        }
+        model::WorkflowEntity::Initiative => {
+            let initiative = store.initiative(id).await?;
+            (
+                initiative.initiative_state_id,
+                initiative.state.title,
+                initiative.blocked_reason,
+            )
+        }
+        model::WorkflowEntity::Project => {
+            let project = store.project(id).await?;
+            (
+                project.project_state_id,
+                project.state.title,
+                project.blocked_reason,
+            )
+        }
+        model::WorkflowEntity::Task => {
+            let task = store.task(id).await?;
+            (task.task_state_id, task.state.title, task.blocked_reason)
+        }
+    })
+}
+
+// Looks up the state of the item being updated and checks its workflow allows
+// moving to the next state. States that require a reason need one, kept from
+// before when none is given; the rest keep none.
+async fn check_state(
+    store: &dyn Store,
+    errors: &mut FieldErrors,
+    entity: model::WorkflowEntity,
+    id: i32,
+    next_state_id: i32,
+    reason: &mut Option<String>,
+) -> Result<(), CustomError> {
+    let (current_state_id, current_title, current_reason) =
+        current_state(store, entity, id).await?;
+    let reason = reason.get_or_insert(current_reason);
+    let next = match store.workflow_state(next_state_id).await {
+        Ok(next) if next.entity == entity => next,
+        Ok(_) | Err(CustomError::WorkflowStateNotFound) => {
+            errors.add(
+                "state_id",
+                format!("State {next_state_id} is not in the {entity} workflow"),
+            );
+            return Ok(());
+        }
+        Err(err) => return Err(err),
+    };
+    if next_state_id != current_state_id
+        && !store
+            .workflow_state(current_state_id)
+            .await?
+            .can_change_to(next_state_id)
+    {
+        errors.add(
+            "state_id",
+            format!("Cannot change state from {current_title} to {}", next.title),
+        );
+    }
+    *reason = match next.requires_reason {
+        true => reason.trim().to_owned(),
+        false => String::new(),
+    };
+    if next.requires_reason && reason.is_empty() {
+        errors.add(
+            "blocked_reason",
+            format!("Say why this is {}", next.title.to_lowercase()),
+        );
+    }
+    Ok(())
+}
+
+fn check_cycle(
+    errors: &mut FieldErrors,
+    title: &mut String,
+    start_date: chrono::NaiveDate,
+    end_date: chrono::NaiveDate,
+) {
+    check_title(errors, title);
+    if end_date < start_date {
+        errors.add("end_date", "End date cannot be before the start date");
+    }
+}
+
+pub fn create_cycle(create_cycle: &mut model::CreateCycle) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_cycle(
+        &mut errors,
+        &mut create_cycle.title,
+        create_cycle.start_date,
+        create_cycle.end_date,
+    );
+    errors.into_result()
+}
+
+pub fn update_cycle(update_cycle: &mut model::UpdateCycle) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_cycle(
+        &mut errors,
+        &mut update_cycle.title,
+        update_cycle.start_date,
+        update_cycle.end_date,
+    );
+    errors.into_result()
+}
+
+// State titles are unique within a workflow
+async fn check_state_title(
+    store: &dyn Store,
+    errors: &mut FieldErrors,
+    entity: model::WorkflowEntity,
+    title: &str,
+    state_id: Option<i32>,
+) -> Result<(), CustomError> {
+    let taken = store
+        .workflow_states(Some(entity))
+        .await?
+        .iter()
+        .any(|state| state.title == title && Some(state.state_id) != state_id);
+    if taken {
+        errors.add(
+            "title",
+            format!("The {entity} workflow already has a state called {title}"),
+        );
+    }
+    Ok(())
+}
+
+pub async fn create_workflow_state(
+    store: &dyn Store,
+    create_state: &mut model::CreateWorkflowState,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut create_state.title);
+    check_state_title(
+        store,
+        &mut errors,
+        create_state.entity,
+        &create_state.title,
+        None,
+    )
+    .await?;
+    errors.into_result()
+}
+
+pub async fn update_workflow_state(
+    store: &dyn Store,
+    state_id: i32,
+    update_state: &mut model::UpdateWorkflowState,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut update_state.title);
+    if update_state.position < 0 {
+        errors.add("position", "Position cannot be negative");
+    }
+    let entity = store.workflow_state(state_id).await?.entity;
+    check_state_title(
+        store,
+        &mut errors,
+        entity,
+        &update_state.title,
+        Some(state_id),
+    )
+    .await?;
+    if let Some(next_state_ids) = update_state.next_state_ids.as_mut() {
+        next_state_ids.sort_unstable();
+        next_state_ids.dedup();
+        let others: Vec<i32> = store
+            .workflow_states(Some(entity))
+            .await?
+            .into_iter()
+            .map(|state| state.state_id)
+            .filter(|id| *id != state_id)
+            .collect();
+        if !next_state_ids.iter().all(|id| others.contains(id)) {
+            errors.add(
+                "next_state_ids",
+                format!("Next states must be other states of the {entity} workflow"),
+            );
+        }
+    }
+    errors.into_result()
+}
+
+// A workflow keeps at least one state, for new items to start in, and states
+// can only go once no item is in them
+pub async fn remove_workflow_state(store: &dyn Store, state_id: i32) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    let entity = store.workflow_state(state_id).await?.entity;
+    let states = store.workflow_states(Some(entity)).await?.len();
+    // Only the count is needed
+    let in_state = model::ListQuery {
+        state_id: Some(state_id),
+        limit: Some(0),
+        ..Default::default()
+    };
+    let items = match entity {
+        model::WorkflowEntity::Theme => store.themes(&in_state).await?.total,
+        model::WorkflowEntity::Initiative => store.initiatives(&in_state).await?.total,
+        model::WorkflowEntity::Project => store.projects(&in_state).await?.total,
+        model::WorkflowEntity::Task => store.tasks(&in_state).await?.total,
+    };
+    if states <= 1 {
+        errors.add("state_id", format!("The {entity} workflow needs a state"));
+    } else if items == 1 {
+        errors.add("state_id", "An item is in this state, move it first");
+    } else if items > 1 {
+        errors.add(
+            "state_id",
+            format!("{items} items are in this state, move them first"),
+        );
+    }
+    errors.into_result()
+}
+
+pub fn create_theme(create_theme: &mut model::CreateTheme) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut create_theme.title);
+    errors.into_result()
+}
+
+pub async fn update_theme(
+    store: &dyn Store,
+    theme_id: i32,
+    update_theme: &mut model::UpdateTheme,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut update_theme.title);
+    check_state(
+        store,
+        &mut errors,
+        model::WorkflowEntity::Theme,
+        theme_id,
+        update_theme.state_id,
+        &mut update_theme.blocked_reason,
+    )
+    .await?;
+    errors.into_result()
+}
+
+pub async fn create_objective(
+    store: &dyn Store,
+    create_objective: &mut model::CreateObjective,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut create_objective.title);
+    check_parent(
+        &mut errors,
+        &THEME,
+        create_objective.theme_id,
+        store.theme(create_objective.theme_id).await,
+    )?;
+    if let Some(cycle_id) = create_objective.cycle_id {
+        check_parent(&mut errors, &CYCLE, cycle_id, store.cycle(cycle_id).await)?;
+    }
+    errors.into_result()
+}
+
+pub async fn update_objective(
+    store: &dyn Store,
+    update_objective: &mut model::UpdateObjective,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut update_objective.title);
+    if let Some(cycle_id) = update_objective.cycle_id {
+        check_parent(&mut errors, &CYCLE, cycle_id, store.cycle(cycle_id).await)?;
+    }
+    errors.into_result()
+}
+
+pub async fn create_keyresult(
+    store: &dyn Store,
+    create_keyresult: &mut model::CreateKeyResult,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_keyresult(
+        &mut errors,
+        &mut create_keyresult.title,
+        &mut create_keyresult.unit,
+        create_keyresult.direction,
+        create_keyresult.baseline,
+        create_keyresult.target,
+        create_keyresult.weight,
+    );
+    check_parent(
+        &mut errors,
+        &OBJECTIVE,
+        create_keyresult.objective_id,
+        store.objective(create_keyresult.objective_id).await,
+    )?;
+    errors.into_result()
+}
+
+pub fn update_keyresult(update_keyresult: &mut model::UpdateKeyResult) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_keyresult(
+        &mut errors,
+        &mut update_keyresult.title,
+        &mut update_keyresult.unit,
+        update_keyresult.direction,
+        update_keyresult.baseline,
+        update_keyresult.target,
+        update_keyresult.weight,
+    );
+    errors.into_result()
+}
+
+pub async fn create_initiative(
+    store: &dyn Store,
+    create_initiative: &mut model::CreateInitiative,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut create_initiative.title);
+    check_dates(
+        &mut errors,
+        create_initiative.start_date,
+        create_initiative.due_date,
+    );
+    check_parent(
+        &mut errors,
+        &OBJECTIVE,
+        create_initiative.objective_id,
+        store.objective(create_initiative.objective_id).await,
+    )?;
+    errors.into_result()
+}
+
+pub async fn update_initiative(
+    store: &dyn Store,
+    initiative_id: i32,
+    update_initiative: &mut model::UpdateInitiative,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut update_initiative.title);
+    // Dates left out keep their current values
+    let current = store.initiative(initiative_id).await?;
+    check_dates(
+        &mut errors,
+        update_initiative.start_date.unwrap_or(current.start_date),
+        update_initiative.due_date.unwrap_or(current.due_date),
+    );
+    check_state(
+        store,
+        &mut errors,
+        model::WorkflowEntity::Initiative,
+        initiative_id,
+        update_initiative.state_id,
+        &mut update_initiative.blocked_reason,
+    )
+    .await?;
+    errors.into_result()
+}
+
+pub async fn create_project(
+    store: &dyn Store,
+    create_project: &mut model::CreateProject,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut create_project.title);
+    check_dates(
+        &mut errors,
+        create_project.start_date,
+        create_project.due_date,
+    );
+    check_parent(
+        &mut errors,
+        &OBJECTIVE,
+        create_project.objective_id,
+        store.objective(create_project.objective_id).await,
+    )?;
+    errors.into_result()
+}
+
+pub async fn update_project(
+    store: &dyn Store,
+    project_id: i32,
+    update_project: &mut model::UpdateProject,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut update_project.title);
+    // Dates left out keep their current values
+    let current = store.project(project_id).await?;
+    check_dates(
+        &mut errors,
+        update_project.start_date.unwrap_or(current.start_date),
+        update_project.due_date.unwrap_or(current.due_date),
+    );
+    check_state(
+        store,
+        &mut errors,
+        model::WorkflowEntity::Project,
+        project_id,
+        update_project.state_id,
+        &mut update_project.blocked_reason,
+    )
+    .await?;
+    errors.into_result()
+}
+
+pub async fn create_task(
+    store: &dyn Store,
+    create_task: &mut model::CreateTask,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut create_task.title);
+    check_assignee(&mut errors, &mut create_task.assignee);
+    check_dates(&mut errors, create_task.start_date, create_task.due_date);
+    check_parent(
+        &mut errors,
+        &PROJECT,
+        create_task.project_id,
+        store.project(create_task.project_id).await,
+    )?;
+    errors.into_result()
+}
+
+pub async fn update_task(
+    store: &dyn Store,
+    task_id: i32,
+    update_task: &mut model::UpdateTask,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_title(&mut errors, &mut update_task.title);
+    if let Some(assignee) = update_task.assignee.as_mut() {
+        check_assignee(&mut errors, assignee);
+    }
+    // Dates left out keep their current values
+    let current = store.task(task_id).await?;
+    check_dates(
+        &mut errors,
+        update_task.start_date.unwrap_or(current.start_date),
+        update_task.due_date.unwrap_or(current.due_date),
+    );
+    check_state(
+        store,
+        &mut errors,
+        model::WorkflowEntity::Task,
+        task_id,
+        update_task.state_id,
+        &mut update_task.blocked_reason,
+    )
+    .await?;
+    errors.into_result()
+}
+
+pub async fn create_measurement(
+    store: &dyn Store,
+    create_measurement: &mut model::CreateMeasurement,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_measurement(
+        &mut errors,
+        &mut create_measurement.title,
+        &mut create_measurement.unit,
+        create_measurement.value,
+    );
+    check_source(&mut errors, &mut create_measurement.source);
+    check_parent(
+        &mut errors,
+        &KEYRESULT,
+        create_measurement.keyresult_id,
+        store.keyresult(create_measurement.keyresult_id).await,
+    )?;
+    errors.into_result()
+}
+
+pub fn update_measurement(
+    update_measurement: &mut model::UpdateMeasurement,
+) -> Result<(), CustomError> {
+    let mut errors = FieldErrors::default();
+    check_measurement(
+        &mut errors,
+        &mut update_measurement.title,
+        &mut update_measurement.unit,
+        update_measurement.value,
+    );
+    if let Some(source) = update_measurement.source.as_mut() {
+        check_source(&mut errors, source);
+    }
+    errors.into_result()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::store::memory::MemoryStore;
+
+    // The demo fixture's tasks are in the default task workflow
+    const IN_PROGRESS: i32 = 17;
+    const BLOCKED: i32 = 18;
+    const COMPLETED: i32 = 19;
+
+    fn store() -> MemoryStore {
+        MemoryStore::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/demo.json"))
+            .unwrap()
+    }
+
+    fn update(state_id: i32, blocked_reason: Option<&str>) -> model::UpdateTask {
+        model::UpdateTask {
+            title: "Task".to_string(),
+            state_id,
+            blocked_reason: blocked_reason.map(str::to_string),
+            description: None,
+            start_date: None,
+            due_date: None,
+            assignee: None,
+        }
+    }
+
+    // The fields a validation failure names, with their messages
+    fn rejected(result: Result<(), CustomError>) -> BTreeMap<String, String> {
+        match result {
+            Err(CustomError::Validation(errors)) => errors.0,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    fn date(date: &str) -> chrono::NaiveDate {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn titles_are_trimmed_and_limited() {
+        let mut errors = FieldErrors::default();
+        let mut title = "  Ship it ".to_string();
+        check_title(&mut errors, &mut title);
+        assert_eq!(title, "Ship it");
+        assert!(errors.0.is_empty());
+
+        check_title(&mut errors, &mut " ".to_string());
+        assert_eq!(errors.0["title"], "Title cannot be empty");
+
+        let mut errors = FieldErrors::default();
+        check_title(&mut errors, &mut "x".repeat(MAX_TITLE_LENGTH + 1));
+        assert_eq!(errors.0["title"], "Title must be at most 255 characters");
+    }
+
+    #[test]
+    fn due_date_cannot_be_before_start_date() {
+        let mut errors = FieldErrors::default();
+        check_dates(
+            &mut errors,
+            Some(date("2026-10-01")),
+            Some(date("2026-10-01")),
+        );
+        check_dates(&mut errors, None, Some(date("2026-10-01")));
+        assert!(errors.0.is_empty());
+        check_dates(
+            &mut errors,
+            Some(date("2026-10-02")),
+            Some(date("2026-10-01")),
+        );
+        assert_eq!(
+            errors.0["due_date"],
+            "Due date cannot be before the start date"
+        );
+    }
+
+    #[test]
+    fn numbers_must_be_finite() {
+        let mut errors = FieldErrors::default();
+        check_number(&mut errors, "value", "Value", -1.5);
+        assert!(errors.0.is_empty());
+        check_number(&mut errors, "value", "Value", f64::NAN);
+        check_number(&mut errors, "target", "Target", f64::INFINITY);
+        assert_eq!(errors.0["value"], "Value must be a finite number");
+        assert_eq!(errors.0["target"], "Target must be a finite number");
+
+        let mut errors = FieldErrors::default();
+        check_keyresult(
+            &mut errors,
+            &mut "Key result".to_string(),
+            &mut String::new(),
+            model::Direction::Increase,
+            0.0,
+            10.0,
+            -1.0,
+        );
+        assert_eq!(errors.0["weight"], "Weight cannot be negative");
+    }
+
+    #[test]
+    fn targets_must_be_in_the_key_results_direction() {
+        let check = |direction, baseline, target| {
+            let mut errors = FieldErrors::default();
+            check_keyresult(
+                &mut errors,
+                &mut "Key result".to_string(),
+                &mut String::new(),
+                direction,
+                baseline,
+                target,
+                1.0,
+            );
+            errors.0.get("target").cloned()
+        };
+        assert_eq!(check(model::Direction::Increase, 0.0, 10.0), None);
+        assert_eq!(
+            check(model::Direction::Increase, 10.0, 10.0).as_deref(),
+            Some("Target must be above the baseline to increase")
+        );
+        assert_eq!(check(model::Direction::Decrease, 10.0, 0.0), None);
+        assert_eq!(
+            check(model::Direction::Decrease, 0.0, 10.0).as_deref(),
+            Some("Target must be below the baseline to decrease")
+        );
+        assert_eq!(check(model::Direction::Range, 10.0, 0.0), None);
+    }
+
+    #[tokio::test]
+    async fn missing_parents_are_field_errors() {
+        let store = store();
+        let mut errors = FieldErrors::default();
+        check_parent(&mut errors, &THEME, 1, store.theme(1).await).unwrap();
+        assert!(errors.0.is_empty());
+        check_parent(&mut errors, &THEME, 99, store.theme(99).await).unwrap();
+        assert_eq!(errors.0["theme_id"], "Theme 99 does not exist");
+        // Anything else going wrong is not the payload's fault
+        let failed: Result<(), _> = Err(CustomError::InternalServerError);
+        assert!(check_parent(&mut errors, &PROJECT, 1, failed).is_err());
+    }
+
+    #[tokio::test]
+    async fn state_changes_follow_the_workflow() {
+        let store = store();
+        // Task 1 is completed, which can only go back to in progress
+        let fields = rejected(update_task(&store, 1, &mut update(BLOCKED, Some("Waiting"))).await);
+        assert_eq!(
+            fields["state_id"],
+            "Cannot change state from Completed to Blocked"
+        );
+        update_task(&store, 1, &mut update(IN_PROGRESS, None))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn staying_in_the_same_state_is_allowed() {
+        let store = store();
+        update_task(&store, 1, &mut update(COMPLETED, None))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn states_must_be_in_the_items_workflow() {
+        let store = store();
+        // State 1 belongs to the theme workflow
+        let fields = rejected(update_task(&store, 2, &mut update(1, None)).await);
+        assert_eq!(fields["state_id"], "State 1 is not in the Task workflow");
+    }
+
+    #[tokio::test]
+    async fn blocked_needs_a_reason() {
+        let store = store();
+        // Task 2 is in progress, with no reason to keep
+        for reason in [None, Some("  ")] {
+            let fields = rejected(update_task(&store, 2, &mut update(BLOCKED, reason)).await);
+            assert_eq!(fields["blocked_reason"], "Say why this is blocked");
+        }
+        let mut blocked = update(BLOCKED, Some(" Waiting on review "));
+        update_task(&store, 2, &mut blocked).await.unwrap();
+        assert_eq!(blocked.blocked_reason.as_deref(), Some("Waiting on review"));
+    }
+
+    #[tokio::test]
+    async fn reasons_are_kept_while_blocked_and_cleared_after() {
+        let store = store();
+        // Task 3 is already blocked, with a reason
+        let mut still_blocked = update(BLOCKED, None);
+        update_task(&store, 3, &mut still_blocked).await.unwrap();
+        assert_eq!(
+            still_blocked.blocked_reason.as_deref(),
+            Some("Design team is out until next week")
+        );
+        let mut unblocked = update(IN_PROGRESS, Some("Done waiting"));
+        update_task(&store, 3, &mut unblocked).await.unwrap();
+        assert_eq!(unblocked.blocked_reason.as_deref(), Some(""));
+    }
+
+    #[tokio::test]
+    async fn updated_dates_are_checked_against_the_ones_kept() {
+        let store = store();
+        // Task 2 is due 2026-10-16
+        let mut late_start = update(IN_PROGRESS, None);
+        late_start.start_date = Some(Some(date("2026-10-20")));
+        let fields = rejected(update_task(&store, 2, &mut late_start).await);
+        assert_eq!(
+            fields["due_date"],
+            "Due date cannot be before the start date"
+        );
+        late_start.due_date = Some(None);
+        update_task(&store, 2, &mut late_start).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn updating_a_missing_item_is_not_found() {
+        let store = store();
+        assert!(matches!(
+            update_task(&store, 99, &mut update(IN_PROGRESS, None)).await,
+            Err(CustomError::TaskNotFound)
+        ));
+    }
+}